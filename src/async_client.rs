@@ -0,0 +1,609 @@
+// The async twin of the blocking client in client.rs. Only the request sending
+// differs: URLs are generated by the same Urls, ApiUrl, SearchUrl and
+// EndpointUrls traits.
+use crate::async_transport::{AsyncHttpResponse, AsyncTransport};
+use crate::auth::{has_session_cookie, Auth};
+use crate::client::{
+    crop_form, dry_run_error, edit_form, flavors_from, form_request, has_product, json_request,
+    product_images, unselect_form, update_body, upload_request, v3_envelope, validate_public_host,
+    with_headers, ApiUrl, EndpointUrls, FlavorUrls, SearchUrl, Urls, IMAGE_CROP, IMAGE_UNSELECT,
+    IMAGE_UPLOAD, PRODUCT_EDIT,
+};
+use crate::edit::{EditResponse, ProductEdit};
+use crate::error::{validate_barcode, Error};
+use crate::facet::{Facet, FacetResponse};
+use crate::flavor::Flavor;
+use crate::host::Host;
+use crate::image::ProductImages;
+use crate::image_edit::{ImageCrop, ImageResponse, ImageUpload};
+use crate::locale::Locale;
+use crate::output::Output;
+use crate::product::{Product, ProductResponse, SearchResponse};
+use crate::product_v3::{ProductResponseV3, ProductUpdate};
+use crate::ratelimit::{EndpointClass, RateLimiter};
+use crate::retry::RetryPolicy;
+use crate::search::{SearchQueryV0, SearchQueryV2};
+use crate::taxonomy::Taxonomy;
+use crate::transport::{HeaderMap, HttpRequest, Method};
+use crate::types::{Version, V0, V2, V3};
+pub use reqwest::Client as AsyncHttpClient;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use url::{ParseError, Url};
+
+/// The return type of all AsyncOffClient methods.
+pub type AsyncResult = std::result::Result<AsyncHttpResponse, Error>;
+
+/// The async OFF API client.
+///
+/// Supports the same endpoints as [crate::OffClient], each method returning a
+/// future instead of blocking. Requires the `async` feature.
+///
+/// ```no_run
+/// use openfoodfacts as off;
+///
+/// # async fn run() -> Result<(), off::Error> {
+/// let client = off::async_v2().build()?;
+/// let response = client.product("069000019832", None).await?;
+/// assert!(response.status().is_success());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct AsyncOffClient<V> {
+    // The version marker.
+    v: V,
+    // The default locale to use when no locale is given in a method call.
+    locale: Locale,
+    // The server.
+    host: Host,
+    // The retry policy of idempotent requests. No retries if None.
+    retry: Option<RetryPolicy>,
+    // The client-side rate limiter. No limits if None.
+    limiter: Option<RateLimiter>,
+    // The default headers sent on each request.
+    headers: HeaderMap,
+    // The authentication credentials. Optional.
+    auth: Option<Auth>,
+    // The underlying transport.
+    transport: Box<dyn AsyncTransport>,
+    // If true, the write requests are described instead of being sent.
+    dry_run: bool,
+}
+
+impl<V> Version for AsyncOffClient<V>
+where
+    V: Version,
+{
+    fn version(&self) -> &str {
+        self.v.version()
+    }
+}
+
+impl<V> Urls for AsyncOffClient<V>
+where
+    V: Version,
+{
+    fn host(&self) -> &Host {
+        &self.host
+    }
+
+    fn locale(&self) -> &Locale {
+        &self.locale
+    }
+}
+
+impl<V> ApiUrl for AsyncOffClient<V> where V: Version {}
+
+impl<V> EndpointUrls for AsyncOffClient<V> where V: Version {}
+
+impl<V> AsyncOffClient<V>
+where
+    V: Version + Copy,
+{
+    // ------------------------------------------------------------------------
+    // Metadata
+    // ------------------------------------------------------------------------
+
+    /// Gets the given taxonomy. See [crate::OffClient::taxonomy].
+    pub async fn taxonomy(&self, taxonomy: &str) -> AsyncResult {
+        let url = self.taxonomy_url(taxonomy)?;
+        self.get(url).await
+    }
+
+    /// Gets the given taxonomy as a graph. See [crate::OffClient::taxonomy_response].
+    pub async fn taxonomy_response(&self, taxonomy: &str) -> std::result::Result<Taxonomy, Error> {
+        self.taxonomy(taxonomy).await?.json().await
+    }
+
+    /// Gets the given facet. See [crate::OffClient::facet].
+    pub async fn facet(&self, facet: &str, output: Option<Output>) -> AsyncResult {
+        let url = self.facet_url(facet, output)?;
+        self.get(url).await
+    }
+
+    /// Gets the given facet, deserialized. See [crate::OffClient::facet_response].
+    pub async fn facet_response(
+        &self,
+        facet: Facet,
+        output: Option<Output>,
+    ) -> std::result::Result<FacetResponse, Error> {
+        self.facet(facet.plural(), output).await?.json().await
+    }
+
+    /// Gets all the categories. See [crate::OffClient::categories].
+    pub async fn categories(&self, output: Option<Output>) -> AsyncResult {
+        let url = self.categories_url(output)?;
+        self.get(url).await
+    }
+
+    /// Gets the nutrients by country. See [crate::OffClient::nutrients].
+    pub async fn nutrients(&self, output: Option<Output>) -> AsyncResult {
+        let url = self.nutrients_url(output)?;
+        self.get(url).await
+    }
+
+    /// Gets all products for the given facet or category. See
+    /// [crate::OffClient::products_by].
+    pub async fn products_by(&self, what: &str, id: &str, output: Option<Output>) -> AsyncResult {
+        let url = self.products_by_url(what, id, output)?;
+        self.get(url).await
+    }
+
+    /// Gets all products for the given facet or category, deserialized. See
+    /// [crate::OffClient::products_by_response].
+    pub async fn products_by_response<P: DeserializeOwned>(
+        &self,
+        what: &str,
+        id: &str,
+        output: Option<Output>,
+    ) -> std::result::Result<SearchResponse<P>, Error> {
+        self.products_by(what, id, output).await?.json().await
+    }
+
+    // ------------------------------------------------------------------------
+    // Read
+    // ------------------------------------------------------------------------
+
+    /// Gets the nutrition facts of the given product. See [crate::OffClient::product].
+    pub async fn product(&self, barcode: &str, output: Option<Output>) -> AsyncResult {
+        validate_barcode(barcode)?;
+        let url = self.product_url(barcode, output)?;
+        self.get(url).await.map_err(|e| e.for_product(barcode))
+    }
+
+    /// Looks up the given product in the databases of all flavors. See
+    /// [crate::OffClient::find_flavor].
+    pub async fn find_flavor(&self, barcode: &str) -> std::result::Result<Option<Flavor>, Error> {
+        validate_barcode(barcode)?;
+        validate_public_host(self.host())?;
+        for flavor in flavors_from(self.flavor()) {
+            let output = Output::new().fields("code");
+            let url = FlavorUrls::new(self, flavor).product_url(barcode, Some(output))?;
+            match self.get(url).await {
+                Ok(response) => {
+                    if has_product(&serde_json::from_str::<Value>(&response.text().await?)?) {
+                        return Ok(Some(flavor));
+                    }
+                }
+                Err(Error::Status { code: 404, .. }) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the image URLs of the given product. See
+    /// [crate::OffClient::product_images].
+    pub fn product_images<'a>(
+        &self,
+        product: &'a Product,
+    ) -> std::result::Result<ProductImages<'a>, Error> {
+        product_images(self.host(), product)
+    }
+
+    /// Returns true if the client is in dry-run mode. See
+    /// [crate::OffClient::is_dry_run].
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        v: V,
+        locale: Locale,
+        host: Host,
+        retry: Option<RetryPolicy>,
+        limiter: Option<RateLimiter>,
+        headers: HeaderMap,
+        auth: Option<Auth>,
+        transport: Box<dyn AsyncTransport>,
+        dry_run: bool,
+    ) -> Self {
+        Self {
+            v,
+            locale,
+            host,
+            retry,
+            limiter,
+            headers,
+            auth,
+            transport,
+            dry_run,
+        }
+    }
+
+    /// Sends the given product edit. See [crate::OffClient::edit_product].
+    pub async fn edit_product(&self, edit: &ProductEdit) -> AsyncResult {
+        let form = edit_form(edit, self.auth.as_ref())?;
+        self.post_form(self.write_url(PRODUCT_EDIT)?, &form).await
+    }
+
+    /// Sends the given product edit, deserializing the status. See
+    /// [crate::OffClient::edit_product_response].
+    pub async fn edit_product_response(
+        &self,
+        edit: &ProductEdit,
+    ) -> std::result::Result<EditResponse, Error> {
+        self.edit_product(edit).await?.json().await
+    }
+
+    /// Uploads the given image. See [crate::OffClient::upload_image].
+    pub async fn upload_image(&self, upload: &ImageUpload) -> AsyncResult {
+        let url = self.write_url(IMAGE_UPLOAD)?;
+        self.send_write(upload_request(url, upload, self.auth.as_ref())?)
+            .await
+    }
+
+    /// Uploads the given image, deserializing the response. See
+    /// [crate::OffClient::upload_image_response].
+    pub async fn upload_image_response(
+        &self,
+        upload: &ImageUpload,
+    ) -> std::result::Result<ImageResponse, Error> {
+        self.upload_image(upload).await?.json().await
+    }
+
+    /// Selects an uploaded image. See [crate::OffClient::crop_image].
+    pub async fn crop_image(&self, crop: &ImageCrop) -> AsyncResult {
+        let form = crop_form(crop, self.auth.as_ref())?;
+        let form: Vec<(&str, &str)> = form.iter().map(|(n, v)| (*n, v.as_str())).collect();
+        self.post_form(self.write_url(IMAGE_CROP)?, &form).await
+    }
+
+    /// Selects an uploaded image, deserializing the response. See
+    /// [crate::OffClient::crop_image_response].
+    pub async fn crop_image_response(
+        &self,
+        crop: &ImageCrop,
+    ) -> std::result::Result<ImageResponse, Error> {
+        self.crop_image(crop).await?.json().await
+    }
+
+    /// Unselects the given selected image. See [crate::OffClient::unselect_image].
+    pub async fn unselect_image(&self, barcode: &str, imagefield: &str) -> AsyncResult {
+        let form = unselect_form(barcode, imagefield, self.auth.as_ref())?;
+        self.post_form(self.write_url(IMAGE_UNSELECT)?, &form).await
+    }
+
+    /// Unselects the given selected image, deserializing the response. See
+    /// [crate::OffClient::unselect_image_response].
+    pub async fn unselect_image_response(
+        &self,
+        barcode: &str,
+        imagefield: &str,
+    ) -> std::result::Result<ImageResponse, Error> {
+        self.unselect_image(barcode, imagefield).await?.json().await
+    }
+
+    /// Opens a session with the credentials given to the builder. See
+    /// [crate::OffClient::login].
+    pub async fn login(&self) -> std::result::Result<(), Error> {
+        let auth = self
+            .auth
+            .as_ref()
+            .ok_or_else(|| Error::Validation(String::from("no credentials")))?;
+        let response = self
+            .post_form(self.session_url()?, &auth.login_form())
+            .await?;
+        if has_session_cookie(response.headers()) {
+            Ok(())
+        } else {
+            Err(Error::Authentication(format!(
+                "no session opened for user '{}'",
+                auth.username
+            )))
+        }
+    }
+
+    // Sends a POST request with the given form, once. Unsuccessful HTTP
+    // statuses are returned as errors.
+    async fn post_form(&self, url: Url, form: &[(&str, &str)]) -> AsyncResult {
+        self.send_write(form_request(url, form)).await
+    }
+
+    // Sends a write request once, after waiting for the write quota of the
+    // rate limits, if any. In dry-run mode, returns the description of the
+    // request instead.
+    async fn send_write(&self, request: HttpRequest) -> AsyncResult {
+        if self.dry_run {
+            return Err(dry_run_error(with_headers(request, &self.headers)));
+        }
+        if let Some(limiter) = &self.limiter {
+            let delay = limiter.reserve(EndpointClass::Write)?;
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+        }
+        self.send(request).await
+    }
+
+    // Builds and sends a GET request. Failed requests are retried according to
+    // the client's retry policy. Each attempt waits for the client's rate
+    // limits, if any.
+    async fn get(&self, url: Url) -> AsyncResult {
+        let class = EndpointClass::of_read(&url);
+        let mut attempt = 1;
+        loop {
+            if let Some(limiter) = &self.limiter {
+                let delay = limiter.reserve(class)?;
+                if !delay.is_zero() {
+                    tokio::time::sleep(delay).await;
+                }
+            }
+            let result = self.send(HttpRequest::new(Method::GET, url.clone())).await;
+            let delay = match result {
+                Err(ref e) => self.retry.as_ref().and_then(|r| r.delay(attempt, e)),
+                Ok(_) => None,
+            };
+            match delay {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => return result,
+            }
+            attempt += 1;
+        }
+    }
+
+    // Sends a request once, with the default headers. Unsuccessful HTTP
+    // statuses are returned as errors.
+    async fn send(&self, request: HttpRequest) -> AsyncResult {
+        let request = with_headers(request, &self.headers);
+        check_status(self.transport.send(request).await?).await
+    }
+}
+
+// Returns the given response if its status is successful, otherwise the
+// matching error.
+async fn check_status(response: AsyncHttpResponse) -> AsyncResult {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let retry_after = response
+        .headers()
+        .get(http::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
+    let body = response.text().await.unwrap_or_default();
+    Err(Error::from_status(
+        status.as_u16(),
+        retry_after.as_deref(),
+        body,
+    ))
+}
+
+impl AsyncOffClient<V0> {
+    /// Gets the given product, deserialized. See
+    /// [crate::OffClient::product_response].
+    pub async fn product_response(
+        &self,
+        barcode: &str,
+        output: Option<Output>,
+    ) -> std::result::Result<ProductResponse, Error> {
+        self.product(barcode, output).await?.json().await
+    }
+
+    /// Returns the query builder for API V0.
+    pub fn query(&self) -> SearchQueryV0 {
+        SearchQueryV0::new()
+    }
+
+    /// Sends the given search query.
+    pub async fn search(&self, query: SearchQueryV0, output: Option<Output>) -> AsyncResult {
+        let url = SearchQueryV0::search_url(query, self, output)?;
+        self.get(url).await
+    }
+
+    /// Sends the given search query and deserializes the response.
+    pub async fn search_response<P: DeserializeOwned>(
+        &self,
+        query: SearchQueryV0,
+        output: Option<Output>,
+    ) -> std::result::Result<SearchResponse<P>, Error> {
+        self.search(query, output).await?.json().await
+    }
+}
+
+impl SearchUrl for AsyncOffClient<V0> {
+    /// Returns the API V0 search URL.
+    ///
+    /// `https://{locale}.openfoodfacts.org/cgi/search.pl`
+    fn search_url(&self, locale: Option<&Locale>) -> std::result::Result<Url, ParseError> {
+        let cgi_url = self.cgi_url(locale)?;
+        cgi_url.join("search.pl")
+    }
+}
+
+impl AsyncOffClient<V2> {
+    /// Gets the given product, deserialized. See
+    /// [crate::OffClient::product_response].
+    pub async fn product_response(
+        &self,
+        barcode: &str,
+        output: Option<Output>,
+    ) -> std::result::Result<ProductResponse, Error> {
+        self.product(barcode, output).await?.json().await
+    }
+
+    /// Returns the query builder for API V2.
+    pub fn query(&self) -> SearchQueryV2 {
+        SearchQueryV2::new()
+    }
+
+    /// Sends the search query.
+    pub async fn search(&self, query: SearchQueryV2, output: Option<Output>) -> AsyncResult {
+        let url = SearchQueryV2::search_url(query, self, output)?;
+        self.get(url).await
+    }
+
+    /// Sends the search query and deserializes the response.
+    pub async fn search_response<P: DeserializeOwned>(
+        &self,
+        query: SearchQueryV2,
+        output: Option<Output>,
+    ) -> std::result::Result<SearchResponse<P>, Error> {
+        self.search(query, output).await?.json().await
+    }
+
+    /// Gets the products given in the `barcodes` list as a string of comma-separated
+    /// product barcodes. See [crate::OffClient::products].
+    pub async fn products(&self, barcodes: &str, output: Option<Output>) -> AsyncResult {
+        for barcode in barcodes.split(',') {
+            validate_barcode(barcode)?;
+        }
+        let url = self.products_url(barcodes, output)?;
+        self.get(url).await
+    }
+
+    /// Gets the given products, deserialized. See
+    /// [crate::OffClient::products_response].
+    pub async fn products_response<P: DeserializeOwned>(
+        &self,
+        barcodes: &str,
+        output: Option<Output>,
+    ) -> std::result::Result<SearchResponse<P>, Error> {
+        self.products(barcodes, output).await?.json().await
+    }
+}
+
+impl SearchUrl for AsyncOffClient<V2> {
+    /// Returns the API V2 search URL.
+    ///
+    /// `https://{locale}.openfoodfacts.org/api/v2/search`
+    fn search_url(&self, locale: Option<&Locale>) -> std::result::Result<Url, ParseError> {
+        let api_url = self.api_url(locale)?;
+        api_url.join("search")
+    }
+}
+
+impl AsyncOffClient<V3> {
+    /// Gets the given product in the API V3 envelope. See
+    /// [crate::OffClient::product_response].
+    pub async fn product_response<P: DeserializeOwned>(
+        &self,
+        barcode: &str,
+        output: Option<Output>,
+    ) -> std::result::Result<ProductResponseV3<P>, Error> {
+        match self.product(barcode, output).await {
+            Ok(response) => response.json().await,
+            Err(e) => v3_envelope(e),
+        }
+    }
+
+    /// Sends the given product update. See [crate::OffClient::update_product].
+    pub async fn update_product(&self, update: &ProductUpdate) -> AsyncResult {
+        let body = update_body(update, self.auth.as_ref())?;
+        let url = self.product_url(update.code(), None)?;
+        self.send_write(json_request(Method::PATCH, url, &body))
+            .await
+    }
+
+    /// Sends the given product update and returns the API V3 envelope. See
+    /// [crate::OffClient::update_product_response].
+    pub async fn update_product_response<P: DeserializeOwned>(
+        &self,
+        update: &ProductUpdate,
+    ) -> std::result::Result<ProductResponseV3<P>, Error> {
+        match self.update_product(update).await {
+            Ok(response) => response.json().await,
+            Err(e) => v3_envelope(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_async_client {
+    use super::*;
+
+    #[test]
+    fn version() {
+        let client_v0 = crate::async_v0().build().unwrap();
+        assert_eq!(client_v0.version(), "v0");
+
+        let client_v2 = crate::async_v2().build().unwrap();
+        assert_eq!(client_v2.version(), "v2");
+
+        let client_v3 = crate::async_v3().build().unwrap();
+        assert_eq!(client_v3.version(), "v3");
+    }
+
+    // The async and blocking clients must target identical endpoints.
+    #[test]
+    fn same_urls_as_blocking() {
+        let locale = || Output::new().locale(Locale::new("fr", None));
+        let blocking = crate::v2().build().unwrap();
+        let client = crate::async_v2().build().unwrap();
+        assert_eq!(
+            client.taxonomy_url("nova_groups"),
+            blocking.taxonomy_url("nova_groups")
+        );
+        assert_eq!(
+            client.facet_url("brands", Some(locale().page(2))),
+            blocking.facet_url("brands", Some(locale().page(2)))
+        );
+        assert_eq!(
+            client.categories_url(Some(locale())),
+            blocking.categories_url(Some(locale()))
+        );
+        assert_eq!(
+            client.nutrients_url(Some(locale())),
+            blocking.nutrients_url(Some(locale()))
+        );
+        assert_eq!(
+            client.products_by_url("category", "cheeses", Some(locale().fields("url"))),
+            blocking.products_by_url("category", "cheeses", Some(locale().fields("url")))
+        );
+        assert_eq!(
+            client.product_url("069000019832", None),
+            blocking.product_url("069000019832", None)
+        );
+        assert_eq!(
+            client.products_url("069000019832,3850102123681", None),
+            blocking.products_url("069000019832,3850102123681", None)
+        );
+    }
+
+    #[test]
+    fn search_url_v0() {
+        let client = crate::async_v0().build().unwrap();
+        assert_eq!(
+            client
+                .search_url(Some(&Locale::new("gr", None)))
+                .unwrap()
+                .as_str(),
+            "https://gr.openfoodfacts.org/cgi/search.pl"
+        );
+    }
+
+    #[test]
+    fn search_url_v2() {
+        let client = crate::async_v2().build().unwrap();
+        assert_eq!(
+            client
+                .search_url(Some(&Locale::new("gr", None)))
+                .unwrap()
+                .as_str(),
+            "https://gr.openfoodfacts.org/api/v2/search"
+        );
+    }
+}
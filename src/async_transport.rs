@@ -0,0 +1,138 @@
+// The async twin of the blocking transport in transport.rs. The requests and
+// errors are shared, only the responses differ: their body is a stream.
+use crate::error::Error;
+use crate::transport::{HttpRequest, TransportError};
+use futures_util::stream::{Stream, StreamExt};
+use http::{HeaderMap, StatusCode};
+use serde::de::DeserializeOwned;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use url::Url;
+
+/// A boxed future, as returned by [AsyncTransport::send].
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// The body stream of an [AsyncHttpResponse].
+pub type BodyStream = Pin<Box<dyn Stream<Item = Result<Vec<u8>, TransportError>> + Send>>;
+
+/// An HTTP response returned by an async transport. The body is a stream of
+/// chunks.
+pub struct AsyncHttpResponse {
+    url: Url,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: BodyStream,
+}
+
+impl AsyncHttpResponse {
+    /// Creates a response with the given final URL (after redirects), status,
+    /// headers and body stream.
+    pub fn new<S>(url: Url, status: StatusCode, headers: HeaderMap, body: S) -> Self
+    where
+        S: Stream<Item = Result<Vec<u8>, TransportError>> + Send + 'static,
+    {
+        Self {
+            url,
+            status,
+            headers,
+            body: Box::pin(body),
+        }
+    }
+
+    /// Returns the final URL of the response.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Returns the HTTP status.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns the response headers.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Returns the body stream.
+    pub fn into_stream(self) -> BodyStream {
+        self.body
+    }
+
+    /// Reads the whole body.
+    pub async fn bytes(mut self) -> Result<Vec<u8>, Error> {
+        let mut body = Vec::new();
+        while let Some(chunk) = self.body.next().await {
+            body.extend(chunk?);
+        }
+        Ok(body)
+    }
+
+    /// Reads the whole body as text. Invalid UTF-8 sequences are replaced.
+    pub async fn text(self) -> Result<String, Error> {
+        Ok(String::from_utf8_lossy(&self.bytes().await?).into_owned())
+    }
+
+    /// Reads and deserializes the JSON body.
+    pub async fn json<T: DeserializeOwned>(self) -> Result<T, Error> {
+        Ok(serde_json::from_slice(&self.bytes().await?)?)
+    }
+}
+
+impl Debug for AsyncHttpResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncHttpResponse")
+            .field("url", &self.url)
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The HTTP transport of the async client. See [crate::Transport].
+pub trait AsyncTransport: Debug + Send + Sync {
+    /// Sends the request and returns the response.
+    fn send(
+        &self,
+        request: HttpRequest,
+    ) -> BoxFuture<'_, Result<AsyncHttpResponse, TransportError>>;
+}
+
+/// The default transport of the async client, a reqwest client.
+#[derive(Debug, Clone)]
+pub struct AsyncReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl AsyncReqwestTransport {
+    /// Creates a transport sending the requests with the given client.
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+impl AsyncTransport for AsyncReqwestTransport {
+    fn send(
+        &self,
+        request: HttpRequest,
+    ) -> BoxFuture<'_, Result<AsyncHttpResponse, TransportError>> {
+        Box::pin(async move {
+            let mut rb = self
+                .client
+                .request(request.method, request.url)
+                .headers(request.headers);
+            if !request.body.is_empty() {
+                rb = rb.body(request.body);
+            }
+            let response = rb.send().await?;
+            let url = response.url().clone();
+            let status = response.status();
+            let headers = response.headers().clone();
+            let body = response
+                .bytes_stream()
+                .map(|chunk| chunk.map(|c| c.to_vec()).map_err(TransportError::from));
+            Ok(AsyncHttpResponse::new(url, status, headers, body))
+        })
+    }
+}
@@ -0,0 +1,101 @@
+use crate::error::Error;
+use http::header::{HeaderMap, HeaderValue, SET_COOKIE};
+use std::fmt::{self, Debug, Formatter};
+
+/// The name of the session cookie set by `cgi/session.pl`.
+const SESSION_COOKIE: &str = "session";
+
+/// Returns true if the given response headers set a non-empty session cookie.
+pub(crate) fn has_session_cookie(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .filter_map(|cookie| cookie.split(';').next()?.split_once('='))
+        .any(|(name, value)| name.trim() == SESSION_COOKIE && !value.trim().is_empty())
+}
+
+/// Authentication credentials. The password is redacted in the Debug output.
+#[derive(Clone, PartialEq)]
+pub(crate) struct Auth {
+    pub(crate) username: String,
+    pub(crate) password: String,
+}
+
+impl Auth {
+    pub(crate) fn new(username: &str, password: &str) -> Self {
+        Self {
+            username: username.to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    /// Returns the value of the Basic `Authorization` header, marked as
+    /// sensitive.
+    pub(crate) fn basic_header(&self) -> Result<HeaderValue, Error> {
+        let credentials = base64::encode(format!("{}:{}", self.username, self.password));
+        let mut value = HeaderValue::from_str(&format!("Basic {}", credentials))
+            .map_err(|_| Error::Validation(String::from("invalid credentials")))?;
+        value.set_sensitive(true);
+        Ok(value)
+    }
+
+    /// Returns the form fields authenticating a write request.
+    pub(crate) fn credentials(&self) -> [(&'static str, &str); 2] {
+        [("user_id", &self.username), ("password", &self.password)]
+    }
+
+    /// Returns the form fields of a `cgi/session.pl` login request.
+    pub(crate) fn login_form(&self) -> [(&str, &str); 3] {
+        [
+            ("user_id", &self.username),
+            ("password", &self.password),
+            (".submit", "Sign-in"),
+        ]
+    }
+}
+
+impl Debug for Auth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Auth")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic_header() {
+        let auth = Auth::new("off", "off");
+        let value = auth.basic_header().unwrap();
+        assert_eq!(value.to_str().unwrap(), "Basic b2ZmOm9mZg==");
+        assert!(value.is_sensitive());
+
+        // Non-ASCII credentials do not panic.
+        let auth = Auth::new("usér", "pa:ss\u{e9}\n");
+        assert!(auth.basic_header().is_ok());
+    }
+
+    #[test]
+    fn session_cookie() {
+        let mut headers = HeaderMap::new();
+        assert!(!has_session_cookie(&headers));
+        headers.append(SET_COOKIE, HeaderValue::from_static("lang=fr; Path=/"));
+        headers.append(SET_COOKIE, HeaderValue::from_static("session=; Path=/"));
+        assert!(!has_session_cookie(&headers));
+        headers.append(SET_COOKIE, HeaderValue::from_static("session=abc; Path=/"));
+        assert!(has_session_cookie(&headers));
+    }
+
+    #[test]
+    fn redacted_debug() {
+        let auth = Auth::new("user", "secret");
+        let debug = format!("{:?}", auth);
+        assert!(debug.contains("user"));
+        assert!(!debug.contains("secret"));
+    }
+}
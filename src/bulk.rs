@@ -0,0 +1,733 @@
+// Bulk edits: rows of barcode + field changes read from CSV or JSONL, applied
+// with the write API one row at a time. The progress is saved in a checkpoint
+// file to resume interrupted runs, and the result of each row is appended to a
+// CSV report.
+//
+// CSV input: a header row with a `code` (or `barcode`) column, the other
+// columns being the product fields to set. Empty cells are left unchanged.
+//
+//     code,brands,quantity
+//     3850102123681,Kraš,100 g
+//
+// JSONL input: one object per line, with the `code` and the fields to set.
+//
+//     {"code": "3850102123681", "brands": "Kraš", "quantity": "100 g"}
+use crate::client::OffClient;
+use crate::dry_run::PreparedRequest;
+use crate::edit::ProductEdit;
+use crate::error::Error;
+use crate::types::Version;
+use serde_json::{Map, Value};
+use std::fmt::{self, Display, Formatter};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// The header of the report files.
+const REPORT_HEADER: &str = "line,code,outcome,fields,message";
+
+/// A row of a bulk edit: the product barcode and the fields to set.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BulkRow {
+    /// The line number of the row in the input, starting at 1.
+    pub line: usize,
+    /// The barcode of the product.
+    pub code: String,
+    /// The product fields to set, as sent in a [ProductEdit].
+    pub fields: Vec<(String, String)>,
+}
+
+impl BulkRow {
+    /// Returns the fields of the row whose value differs from the given
+    /// product. The nutrient fields are compared with the product nutriments
+    /// as entered, not normalized to grams: `nutriment_fat` with `fat_value`,
+    /// `nutriment_fat_unit` with `fat_unit`.
+    pub fn changes(&self, product: &Map<String, Value>) -> Vec<(&str, &str)> {
+        self.fields
+            .iter()
+            .filter(|(name, value)| {
+                let current = match name.strip_prefix("nutriment_") {
+                    Some(id) => {
+                        let key = if id.ends_with("_unit") {
+                            id.to_string()
+                        } else {
+                            format!("{}_value", id)
+                        };
+                        product.get("nutriments").and_then(|n| n.get(&key))
+                    }
+                    None => product.get(name.as_str()),
+                };
+                !same_value(current, value)
+            })
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect()
+    }
+}
+
+/// The outcome of a row of a bulk edit.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RowOutcome {
+    /// The edit was saved.
+    Saved,
+    /// The product already has the values of the row: no edit was sent.
+    Unchanged,
+    /// The server did not save the edit. Holds its status message.
+    NotSaved(String),
+    /// The row could not be applied, i.e. for an invalid barcode. Holds the
+    /// error message.
+    Failed(String),
+    /// The client is in dry-run mode: the edit was prepared, not sent.
+    DryRun(Box<PreparedRequest>),
+}
+
+impl RowOutcome {
+    /// The name of the outcome in the reports.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Saved => "saved",
+            Self::Unchanged => "unchanged",
+            Self::NotSaved(_) => "not_saved",
+            Self::Failed(_) => "failed",
+            Self::DryRun(_) => "dry_run",
+        }
+    }
+}
+
+impl Display for RowOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotSaved(message) | Self::Failed(message) => {
+                write!(f, "{}: {}", self.name(), message)
+            }
+            _ => write!(f, "{}", self.name()),
+        }
+    }
+}
+
+/// The result of a row of a bulk edit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowResult {
+    /// The line number of the row in the input.
+    pub line: usize,
+    /// The barcode of the product.
+    pub code: String,
+    /// What became of the row.
+    pub outcome: RowOutcome,
+    /// The names of the fields sent, i.e. the fields of the row that differ
+    /// from the current product.
+    pub fields: Vec<String>,
+}
+
+impl RowResult {
+    // Returns the line of the CSV report.
+    fn to_csv(&self) -> String {
+        let message = match &self.outcome {
+            RowOutcome::NotSaved(message) | RowOutcome::Failed(message) => message.as_str(),
+            _ => "",
+        };
+        [
+            &self.line.to_string(),
+            &self.code,
+            self.outcome.name(),
+            &self.fields.join(" "),
+            message,
+        ]
+        .iter()
+        .map(|cell| csv_cell(cell))
+        .collect::<Vec<_>>()
+        .join(",")
+    }
+}
+
+/// The report of a bulk edit run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BulkReport {
+    /// The number of rows skipped because a previous run processed them, as
+    /// saved in the checkpoint file.
+    pub skipped: usize,
+    /// The results of the rows processed by the run, in order.
+    pub rows: Vec<RowResult>,
+}
+
+impl BulkReport {
+    /// Returns the number of rows with the given outcome name, i.e. `saved`.
+    /// See [RowOutcome::name].
+    pub fn count(&self, outcome: &str) -> usize {
+        self.rows
+            .iter()
+            .filter(|r| r.outcome.name() == outcome)
+            .count()
+    }
+}
+
+/// A bulk edit: rows of product changes applied with the write API. See
+/// [OffClient::bulk_edit].
+///
+/// ```
+/// use openfoodfacts::BulkEdit;
+///
+/// let bulk = BulkEdit::from_csv("code,brands,quantity\n3850102123681,Kraš,\n")
+///     .unwrap()
+///     .checkpoint("fixes.checkpoint")
+///     .report("fixes.report.csv")
+///     .comment("Brand fixes");
+/// let row = &bulk.rows()[0];
+/// assert_eq!(row.line, 2);
+/// assert_eq!(row.fields, vec![(String::from("brands"), String::from("Kraš"))]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BulkEdit {
+    rows: Vec<BulkRow>,
+    // The file saving the number of rows processed. Optional.
+    checkpoint: Option<PathBuf>,
+    // The CSV file the row results are appended to. Optional.
+    report: Option<PathBuf>,
+    // The comment of the edits. Optional.
+    comment: Option<String>,
+    // The name and version of the application sending the edits. Optional.
+    app: Option<(String, String)>,
+}
+
+impl BulkEdit {
+    /// Creates a bulk edit of the given rows.
+    pub fn new(rows: Vec<BulkRow>) -> Self {
+        Self {
+            rows,
+            ..Self::default()
+        }
+    }
+
+    /// Reads the rows of a CSV text. The first line is the header.
+    ///
+    /// Returns [Error::Syntax] if a row is invalid or the header has no `code`
+    /// column.
+    pub fn from_csv(text: &str) -> Result<Self, Error> {
+        let mut records = parse_csv(text)?.into_iter();
+        let (_, header) = records.next().ok_or_else(|| syntax(1, "no header"))?;
+        let code = header
+            .iter()
+            .position(|name| name == "code" || name == "barcode")
+            .ok_or_else(|| syntax(1, "no code column"))?;
+        let mut rows = Vec::new();
+        for (line, cells) in records {
+            if cells.len() != header.len() {
+                return Err(syntax(
+                    line,
+                    &format!("{} cells, {} expected", cells.len(), header.len()),
+                ));
+            }
+            let fields = header
+                .iter()
+                .zip(cells.iter())
+                .enumerate()
+                .filter(|(i, (_, value))| *i != code && !value.is_empty())
+                .map(|(_, (name, value))| (name.clone(), value.clone()))
+                .collect();
+            rows.push(BulkRow {
+                line,
+                code: cells[code].trim().to_string(),
+                fields,
+            });
+        }
+        Ok(Self::new(rows))
+    }
+
+    /// Reads the rows of a JSONL text: one JSON object per line. Blank lines are
+    /// skipped, null values are left unchanged. The fields of a row are sorted
+    /// by name.
+    ///
+    /// Returns [Error::Syntax] if a line is not an object with a `code`, or has
+    /// an array or object value.
+    pub fn from_jsonl(text: &str) -> Result<Self, Error> {
+        let mut rows = Vec::new();
+        for (i, text) in text.lines().enumerate() {
+            let line = i + 1;
+            if text.trim().is_empty() {
+                continue;
+            }
+            let object = match serde_json::from_str(text) {
+                Ok(Value::Object(object)) => object,
+                Ok(_) => return Err(syntax(line, "not an object")),
+                Err(e) => return Err(syntax(line, &e.to_string())),
+            };
+            let mut row = BulkRow {
+                line,
+                ..BulkRow::default()
+            };
+            for (name, value) in object {
+                let value = match value {
+                    Value::Null => continue,
+                    Value::String(s) => s,
+                    Value::Number(_) | Value::Bool(_) => value.to_string(),
+                    _ => return Err(syntax(line, &format!("invalid value of '{}'", name))),
+                };
+                match name.as_str() {
+                    "code" | "barcode" => row.code = value,
+                    _ => row.fields.push((name, value)),
+                }
+            }
+            if row.code.is_empty() {
+                return Err(syntax(line, "no code"));
+            }
+            rows.push(row);
+        }
+        Ok(Self::new(rows))
+    }
+
+    /// Reads the rows of the given file: JSONL if its extension is `jsonl` or
+    /// `ndjson`, CSV otherwise.
+    ///
+    /// Returns [Error::Io] if the file cannot be read.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("jsonl") | Some("ndjson") => Self::from_jsonl(&text),
+            _ => Self::from_csv(&text),
+        }
+    }
+
+    /// Sets the checkpoint file. The number of rows processed is saved in the
+    /// file after each row, and the rows it counts are skipped by the next
+    /// runs. Not saved in dry-run mode.
+    pub fn checkpoint<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.checkpoint = Some(path.into());
+        self
+    }
+
+    /// Sets the report file. The result of each row is appended to the file, in
+    /// CSV with the columns `line,code,outcome,fields,message`.
+    pub fn report<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.report = Some(path.into());
+        self
+    }
+
+    /// Sets the comment of the edits, shown in the product history.
+    pub fn comment(mut self, comment: &str) -> Self {
+        self.comment = Some(comment.to_string());
+        self
+    }
+
+    /// Sets the name and version of the application sending the edits.
+    pub fn app(mut self, name: &str, version: &str) -> Self {
+        self.app = Some((name.to_string(), version.to_string()));
+        self
+    }
+
+    /// Returns the rows.
+    pub fn rows(&self) -> &[BulkRow] {
+        &self.rows
+    }
+
+    /// Applies the rows with the given client. See [OffClient::bulk_edit].
+    pub(crate) fn run<V: Version + Copy>(
+        &self,
+        client: &OffClient<V>,
+    ) -> Result<BulkReport, Error> {
+        let skipped = match self.checkpoint {
+            Some(ref path) => read_checkpoint(path, &self.rows)?,
+            None => 0,
+        };
+        let mut report = BulkReport {
+            skipped,
+            rows: Vec::new(),
+        };
+        for (i, row) in self.rows.iter().enumerate().skip(skipped) {
+            let result = self.apply(client, row)?;
+            if let Some(ref path) = self.report {
+                append_report(path, &result)?;
+            }
+            if let (Some(path), false) = (&self.checkpoint, client.is_dry_run()) {
+                write_checkpoint(path, i + 1, &row.code)?;
+            }
+            report.rows.push(result);
+        }
+        Ok(report)
+    }
+
+    // Applies a row. The errors stopping the run, i.e. transport errors, are
+    // returned, the others are the outcome of the row.
+    fn apply<V: Version + Copy>(
+        &self,
+        client: &OffClient<V>,
+        row: &BulkRow,
+    ) -> Result<RowResult, Error> {
+        let mut result = RowResult {
+            line: row.line,
+            code: row.code.clone(),
+            outcome: RowOutcome::Unchanged,
+            fields: Vec::new(),
+        };
+        let product = match current_product(client, &row.code) {
+            Ok(product) => product,
+            Err(e) => return failed(result, e),
+        };
+        let changes = row.changes(&product);
+        if changes.is_empty() {
+            return Ok(result);
+        }
+        let mut edit = ProductEdit::new(&row.code);
+        for (name, value) in changes {
+            result.fields.push(name.to_string());
+            edit = edit.set(name, value);
+        }
+        if let Some(ref comment) = self.comment {
+            edit = edit.comment(comment);
+        }
+        if let Some((ref name, ref version)) = self.app {
+            edit = edit.app(name, version);
+        }
+        result.outcome = match client.edit_product_response(&edit) {
+            Ok(response) if response.is_saved() => RowOutcome::Saved,
+            Ok(response) => RowOutcome::NotSaved(response.status_verbose),
+            Err(Error::DryRun(request)) => RowOutcome::DryRun(request),
+            Err(e) => return failed(result, e),
+        };
+        Ok(result)
+    }
+}
+
+// Returns the product fields, empty for unknown products.
+fn current_product<V: Version + Copy>(
+    client: &OffClient<V>,
+    barcode: &str,
+) -> Result<Map<String, Value>, Error> {
+    let json: Value = match client.product(barcode, None) {
+        Ok(response) => response.json()?,
+        Err(Error::ProductNotFound(_)) => return Ok(Map::new()),
+        Err(e) => return Err(e),
+    };
+    match json.get("product") {
+        Some(Value::Object(product)) => Ok(product.clone()),
+        _ => Ok(Map::new()),
+    }
+}
+
+// Returns the given row result as failed with the error, or the error if it
+// stops the run: the server or the network is unavailable, or the rate
+// limits are exceeded.
+fn failed(mut result: RowResult, e: Error) -> Result<RowResult, Error> {
+    match e {
+        Error::Transport(_) | Error::RateLimited { .. } | Error::QuotaExceeded { .. } => Err(e),
+        Error::Status { code, .. } if code >= 500 => Err(e),
+        e => {
+            result.outcome = RowOutcome::Failed(e.to_string());
+            Ok(result)
+        }
+    }
+}
+
+// Returns true if the product value is the given text: same string, same
+// number or same list of values separated by commas.
+fn same_value(current: Option<&Value>, text: &str) -> bool {
+    let text = text.trim();
+    match current {
+        None | Some(Value::Null) => text.is_empty(),
+        Some(Value::String(s)) => s.trim() == text || same_number(s, text),
+        Some(Value::Number(n)) => same_number(&n.to_string(), text),
+        Some(Value::Bool(b)) => b.to_string() == text,
+        Some(Value::Array(values)) => {
+            let items: Vec<&str> = text.split(',').map(str::trim).collect();
+            values.len() == items.len()
+                && values
+                    .iter()
+                    .zip(items)
+                    .all(|(value, item)| same_value(Some(value), item))
+        }
+        Some(Value::Object(_)) => false,
+    }
+}
+
+fn same_number(a: &str, b: &str) -> bool {
+    match (a.trim().parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+// Returns the number of rows processed according to the checkpoint file, 0 if
+// the file does not exist. The checkpoint holds the number of rows and the
+// barcode of the last one, checked against the rows.
+fn read_checkpoint(path: &Path, rows: &[BulkRow]) -> Result<usize, Error> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    let mut parts = text.split_whitespace();
+    let count = parts
+        .next()
+        .and_then(|n| n.parse::<usize>().ok())
+        .ok_or_else(|| syntax(1, "invalid checkpoint"))?;
+    let code = parts.next().unwrap_or_default();
+    if count == 0 {
+        return Ok(0);
+    }
+    match rows.get(count - 1) {
+        Some(row) if row.code == code => Ok(count),
+        _ => Err(Error::Validation(format!(
+            "checkpoint {} does not match the rows",
+            path.display()
+        ))),
+    }
+}
+
+// Saves the number of rows processed and the barcode of the last one. The file
+// is replaced atomically.
+fn write_checkpoint(path: &Path, count: usize, code: &str) -> Result<(), Error> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, format!("{} {}\n", count, code))?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+// Appends a row result to the report file, after the header for new files.
+fn append_report(path: &Path, result: &RowResult) -> Result<(), Error> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if file.metadata()?.len() == 0 {
+        writeln!(file, "{}", REPORT_HEADER)?;
+    }
+    writeln!(file, "{}", result.to_csv())?;
+    Ok(())
+}
+
+// Quotes a CSV cell if needed.
+fn csv_cell(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+// Splits a CSV text into records, with the line number of their first line.
+// Quoted cells may contain commas, newlines and doubled quotes. Blank lines are
+// skipped.
+fn parse_csv(text: &str) -> Result<Vec<(usize, Vec<String>)>, Error> {
+    let mut records = Vec::new();
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut line = 1;
+    let mut start = 1;
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    cell.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if cell.is_empty() => quoted = true,
+            '\n' if quoted => {
+                line += 1;
+                cell.push(c);
+            }
+            ',' if !quoted => cells.push(std::mem::take(&mut cell)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                cells.push(std::mem::take(&mut cell));
+                if cells.len() > 1 || !cells[0].trim().is_empty() {
+                    records.push((start, std::mem::take(&mut cells)));
+                }
+                cells.clear();
+                line += 1;
+                start = line;
+            }
+            c => cell.push(c),
+        }
+    }
+    if quoted {
+        return Err(syntax(start, "unterminated quoted cell"));
+    }
+    if !cell.is_empty() || !cells.is_empty() {
+        cells.push(cell);
+        records.push((start, cells));
+    }
+    Ok(records)
+}
+
+fn syntax(line: usize, message: &str) -> Error {
+    Error::Syntax {
+        line,
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn csv() {
+        let bulk = BulkEdit::from_csv(
+            "brands,code,generic_name\r\n\
+             \"Kraš, d.d.\",3850102123681,\"Chocolate \"\"dark\"\"\"\r\n\
+             \n\
+             ,123,\"two\nlines\"\n\
+             Milka,456,",
+        )
+        .unwrap();
+        let rows = bulk.rows();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].line, 2);
+        assert_eq!(rows[0].code, "3850102123681");
+        assert_eq!(
+            rows[0].fields,
+            vec![
+                (String::from("brands"), String::from("Kraš, d.d.")),
+                (
+                    String::from("generic_name"),
+                    String::from("Chocolate \"dark\"")
+                ),
+            ]
+        );
+        assert_eq!(rows[1].line, 4);
+        assert_eq!(
+            rows[1].fields,
+            vec![(String::from("generic_name"), String::from("two\nlines"))]
+        );
+        assert_eq!(rows[2].line, 6);
+        assert_eq!(rows[2].code, "456");
+
+        assert!(matches!(
+            BulkEdit::from_csv("brands\nKraš\n"),
+            Err(Error::Syntax { line: 1, .. })
+        ));
+        assert!(matches!(
+            BulkEdit::from_csv("code,brands\n123\n"),
+            Err(Error::Syntax { line: 2, .. })
+        ));
+        assert!(matches!(
+            BulkEdit::from_csv("code,brands\n123,\"Kraš\n"),
+            Err(Error::Syntax { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn jsonl() {
+        let bulk = BulkEdit::from_jsonl(
+            "{\"code\": \"123\", \"brands\": \"Kraš\", \"nutriment_fat\": 31.5, \"labels\": null}\n\
+             \n\
+             {\"barcode\": \"456\"}\n",
+        )
+        .unwrap();
+        let rows = bulk.rows();
+        assert_eq!(rows[0].code, "123");
+        assert_eq!(
+            rows[0].fields,
+            vec![
+                (String::from("brands"), String::from("Kraš")),
+                (String::from("nutriment_fat"), String::from("31.5")),
+            ]
+        );
+        assert_eq!(rows[1].line, 3);
+        assert!(rows[1].fields.is_empty());
+        for text in [
+            "[1]",
+            "{\"brands\": \"Kraš\"}",
+            "{\"code\": \"1\", \"a\": [1]}",
+            "{",
+        ] {
+            assert!(matches!(
+                BulkEdit::from_jsonl(text),
+                Err(Error::Syntax { line: 1, .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn changes() {
+        let row = BulkRow {
+            line: 2,
+            code: String::from("123"),
+            fields: [
+                ("brands", "Kraš"),
+                ("quantity", "200 g"),
+                ("serving_quantity", "25.0"),
+                ("categories_tags", "en:snacks, en:sweets"),
+                ("nutriment_fat", "31.5"),
+                ("nutriment_fat_unit", "g"),
+                ("labels", "Organic"),
+            ]
+            .iter()
+            .map(|(n, v)| (n.to_string(), v.to_string()))
+            .collect(),
+        };
+        let product = json!({
+            "brands": "Kraš",
+            "quantity": "100 g",
+            "serving_quantity": 25,
+            "categories_tags": ["en:snacks", "en:sweets"],
+            "nutriments": {"fat": 0.0315, "fat_value": 31.5, "fat_unit": "mg"}
+        });
+        assert_eq!(
+            row.changes(product.as_object().unwrap()),
+            vec![
+                ("quantity", "200 g"),
+                ("nutriment_fat_unit", "g"),
+                ("labels", "Organic")
+            ]
+        );
+        assert_eq!(row.changes(&Map::new()).len(), 7);
+    }
+
+    #[test]
+    fn nutrient_changes() {
+        let row = BulkRow {
+            line: 2,
+            code: String::from("123"),
+            fields: vec![
+                (String::from("nutriment_salt"), String::from("500")),
+                (String::from("nutriment_salt_unit"), String::from("mg")),
+            ],
+        };
+        // The salt is saved normalized to grams in `salt`, as entered in
+        // `salt_value` and `salt_unit`.
+        let product = json!({
+            "nutriments": {"salt": 0.5, "salt_value": 500, "salt_unit": "mg"}
+        });
+        assert!(row.changes(product.as_object().unwrap()).is_empty());
+        let product = json!({
+            "nutriments": {"salt": 0.5, "salt_value": 0.5, "salt_unit": "g"}
+        });
+        assert_eq!(
+            row.changes(product.as_object().unwrap()),
+            vec![("nutriment_salt", "500"), ("nutriment_salt_unit", "mg")]
+        );
+    }
+
+    #[test]
+    fn checkpoint() {
+        let rows = BulkEdit::from_csv("code\n1\n2\n3\n").unwrap().rows;
+        let path = std::env::temp_dir().join(format!("off-checkpoint-{}", std::process::id()));
+        assert_eq!(read_checkpoint(&path, &rows).unwrap(), 0);
+        write_checkpoint(&path, 2, "2").unwrap();
+        assert_eq!(read_checkpoint(&path, &rows).unwrap(), 2);
+        write_checkpoint(&path, 2, "3").unwrap();
+        assert!(matches!(
+            read_checkpoint(&path, &rows),
+            Err(Error::Validation(_))
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn report_line() {
+        let result = RowResult {
+            line: 3,
+            code: String::from("123"),
+            outcome: RowOutcome::NotSaved(String::from("no code, or invalid code")),
+            fields: vec![String::from("brands"), String::from("quantity")],
+        };
+        assert_eq!(
+            result.to_csv(),
+            "3,123,not_saved,brands quantity,\"no code, or invalid code\""
+        );
+    }
+}
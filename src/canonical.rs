@@ -0,0 +1,395 @@
+// Tag canonicalization: free text in a given language to the id of a taxonomy
+// tag, the way Product Opener does it. The text is normalized (lowercased,
+// unaccented in some languages, non alphanumeric characters folded to dashes),
+// then its stopwords are removed and its synonyms replaced before looking it up
+// among the names and synonyms of the taxonomy.
+use crate::error::Error;
+use crate::taxonomy::Taxonomy;
+use std::collections::{BTreeMap, BTreeSet};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// The languages whose accents are removed by the normalization.
+const UNACCENTED_LANGS: [&str; 8] = ["ca", "en", "es", "fr", "it", "nl", "pt", "xx"];
+
+/// The language of the names valid in all languages.
+const ANY_LANG: &str = "xx";
+
+/// Normalizes a tag name the way Product Opener builds tag ids: lowercased,
+/// unaccented in some languages, and with the runs of characters other than
+/// letters and digits replaced by a dash.
+///
+/// ```
+/// use openfoodfacts::normalize_tag;
+///
+/// assert_eq!(normalize_tag("fr", "Fromages à pâte dure"), "fromages-a-pate-dure");
+/// assert_eq!(normalize_tag("de", "Käse, Hartkäse"), "käse-hartkäse");
+/// ```
+pub fn normalize_tag(lang: &str, name: &str) -> String {
+    let unaccent = UNACCENTED_LANGS.contains(&lang);
+    let mut id = String::new();
+    let mut push = |c: char| {
+        if c.is_alphanumeric() {
+            id.push(c);
+        } else if !id.is_empty() && !id.ends_with('-') {
+            id.push('-');
+        }
+    };
+    for c in name.chars().flat_map(char::to_lowercase) {
+        match c {
+            'œ' if unaccent => "oe".chars().for_each(&mut push),
+            'æ' if unaccent => "ae".chars().for_each(&mut push),
+            c if unaccent => c
+                .to_string()
+                .nfd()
+                .filter(|c| !is_combining_mark(*c))
+                .for_each(&mut push),
+            c => push(c),
+        }
+    }
+    id.trim_end_matches('-').to_string()
+}
+
+/// The result of a canonicalization.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Canonical {
+    /// The id of the matching taxonomy tag, i.e. `en:hard-cheeses`.
+    Known(String),
+    /// No tag matches. Holds the normalized tag, i.e. `fr:fromages-bizarres`,
+    /// as Product Opener would store it.
+    Unknown(String),
+}
+
+impl Canonical {
+    /// Returns the id of the matching tag, if any.
+    pub fn known(&self) -> Option<&str> {
+        match self {
+            Self::Known(id) => Some(id),
+            Self::Unknown(_) => None,
+        }
+    }
+
+    /// Returns the tag, known or not.
+    pub fn tag(&self) -> &str {
+        match self {
+            Self::Known(tag) | Self::Unknown(tag) => tag,
+        }
+    }
+}
+
+/// Converts free text to canonical tags of a taxonomy.
+///
+/// The text may be prefixed by a language code, i.e. `fr:fromages`, which
+/// overrides the language given to [Canonicalizer::canonicalize]. Names in the
+/// `xx` language match in all languages.
+///
+/// ```
+/// use openfoodfacts::{Canonical, Canonicalizer, Taxonomy};
+///
+/// let taxonomy = Taxonomy::from_txt("\
+/// stopwords:fr: a, de
+///
+/// en: Hard cheeses
+/// fr: Fromages à pâte dure, fromages durs
+/// ").unwrap();
+/// let canonicalizer = Canonicalizer::new(&taxonomy);
+/// assert_eq!(
+///     canonicalizer.canonicalize("Fromages à pâte dure", "fr"),
+///     Canonical::Known(String::from("en:hard-cheeses"))
+/// );
+/// assert_eq!(
+///     canonicalizer.canonicalize("fr:fromages pâte dure", "en").known(),
+///     Some("en:hard-cheeses")
+/// );
+/// assert_eq!(
+///     canonicalizer.canonicalize("Fromages bizarres", "fr"),
+///     Canonical::Unknown(String::from("fr:fromages-bizarres"))
+/// );
+/// ```
+#[derive(Debug)]
+pub struct Canonicalizer<'a> {
+    taxonomy: &'a Taxonomy,
+    // The normalized stopwords by language.
+    stopwords: BTreeMap<String, BTreeSet<String>>,
+    // The normalized synonyms and the word replacing them, by language, the
+    // longest synonyms first.
+    synonyms: BTreeMap<String, Vec<(String, String)>>,
+    // The tag ids by `lang:key`, the key being the normalized name without
+    // stopwords and with the synonyms replaced.
+    index: BTreeMap<String, String>,
+}
+
+impl<'a> Canonicalizer<'a> {
+    /// Creates a canonicalizer for the names, synonyms, stopwords and synonym
+    /// groups of the given taxonomy.
+    pub fn new(taxonomy: &'a Taxonomy) -> Self {
+        let mut canonicalizer = Self {
+            taxonomy,
+            stopwords: BTreeMap::new(),
+            synonyms: BTreeMap::new(),
+            index: BTreeMap::new(),
+        };
+        for (lang, words) in taxonomy.stopwords() {
+            let stopwords = canonicalizer.stopwords.entry(lang.clone()).or_default();
+            stopwords.extend(words.iter().map(|w| normalize_tag(lang, w)));
+        }
+        for (lang, words) in taxonomy.synonym_groups() {
+            let mut words = words.iter().map(|w| normalize_tag(lang, w));
+            if let Some(first) = words.next() {
+                let synonyms = canonicalizer.synonyms.entry(lang.clone()).or_default();
+                synonyms.extend(words.map(|w| (w, first.clone())));
+            }
+        }
+        for synonyms in canonicalizer.synonyms.values_mut() {
+            synonyms.sort_by_key(|(word, _)| std::cmp::Reverse(word.len()));
+        }
+        for entry in taxonomy.iter() {
+            for (lang, name) in entry.names.iter().chain(
+                entry
+                    .synonyms
+                    .iter()
+                    .flat_map(|(lang, names)| names.iter().map(move |n| (lang, n))),
+            ) {
+                let key = format!("{}:{}", lang, canonicalizer.key(lang, name));
+                canonicalizer
+                    .index
+                    .entry(key)
+                    .or_insert_with(|| entry.id.clone());
+            }
+        }
+        canonicalizer
+    }
+
+    /// Returns the canonical tag of the given text in the given language.
+    pub fn canonicalize(&self, text: &str, lang: &str) -> Canonical {
+        let text = text.trim();
+        let (lang, name) = match text.split_once(':') {
+            Some((prefix, name)) if is_lang(prefix.trim()) => (prefix.trim(), name),
+            _ => (lang, text),
+        };
+        let normalized = normalize_tag(lang, name);
+        let id = format!("{}:{}", lang, normalized);
+        if self.taxonomy.contains(&id) {
+            return Canonical::Known(id);
+        }
+        let key = self.key(lang, name);
+        [lang, ANY_LANG]
+            .iter()
+            .find_map(|lang| self.index.get(&format!("{}:{}", lang, key)))
+            .map_or(Canonical::Unknown(id), |id| Canonical::Known(id.clone()))
+    }
+
+    /// Canonicalizes a criteria value of [crate::search::SearchQueryV2]: a list
+    /// of tags separated by `,` (and) or `|` (or), each optionally negated by a
+    /// leading `-`. Returns [Error::Validation] if a tag is unknown.
+    ///
+    /// ```
+    /// # use openfoodfacts::{Canonicalizer, Taxonomy};
+    /// # let taxonomy = Taxonomy::from_txt("en: Cheeses\nfr: Fromages\n\nen: Beverages").unwrap();
+    /// let canonicalizer = Canonicalizer::new(&taxonomy);
+    /// assert_eq!(
+    ///     canonicalizer.criteria_value("Fromages,-en:Beverages", "fr").unwrap(),
+    ///     "en:cheeses,-en:beverages"
+    /// );
+    /// assert!(canonicalizer.criteria_value("Fromages|Chocolats", "fr").is_err());
+    /// ```
+    pub fn criteria_value(&self, value: &str, lang: &str) -> Result<String, Error> {
+        let mut canonical = String::new();
+        for (i, and) in value.split(',').enumerate() {
+            if i > 0 {
+                canonical.push(',');
+            }
+            for (j, or) in and.split('|').enumerate() {
+                if j > 0 {
+                    canonical.push('|');
+                }
+                let (negation, tag) = match or.trim().strip_prefix('-') {
+                    Some(tag) => ("-", tag),
+                    None => ("", or),
+                };
+                match self.canonicalize(tag, lang) {
+                    Canonical::Known(id) => {
+                        canonical.push_str(negation);
+                        canonical.push_str(&id);
+                    }
+                    Canonical::Unknown(tag) => {
+                        return Err(Error::Validation(format!("unknown tag '{}'", tag)))
+                    }
+                }
+            }
+        }
+        Ok(canonical)
+    }
+
+    // Returns the matching key of a name: normalized, without stopwords and
+    // with the synonyms replaced.
+    fn key(&self, lang: &str, name: &str) -> String {
+        let normalized = normalize_tag(lang, name);
+        let words: Vec<&str> = normalized.split('-').collect();
+        let kept: Vec<&str> = match self.stopwords.get(lang) {
+            Some(stopwords) => words
+                .iter()
+                .copied()
+                .filter(|w| !stopwords.contains(*w))
+                .collect(),
+            None => words.clone(),
+        };
+        // Names made only of stopwords are kept.
+        let mut key = format!("-{}-", if kept.is_empty() { words } else { kept }.join("-"));
+        for (synonym, word) in self.synonyms.get(lang).into_iter().flatten() {
+            key = key.replace(&format!("-{}-", synonym), &format!("-{}-", word));
+        }
+        key.trim_matches('-').to_string()
+    }
+}
+
+/// Returns true for language codes: 2 or 3 lowercase letters, optionally
+/// followed by a region, i.e. `pt_br`.
+pub(crate) fn is_lang(code: &str) -> bool {
+    let (lang, region) = match code.split_once('_') {
+        Some((lang, region)) => (lang, Some(region)),
+        None => (code, None),
+    };
+    let lower = |s: &str| s.chars().all(|c| c.is_ascii_lowercase());
+    let region = match region {
+        Some(region) => region.len() == 2 && lower(region),
+        None => true,
+    };
+    (2..=3).contains(&lang.len()) && lower(lang) && region
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "\
+stopwords:fr: a, au, de, la
+synonyms:en: soda, soft drink, fizzy drink
+synonyms:fr: boisson, breuvage
+
+en: Beverages
+fr: Boissons
+
+< en:Beverages
+en: Sodas
+
+< en:Sodas
+en: Diet soda
+fr: Boisson gazeuse light
+
+en: Hard cheeses
+fr: Fromages à pâte dure, fromages durs
+pt_br: Queijos duros
+
+en: Parmigiano Reggiano
+xx: Parmigiano Reggiano
+";
+
+    fn known(id: &str) -> Canonical {
+        Canonical::Known(id.to_string())
+    }
+
+    #[test]
+    fn normalize() {
+        assert_eq!(normalize_tag("fr", "  Crème fraîche!! "), "creme-fraiche");
+        assert_eq!(normalize_tag("fr", "Œufs & bœuf"), "oeufs-boeuf");
+        assert_eq!(normalize_tag("de", "Crème fraîche"), "crème-fraîche");
+        assert_eq!(normalize_tag("en", "E-322 (lecithins)"), "e-322-lecithins");
+    }
+
+    #[test]
+    fn lang_codes() {
+        assert!(is_lang("fr"));
+        assert!(is_lang("ast"));
+        assert!(is_lang("pt_br"));
+        assert!(!is_lang("FR"));
+        assert!(!is_lang("e"));
+        assert!(!is_lang("pt_bra"));
+        assert!(!is_lang("wikidata"));
+    }
+
+    #[test]
+    fn canonicalize() {
+        let taxonomy = Taxonomy::from_txt(SOURCE).unwrap();
+        let canonicalizer = Canonicalizer::new(&taxonomy);
+        // Ids, names and synonyms.
+        assert_eq!(
+            canonicalizer.canonicalize("en:hard-cheeses", "fr"),
+            known("en:hard-cheeses")
+        );
+        assert_eq!(
+            canonicalizer.canonicalize("Fromages à pâte dure", "fr"),
+            known("en:hard-cheeses")
+        );
+        assert_eq!(
+            canonicalizer.canonicalize("FROMAGES DURS", "fr"),
+            known("en:hard-cheeses")
+        );
+        assert_eq!(
+            canonicalizer.canonicalize("fr:fromages-a-pate-dure", "en"),
+            known("en:hard-cheeses")
+        );
+        assert_eq!(
+            canonicalizer.canonicalize("pt_br:Queijos duros", "fr"),
+            known("en:hard-cheeses")
+        );
+        // Names in all languages.
+        assert_eq!(
+            canonicalizer.canonicalize("parmigiano reggiano", "it"),
+            known("en:parmigiano-reggiano")
+        );
+        // Unknown, with the normalized tag.
+        assert_eq!(
+            canonicalizer.canonicalize("Fromages à pâte molle", "fr"),
+            Canonical::Unknown(String::from("fr:fromages-a-pate-molle"))
+        );
+    }
+
+    #[test]
+    fn stopwords_and_synonyms() {
+        let taxonomy = Taxonomy::from_txt(SOURCE).unwrap();
+        let canonicalizer = Canonicalizer::new(&taxonomy);
+        // Stopwords.
+        assert_eq!(
+            canonicalizer.canonicalize("Fromages pâte dure", "fr"),
+            known("en:hard-cheeses")
+        );
+        assert_eq!(
+            canonicalizer.canonicalize("Fromages de la pâte dure", "fr"),
+            known("en:hard-cheeses")
+        );
+        // Synonym groups.
+        assert_eq!(
+            canonicalizer.canonicalize("Diet soft drink", "en"),
+            known("en:diet-soda")
+        );
+        assert_eq!(
+            canonicalizer.canonicalize("Diet fizzy-drink", "en"),
+            known("en:diet-soda")
+        );
+        assert_eq!(
+            canonicalizer.canonicalize("Breuvage gazeuse light", "fr"),
+            known("en:diet-soda")
+        );
+        assert_eq!(
+            canonicalizer.canonicalize("Diet soft drinks", "en").known(),
+            None
+        );
+    }
+
+    #[test]
+    fn criteria_value() {
+        let taxonomy = Taxonomy::from_txt(SOURCE).unwrap();
+        let canonicalizer = Canonicalizer::new(&taxonomy);
+        assert_eq!(
+            canonicalizer
+                .criteria_value("Boissons|fromages durs,-en:Sodas", "fr")
+                .unwrap(),
+            "en:beverages|en:hard-cheeses,-en:sodas"
+        );
+        match canonicalizer.criteria_value("Boissons,Chocolats", "fr") {
+            Err(Error::Validation(msg)) => assert!(msg.contains("fr:chocolats")),
+            r => panic!("unexpected result {:?}", r),
+        }
+    }
+}
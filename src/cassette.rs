@@ -0,0 +1,524 @@
+// Record-and-replay transports. A RecordingTransport saves the interactions with
+// a real server to a cassette file, a ReplayTransport serves them back without
+// network access.
+#[cfg(feature = "async")]
+use crate::async_transport::{AsyncHttpResponse, AsyncTransport, BoxFuture};
+use crate::dry_run::{is_secret_header, redacted_body, REDACTED};
+use crate::error::Error;
+use crate::transport::{
+    HeaderMap, HttpRequest, HttpResponse, Transport, TransportError, TransportErrorKind,
+};
+use http::header::{HeaderName, HeaderValue};
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use url::Url;
+
+/// A recorded request. The request headers are not recorded and the passwords
+/// of the bodies are redacted, as are the cookies and credentials of the
+/// response headers, so that no credentials end up in the cassettes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    /// The request method.
+    pub method: String,
+    /// The full request URL.
+    pub url: String,
+    /// The request body, if any, with the passwords redacted. Only form and
+    /// JSON bodies are recorded. Informative only: not used for matching.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+}
+
+/// A recorded response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    /// The HTTP status code.
+    pub status: u16,
+    /// The response headers, as (name, value) pairs. The values of the
+    /// headers holding credentials or session tokens, i.e. `set-cookie`, are
+    /// redacted.
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    /// The response body. Bodies that are not valid UTF-8 are stored lossily.
+    pub body: String,
+}
+
+/// A recorded request/response pair.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+/// A list of interactions, stored as a JSON file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    /// Loads a cassette file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = std::fs::File::open(path)?;
+        Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+    }
+
+    /// Saves the cassette to the given file, creating the parent directories
+    /// if needed.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+        Ok(std::fs::write(path, json)?)
+    }
+}
+
+/// A transport recording the interactions of an inner transport, i.e.
+/// [crate::ReqwestTransport], to a cassette file. The file is rewritten after
+/// each interaction.
+///
+/// ```no_run
+/// use openfoodfacts::{self as off, RecordingTransport, ReqwestTransport};
+///
+/// # fn main() -> Result<(), off::Error> {
+/// let inner = ReqwestTransport::new(off::HttpClient::new());
+/// let client = off::v2()
+///     .transport(RecordingTransport::new(inner, "cassettes/product.json"))
+///     .build()?;
+/// client.product("3850102123681", None)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RecordingTransport<T> {
+    inner: T,
+    path: PathBuf,
+    cassette: Mutex<Cassette>,
+}
+
+impl<T> RecordingTransport<T> {
+    /// Creates a transport sending the requests with `inner` and recording
+    /// them to the cassette file at `path`.
+    pub fn new<P: Into<PathBuf>>(inner: T, path: P) -> Self {
+        Self {
+            inner,
+            path: path.into(),
+            cassette: Mutex::new(Cassette::default()),
+        }
+    }
+
+    // Records an interaction and saves the cassette.
+    fn record(&self, interaction: Interaction) -> Result<(), TransportError> {
+        let mut cassette = self.cassette.lock().unwrap_or_else(|e| e.into_inner());
+        cassette.interactions.push(interaction);
+        cassette
+            .save(&self.path)
+            .map_err(|e| TransportError::new(TransportErrorKind::Other, e))
+    }
+}
+
+impl<T> Transport for RecordingTransport<T>
+where
+    T: Transport,
+{
+    fn send(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        let recorded = record_request(&request);
+        let response = self.inner.send(request)?;
+        let url = response.url().clone();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let mut body = Vec::new();
+        std::io::Read::read_to_end(&mut response.into_reader(), &mut body)?;
+        self.record(Interaction {
+            request: recorded,
+            response: record_response(status, &headers, &body),
+        })?;
+        Ok(HttpResponse::new(url, status, headers, Cursor::new(body)))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> AsyncTransport for RecordingTransport<T>
+where
+    T: AsyncTransport,
+{
+    fn send(
+        &self,
+        request: HttpRequest,
+    ) -> BoxFuture<'_, Result<AsyncHttpResponse, TransportError>> {
+        Box::pin(async move {
+            let recorded = record_request(&request);
+            let response = self.inner.send(request).await?;
+            let url = response.url().clone();
+            let status = response.status();
+            let headers = response.headers().clone();
+            let body = response.bytes().await.map_err(|e| match e {
+                Error::Transport(e) => e,
+                e => TransportError::new(TransportErrorKind::Other, e.to_string()),
+            })?;
+            self.record(Interaction {
+                request: recorded,
+                response: record_response(status, &headers, &body),
+            })?;
+            let body = futures_util::stream::iter(std::iter::once(Ok(body)));
+            Ok(AsyncHttpResponse::new(url, status, headers, body))
+        })
+    }
+}
+
+/// A transport serving the interactions of a cassette, without network access.
+///
+/// A request matches a recorded one if they have the same method, path and
+/// query parameters, in any order. The host is not compared. Volatile query
+/// parameters can be excluded from the comparison. Each recorded interaction is
+/// served once, in the recording order; requests without an unused matching
+/// interaction fail with a transport error.
+///
+/// ```no_run
+/// use openfoodfacts::{self as off, ReplayTransport};
+///
+/// # fn main() -> Result<(), off::Error> {
+/// let transport = ReplayTransport::load("cassettes/product.json")?.ignore_param("nocache");
+/// let client = off::v2().transport(transport).build()?;
+/// client.product("3850102123681", None)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ReplayTransport {
+    interactions: Vec<Interaction>,
+    ignored_params: Vec<String>,
+    // The indexes of the interactions already served.
+    used: Mutex<Vec<bool>>,
+}
+
+impl ReplayTransport {
+    /// Creates a transport serving the interactions of the given cassette.
+    pub fn new(cassette: Cassette) -> Self {
+        let used = vec![false; cassette.interactions.len()];
+        Self {
+            interactions: cassette.interactions,
+            ignored_params: Vec::new(),
+            used: Mutex::new(used),
+        }
+    }
+
+    /// Creates a transport serving the interactions of the given cassette file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Ok(Self::new(Cassette::load(path)?))
+    }
+
+    /// Excludes the given query parameter from the request matching.
+    pub fn ignore_param(mut self, name: &str) -> Self {
+        self.ignored_params.push(name.to_string());
+        self
+    }
+
+    // Returns the response of the first unused interaction matching the request.
+    fn replay(&self, request: &HttpRequest) -> Result<RecordedResponse, TransportError> {
+        let key = self.key(request.method.as_str(), &request.url);
+        let mut used = self.used.lock().unwrap_or_else(|e| e.into_inner());
+        let found = self
+            .interactions
+            .iter()
+            .enumerate()
+            .find(|(i, interaction)| {
+                !used[*i]
+                    && Url::parse(&interaction.request.url)
+                        .map(|url| self.key(&interaction.request.method, &url) == key)
+                        .unwrap_or(false)
+            });
+        match found {
+            Some((i, interaction)) => {
+                used[i] = true;
+                Ok(interaction.response.clone())
+            }
+            None => Err(TransportError::new(
+                TransportErrorKind::Other,
+                format!(
+                    "no recorded interaction for {} {}",
+                    request.method, request.url
+                ),
+            )),
+        }
+    }
+
+    // Returns the matching key of a request: the method, the path and the
+    // sorted query parameters, less the ignored ones.
+    fn key(&self, method: &str, url: &Url) -> (String, String, Vec<(String, String)>) {
+        let mut query: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(name, _)| !self.ignored_params.iter().any(|p| p == name))
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        query.sort();
+        (method.to_ascii_uppercase(), url.path().to_string(), query)
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn send(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        let recorded = self.replay(&request)?;
+        let (status, headers) = response_parts(&recorded)?;
+        let body = Cursor::new(recorded.body.into_bytes());
+        Ok(HttpResponse::new(request.url, status, headers, body))
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncTransport for ReplayTransport {
+    fn send(
+        &self,
+        request: HttpRequest,
+    ) -> BoxFuture<'_, Result<AsyncHttpResponse, TransportError>> {
+        let result = self.replay(&request).and_then(|recorded| {
+            let (status, headers) = response_parts(&recorded)?;
+            let body = futures_util::stream::iter(std::iter::once(Ok(recorded.body.into_bytes())));
+            Ok(AsyncHttpResponse::new(request.url, status, headers, body))
+        });
+        Box::pin(async move { result })
+    }
+}
+
+fn record_request(request: &HttpRequest) -> RecordedRequest {
+    RecordedRequest {
+        method: request.method.to_string(),
+        url: request.url.to_string(),
+        body: redacted_body(request),
+    }
+}
+
+fn record_response(status: StatusCode, headers: &HeaderMap, body: &[u8]) -> RecordedResponse {
+    RecordedResponse {
+        status: status.as_u16(),
+        headers: headers
+            .iter()
+            .filter_map(|(name, value)| {
+                let value = if is_secret_header(name, value) {
+                    REDACTED
+                } else {
+                    value.to_str().ok()?
+                };
+                Some((name.to_string(), value.to_string()))
+            })
+            .collect(),
+        body: String::from_utf8_lossy(body).into_owned(),
+    }
+}
+
+// Returns the status and headers of a recorded response.
+fn response_parts(recorded: &RecordedResponse) -> Result<(StatusCode, HeaderMap), TransportError> {
+    let invalid = |e: String| TransportError::new(TransportErrorKind::Other, e);
+    let status = StatusCode::from_u16(recorded.status).map_err(|e| invalid(e.to_string()))?;
+    let mut headers = HeaderMap::new();
+    for (name, value) in &recorded.headers {
+        headers.append(
+            HeaderName::from_bytes(name.as_bytes()).map_err(|e| invalid(e.to_string()))?,
+            HeaderValue::from_str(value).map_err(|e| invalid(e.to_string()))?,
+        );
+    }
+    Ok((status, headers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::Method;
+
+    fn interaction(url: &str, body: &str) -> Interaction {
+        Interaction {
+            request: RecordedRequest {
+                method: String::from("GET"),
+                url: url.to_string(),
+                body: None,
+            },
+            response: RecordedResponse {
+                status: 200,
+                headers: vec![(
+                    String::from("content-type"),
+                    String::from("application/json"),
+                )],
+                body: body.to_string(),
+            },
+        }
+    }
+
+    fn get(url: &str) -> HttpRequest {
+        HttpRequest::new(Method::GET, Url::parse(url).unwrap())
+    }
+
+    // Sends with the blocking transport, the async one being ambiguous.
+    fn send(
+        transport: &dyn Transport,
+        request: HttpRequest,
+    ) -> Result<HttpResponse, TransportError> {
+        transport.send(request)
+    }
+
+    fn replay() -> ReplayTransport {
+        ReplayTransport::new(Cassette {
+            interactions: vec![
+                interaction(
+                    "https://world.openfoodfacts.org/brands.json?page=2&nocache=1",
+                    "1",
+                ),
+                interaction(
+                    "https://world.openfoodfacts.org/brands.json?page=2&nocache=1",
+                    "2",
+                ),
+            ],
+        })
+    }
+
+    #[test]
+    fn strict_matching() {
+        let transport = replay();
+        // Sorted query, any host.
+        let response = send(
+            &transport,
+            get("http://localhost/brands.json?nocache=1&page=2"),
+        )
+        .unwrap();
+        assert_eq!(response.text().unwrap(), "1");
+        // Missing parameter.
+        assert!(send(&transport, get("http://localhost/brands.json?page=2")).is_err());
+        // Other method.
+        let request = HttpRequest::new(
+            Method::POST,
+            Url::parse("http://localhost/brands.json?nocache=1&page=2").unwrap(),
+        );
+        assert!(send(&transport, request).is_err());
+    }
+
+    #[test]
+    fn recording_order() {
+        let transport = replay();
+        let url = "https://world.openfoodfacts.org/brands.json?page=2&nocache=1";
+        assert_eq!(send(&transport, get(url)).unwrap().text().unwrap(), "1");
+        assert_eq!(send(&transport, get(url)).unwrap().text().unwrap(), "2");
+        // All the matching interactions are used.
+        assert!(send(&transport, get(url)).is_err());
+    }
+
+    #[test]
+    fn ignored_params() {
+        let transport = replay().ignore_param("nocache");
+        let response = send(
+            &transport,
+            get("https://world.openfoodfacts.org/brands.json?page=2&nocache=2"),
+        )
+        .unwrap();
+        assert_eq!(response.text().unwrap(), "1");
+    }
+
+    #[derive(Debug)]
+    struct Server;
+
+    impl Transport for Server {
+        fn send(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+            let mut headers = HeaderMap::new();
+            headers.insert("content-type", HeaderValue::from_static("application/json"));
+            let body: &[u8] = br#"{"status": 1}"#;
+            Ok(HttpResponse::new(
+                request.url,
+                StatusCode::OK,
+                headers,
+                body,
+            ))
+        }
+    }
+
+    #[test]
+    fn record_then_replay() {
+        let path = std::env::temp_dir().join(format!("off-cassette-{}.json", std::process::id()));
+        let url = "https://world.openfoodfacts.org/api/v2/product/3850102123681?fields=code";
+
+        let recording = RecordingTransport::new(Server, &path);
+        let response = send(&recording, get(url)).unwrap();
+        assert_eq!(response.text().unwrap(), r#"{"status": 1}"#);
+
+        let cassette = Cassette::load(&path).unwrap();
+        assert_eq!(cassette.interactions.len(), 1);
+        assert_eq!(cassette.interactions[0].request.url, url);
+        assert_eq!(cassette.interactions[0].response.status, 200);
+
+        let replay = ReplayTransport::load(&path).unwrap();
+        let response = send(&replay, get(url)).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "application/json");
+        assert_eq!(response.text().unwrap(), r#"{"status": 1}"#);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn record_write() {
+        let path =
+            std::env::temp_dir().join(format!("off-cassette-write-{}.json", std::process::id()));
+        let url = Url::parse("https://world.openfoodfacts.org/cgi/product_jqm2.pl").unwrap();
+        let request = crate::client::form_request(
+            url,
+            &[("code", "123"), ("user_id", "user"), ("password", "secret")],
+        );
+        let recording = RecordingTransport::new(Server, &path);
+        send(&recording, request).unwrap();
+
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(!saved.contains("secret"));
+        let cassette = Cassette::load(&path).unwrap();
+        assert_eq!(
+            cassette.interactions[0].request.body.as_deref(),
+            Some("code=123&user_id=user&password=%3Credacted%3E")
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[derive(Debug)]
+    struct LoginServer;
+
+    impl Transport for LoginServer {
+        fn send(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+            let mut headers = HeaderMap::new();
+            headers.insert("content-type", HeaderValue::from_static("text/html"));
+            headers.insert(
+                "set-cookie",
+                HeaderValue::from_static("session=user_session&s3cr3t-t0k3n; path=/"),
+            );
+            let body: &[u8] = b"<html></html>";
+            Ok(HttpResponse::new(
+                request.url,
+                StatusCode::OK,
+                headers,
+                body,
+            ))
+        }
+    }
+
+    #[test]
+    fn record_login() {
+        let path =
+            std::env::temp_dir().join(format!("off-cassette-login-{}.json", std::process::id()));
+        let url = Url::parse("https://world.openfoodfacts.org/cgi/session.pl").unwrap();
+        let request =
+            crate::client::form_request(url, &[("user_id", "user"), ("password", "secret")]);
+        let recording = RecordingTransport::new(LoginServer, &path);
+        send(&recording, request).unwrap();
+
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(!saved.contains("s3cr3t-t0k3n"));
+        assert!(!saved.contains("secret"));
+        let cassette = Cassette::load(&path).unwrap();
+        assert_eq!(
+            cassette.interactions[0].response.headers,
+            vec![
+                (String::from("content-type"), String::from("text/html")),
+                (String::from("set-cookie"), String::from(REDACTED)),
+            ]
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,41 +1,75 @@
 // Notes:
 //
-// * The 'cc' and 'lc' query parmeters are not supported. The country and
-//   language are always selected via the subdomain.
+// * The country and language are selected via the subdomain, or via the 'cc'
+//   and 'lc' query parameters if the host does not support locale subdomains.
 // * Only JSON calls are supported.
+use crate::auth::{has_session_cookie, Auth};
+use crate::bulk::{BulkEdit, BulkReport};
+use crate::dry_run::PreparedRequest;
+use crate::edit::{EditResponse, ProductEdit};
+use crate::error::validate_barcode;
+pub use crate::error::Error;
+use crate::facet::{Facet, FacetResponse};
+use crate::flavor::Flavor;
+use crate::host::Host;
+use crate::image::ProductImages;
+use crate::image_edit::{ImageCrop, ImageResponse, ImageUpload};
 use crate::locale::Locale;
 use crate::output::Output;
+use crate::product::{Product, ProductResponse, SearchResponse};
+use crate::product_v3::{ProductResponseV3, ProductUpdate};
+use crate::ratelimit::{EndpointClass, RateLimiter};
+use crate::retry::RetryPolicy;
 use crate::search::{SearchQueryV0, SearchQueryV2};
-use crate::types::{Params, Version, V0, V2};
-pub use reqwest::blocking::{Client as HttpClient, Response as HttpResponse};
+use crate::taxonomy::Taxonomy;
+use crate::transport::{HeaderMap, HttpRequest, HttpResponse, Method, Transport};
+use crate::types::{Params, Version, V0, V2, V3};
+pub use reqwest::blocking::Client as HttpClient;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
 use url::{ParseError, Url};
 
-/// The error type of all OffClient methods.
-pub type Error = Box<dyn std::error::Error>;
+/// The scripts of the write API.
+pub(crate) const PRODUCT_EDIT: &str = "product_jqm2.pl";
+pub(crate) const IMAGE_UPLOAD: &str = "product_image_upload.pl";
+pub(crate) const IMAGE_CROP: &str = "product_image_crop.pl";
+pub(crate) const IMAGE_UNSELECT: &str = "product_image_unselect.pl";
 
 /// The return type of all OffClient methods.
 pub type Result = std::result::Result<HttpResponse, Error>;
 
 /// The OFF API client.
 ///
-/// The client owns a [reqwest::Client] object. One single OFF client should
-/// be used per application.
+/// The client owns a [Transport] object, a reqwest client by default. One
+/// single OFF client should be used per application.
 ///
-/// All methods return an [OffResult] object.
+/// All methods return a [Result] object.
 #[derive(Debug)]
 pub struct OffClient<V> {
     // The version marker.
     v: V,
     // The default locale to use when no locale is given in a method call.
     locale: Locale,
-    // The uderlying reqwest client.
-    client: HttpClient,
+    // The server.
+    host: Host,
+    // The retry policy of idempotent requests. No retries if None.
+    retry: Option<RetryPolicy>,
+    // The client-side rate limiter. No limits if None.
+    limiter: Option<RateLimiter>,
+    // The default headers sent on each request.
+    headers: HeaderMap,
+    // The authentication credentials. Optional.
+    auth: Option<Auth>,
+    // The underlying transport.
+    transport: Box<dyn Transport>,
+    // If true, the write requests are described instead of being sent.
+    dry_run: bool,
 }
 
 /// Generates common OFF Urls.
 ///
 /// This trait provides the default implementations. Concrete types need only to
-/// implement the host_with_locale() method.
+/// implement the host() and locale() methods.
 pub(crate) trait Urls {
     /// Return the base URL with the given locale or the default locale if
     /// none given.
@@ -55,9 +89,31 @@ pub(crate) trait Urls {
         base.join("cgi/")
     }
 
+    /// Append the 'cc' and 'lc' query parameters selecting the given locale, or the
+    /// default locale if none given, if the host does not support locale subdomains.
+    fn localize(&self, url: Url, locale: Option<&Locale>) -> Url {
+        let params = self
+            .host()
+            .locale_params(locale.unwrap_or_else(|| self.locale()));
+        with_params(url, Some(&params))
+    }
+
     // Return the base URL with the given locale. If locale is None, return the
     // client's default locale.
-    fn host_with_locale(&self, locale: Option<&Locale>) -> std::result::Result<Url, ParseError>;
+    fn host_with_locale(&self, locale: Option<&Locale>) -> std::result::Result<Url, ParseError> {
+        self.host().url(locale.unwrap_or_else(|| self.locale()))
+    }
+
+    /// Return the database flavor.
+    fn flavor(&self) -> Flavor {
+        self.host().flavor()
+    }
+
+    // Return the server.
+    fn host(&self) -> &Host;
+
+    // Return the default locale.
+    fn locale(&self) -> &Locale;
 }
 
 /// Generate versioned API URLs.
@@ -74,12 +130,399 @@ pub(crate) trait ApiUrl: Version + Urls {
 pub(crate) trait SearchUrl: ApiUrl {
     /// Return the versioned search URL.
     fn search_url(&self, locale: Option<&Locale>) -> std::result::Result<Url, ParseError>;
+
+    /// Return the search URL selecting the products given in `barcodes`, a string
+    /// of comma-separated product barcodes.
+    fn products_url(
+        &self,
+        barcodes: &str,
+        output: Option<Output>,
+    ) -> std::result::Result<Url, ParseError> {
+        // Borrow output and extract Option<&Locale>
+        let locale = output.as_ref().and_then(|o| o.locale.as_ref());
+        let url = self.search_url(locale)?;
+        let mut params = Params::new();
+        params.push(("code", String::from(barcodes)));
+        if let Some(output_params) = output.as_ref().map(|o| o.params(&["fields"])) {
+            params.extend(output_params);
+        }
+        Ok(self.localize(with_params(url, Some(&params)), locale))
+    }
+}
+
+/// Generates the full request URLs, query parameters included, of the endpoints
+/// shared by all API versions.
+///
+/// Both the blocking and the async clients build their requests with this trait,
+/// so that they always target identical endpoints.
+pub(crate) trait EndpointUrls: ApiUrl {
+    /// `https://world.openfoodfacts.org/data/taxonomies/{taxonomy}.json`
+    fn taxonomy_url(&self, taxonomy: &str) -> std::result::Result<Url, ParseError> {
+        let base_url = self.base_url_world()?; // force world locale.
+        base_url.join(&format!("data/taxonomies/{}.json", taxonomy))
+    }
+
+    /// `https://{locale}.openfoodfacts.org/{facet}.json`
+    fn facet_url(
+        &self,
+        facet: &str,
+        output: Option<Output>,
+    ) -> std::result::Result<Url, ParseError> {
+        // Borrow output and extract Option<&Locale>
+        let locale = output.as_ref().and_then(|o| o.locale.as_ref());
+        let base_url = self.base_url(locale)?;
+        let url = base_url.join(&format!("{}.json", facet))?;
+        let params = output
+            .as_ref()
+            .map(|o| o.params(&["page", "page_size", "fields", "nocache"]));
+        Ok(self.localize(with_params(url, params.as_ref()), locale))
+    }
+
+    /// `https://{locale}.openfoodfacts.org/categories.json`
+    fn categories_url(&self, output: Option<Output>) -> std::result::Result<Url, ParseError> {
+        let locale = output.as_ref().and_then(|o| o.locale.as_ref());
+        let base_url = self.base_url(locale)?;
+        Ok(self.localize(base_url.join("categories.json")?, locale))
+    }
+
+    /// `https://{locale}.openfoodfacts.org/cgi/nutrients.pl`
+    fn nutrients_url(&self, output: Option<Output>) -> std::result::Result<Url, ParseError> {
+        let locale = output.as_ref().and_then(|o| o.locale.as_ref());
+        let cgi_url = self.cgi_url(locale)?;
+        Ok(self.localize(cgi_url.join("nutrients.pl")?, locale))
+    }
+
+    /// `https://{locale}.openfoodfacts.org/{facet}/{value}.json`
+    fn products_by_url(
+        &self,
+        what: &str,
+        id: &str,
+        output: Option<Output>,
+    ) -> std::result::Result<Url, ParseError> {
+        let locale = output.as_ref().and_then(|o| o.locale.as_ref());
+        let base_url = self.base_url(locale)?;
+        let url = base_url.join(&format!("{}/{}.json", what, id))?;
+        let params = output
+            .as_ref()
+            .map(|o| o.params(&["page", "page_size", "fields"]));
+        Ok(self.localize(with_params(url, params.as_ref()), locale))
+    }
+
+    /// `https://world.openfoodfacts.org/cgi/session.pl`
+    fn session_url(&self) -> std::result::Result<Url, ParseError> {
+        let cgi_url = self.cgi_url(Some(&Locale::default()))?; // force world locale.
+        cgi_url.join("session.pl")
+    }
+
+    /// `https://{locale}.openfoodfacts.org/cgi/{script}`, the URLs of the write
+    /// API.
+    fn write_url(&self, script: &str) -> std::result::Result<Url, ParseError> {
+        let cgi_url = self.cgi_url(None)?;
+        Ok(self.localize(cgi_url.join(script)?, None))
+    }
+
+    /// `https://{locale}.openfoodfacts.org/api/{version}/product/{barcode}`
+    fn product_url(
+        &self,
+        barcode: &str,
+        output: Option<Output>,
+    ) -> std::result::Result<Url, ParseError> {
+        let locale = output.as_ref().and_then(|o| o.locale.as_ref());
+        let api_url = self.api_url(locale)?;
+        let url = api_url.join(&format!("product/{}", barcode))?;
+        let params = output.as_ref().map(|o| o.params(&["fields"]));
+        Ok(self.localize(with_params(url, params.as_ref()), locale))
+    }
+}
+
+/// Generates the URLs of a client for the public server of another flavor.
+pub(crate) struct FlavorUrls<'a, C> {
+    client: &'a C,
+    host: Host,
+}
+
+impl<'a, C> FlavorUrls<'a, C>
+where
+    C: Urls,
+{
+    pub(crate) fn new(client: &'a C, flavor: Flavor) -> Self {
+        Self {
+            client,
+            host: client.host().with_flavor(flavor),
+        }
+    }
+}
+
+impl<C> Version for FlavorUrls<'_, C>
+where
+    C: Version,
+{
+    fn version(&self) -> &str {
+        self.client.version()
+    }
+}
+
+impl<C> Urls for FlavorUrls<'_, C>
+where
+    C: Urls,
+{
+    fn host(&self) -> &Host {
+        &self.host
+    }
+
+    fn locale(&self) -> &Locale {
+        self.client.locale()
+    }
+}
+
+impl<C> ApiUrl for FlavorUrls<'_, C> where C: Version + Urls {}
+
+impl<C> EndpointUrls for FlavorUrls<'_, C> where C: Version + Urls {}
+
+/// Returns all the flavors, starting with the given one.
+pub(crate) fn flavors_from(first: Flavor) -> impl Iterator<Item = Flavor> {
+    std::iter::once(first).chain(Flavor::ALL.iter().copied().filter(move |f| *f != first))
+}
+
+/// Returns the image URLs of the given product on the images server of the host.
+pub(crate) fn product_images<'a>(
+    host: &Host,
+    product: &'a Product,
+) -> std::result::Result<ProductImages<'a>, Error> {
+    let images = ProductImages::new(host.images_url()?, &product.code, &product.images)?;
+    Ok(match product.lang.as_deref() {
+        Some(lang) if !lang.is_empty() => images.main_lang(lang),
+        _ => images,
+    })
+}
+
+/// Returns [Error::Validation] if the given host is not a public server, whose
+/// sibling databases are known.
+pub(crate) fn validate_public_host(host: &Host) -> std::result::Result<(), Error> {
+    if host.is_custom() {
+        Err(Error::Validation(String::from(
+            "the flavor lookup needs a public server, not a custom host",
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the form of the given product edit, with the credentials, if any.
+pub(crate) fn edit_form<'a>(
+    edit: &'a ProductEdit,
+    auth: Option<&'a Auth>,
+) -> std::result::Result<Vec<(&'a str, &'a str)>, Error> {
+    validate_barcode(edit.code())?;
+    Ok(with_credentials(edit.form(), auth))
+}
+
+/// Returns the JSON body of the given product update, with the credentials, if
+/// any.
+pub(crate) fn update_body(
+    update: &ProductUpdate,
+    auth: Option<&Auth>,
+) -> std::result::Result<Value, Error> {
+    validate_barcode(update.code())?;
+    let mut body = update.to_json();
+    if let Some(auth) = auth {
+        for (name, value) in auth.credentials().iter() {
+            body[*name] = Value::from(*value);
+        }
+    }
+    Ok(body)
+}
+
+/// Returns the API V3 envelope held by the body of an unsuccessful response, or
+/// the given error if the body is not an envelope.
+pub(crate) fn v3_envelope<P: DeserializeOwned>(
+    error: Error,
+) -> std::result::Result<ProductResponseV3<P>, Error> {
+    match error {
+        Error::Status { code, ref body, .. } if (400..500).contains(&code) => {
+            serde_json::from_str(body).map_err(|_| error)
+        }
+        e => Err(e),
+    }
+}
+
+/// Returns the multipart request uploading the given image, with the
+/// credentials, if any.
+pub(crate) fn upload_request(
+    url: Url,
+    upload: &ImageUpload,
+    auth: Option<&Auth>,
+) -> std::result::Result<HttpRequest, Error> {
+    validate_barcode(upload.code())?;
+    let form = with_credentials(upload.form(), auth);
+    Ok(multipart_request(url, &form, upload.file()))
+}
+
+/// Returns the form selecting the given crop of an image, with the credentials,
+/// if any.
+pub(crate) fn crop_form(
+    crop: &ImageCrop,
+    auth: Option<&Auth>,
+) -> std::result::Result<Vec<(&'static str, String)>, Error> {
+    validate_barcode(crop.code())?;
+    crop.validate()?;
+    let mut form = crop.form();
+    if let Some(auth) = auth {
+        form.extend(auth.credentials().iter().map(|&(n, v)| (n, v.to_string())));
+    }
+    Ok(form)
+}
+
+/// Returns the form unselecting the given image, with the credentials, if any.
+pub(crate) fn unselect_form<'a>(
+    barcode: &'a str,
+    imagefield: &'a str,
+    auth: Option<&'a Auth>,
+) -> std::result::Result<Vec<(&'a str, &'a str)>, Error> {
+    validate_barcode(barcode)?;
+    let form = vec![("code", barcode), ("id", imagefield)];
+    Ok(with_credentials(form, auth))
+}
+
+/// Appends the credentials, if any, to the given form.
+fn with_credentials<'a>(
+    mut form: Vec<(&'a str, &'a str)>,
+    auth: Option<&'a Auth>,
+) -> Vec<(&'a str, &'a str)> {
+    if let Some(auth) = auth {
+        form.extend(auth.credentials().iter().copied());
+    }
+    form
+}
+
+/// Returns true if the JSON body of a product API response holds a product.
+pub(crate) fn has_product(json: &Value) -> bool {
+    json["status"].as_u64() == Some(1)
+        || matches!(
+            json["status"].as_str(),
+            Some("success") | Some("success_with_warnings")
+        )
 }
 
-/// OFF request methods. At present, only GET is implemented.
+/// Returns the given response if its status is successful, otherwise the
+/// matching error.
+fn check_status(response: HttpResponse) -> Result {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let retry_after = response
+        .headers()
+        .get(http::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
+    let body = response.text().unwrap_or_default();
+    Err(Error::from_status(
+        status.as_u16(),
+        retry_after.as_deref(),
+        body,
+    ))
+}
+
+/// Returns a POST request sending the given form.
+pub(crate) fn form_request(url: Url, form: &[(&str, &str)]) -> HttpRequest {
+    let mut request = HttpRequest::new(Method::POST, url);
+    request.headers.insert(
+        http::header::CONTENT_TYPE,
+        http::HeaderValue::from_static("application/x-www-form-urlencoded"),
+    );
+    request.body = serde_urlencoded::to_string(form)
+        .unwrap_or_default()
+        .into_bytes();
+    request
+}
+
+/// Returns a request sending the given JSON body.
+pub(crate) fn json_request(method: Method, url: Url, body: &Value) -> HttpRequest {
+    let mut request = HttpRequest::new(method, url);
+    request.headers.insert(
+        http::header::CONTENT_TYPE,
+        http::HeaderValue::from_static("application/json"),
+    );
+    request.body = body.to_string().into_bytes();
+    request
+}
+
+/// Returns a POST request sending the given form and file as
+/// `multipart/form-data`. The file is given by its form field name, file name,
+/// content type and contents.
+pub(crate) fn multipart_request(
+    url: Url,
+    form: &[(&str, &str)],
+    file: (String, &str, &str, &[u8]),
+) -> HttpRequest {
+    let boundary = format!("------------------------{:016x}", fastrand::u64(..));
+    let mut body = Vec::new();
+    for (name, value) in form {
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                boundary, name, value
+            )
+            .as_bytes(),
+        );
+    }
+    let (name, file_name, content_type, bytes) = file;
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\
+             Content-Type: {}\r\n\r\n",
+            boundary,
+            name,
+            file_name.replace('"', "%22"),
+            content_type
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(bytes);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+    let mut request = HttpRequest::new(Method::POST, url);
+    let content_type = format!("multipart/form-data; boundary={}", boundary);
+    if let Ok(value) = http::HeaderValue::from_str(&content_type) {
+        request.headers.insert(http::header::CONTENT_TYPE, value);
+    }
+    request.body = body;
+    request
+}
+
+/// Adds the given default headers to the request, unless already set.
+pub(crate) fn with_headers(mut request: HttpRequest, headers: &HeaderMap) -> HttpRequest {
+    for (name, value) in headers {
+        if !request.headers.contains_key(name) {
+            request.headers.insert(name, value.clone());
+        }
+    }
+    request
+}
+
+/// Returns the [Error::DryRun] describing the given request.
+pub(crate) fn dry_run_error(request: HttpRequest) -> Error {
+    Error::DryRun(Box::new(PreparedRequest::new(&request)))
+}
+
+/// Appends the given query parameters, if any, to the URL.
+pub(crate) fn with_params(mut url: Url, params: Option<&Params>) -> Url {
+    if let Some(p) = params.filter(|p| !p.is_empty()) {
+        url.query_pairs_mut().extend_pairs(p);
+    }
+    url
+}
+
+/// OFF request methods.
 pub trait RequestMethods {
-    /// Build and send a GET request.
+    /// Build and send a GET request. Unsuccessful HTTP statuses are returned as
+    /// errors.
     fn get(&self, url: Url, params: Option<&Params>) -> Result;
+
+    /// Build and send a POST request with the given form. Unsuccessful HTTP
+    /// statuses are returned as errors.
+    fn post(&self, url: Url, form: &[(&str, &str)]) -> Result;
 }
 
 impl<V> Version for OffClient<V>
@@ -95,28 +538,82 @@ impl<V> Urls for OffClient<V>
 where
     V: Version,
 {
-    /// Returns the base URL with the given locale. If locale is None, return the
-    /// client's default locale.
-    fn host_with_locale(&self, locale: Option<&Locale>) -> std::result::Result<Url, ParseError> {
-        let url = format!(
-            "https://{}.openfoodfacts.org/",
-            locale.map_or(self.locale.to_string(), |l| l.to_string())
-        );
-        Url::parse(&url)
+    fn host(&self) -> &Host {
+        &self.host
+    }
+
+    fn locale(&self) -> &Locale {
+        &self.locale
     }
 }
 
 impl<V> ApiUrl for OffClient<V> where V: Version {}
 
+impl<V> EndpointUrls for OffClient<V> where V: Version {}
+
+impl<V> OffClient<V> {
+    /// Returns true if the client is in dry-run mode. See
+    /// [crate::OffBuilder::dry_run].
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    // Sends a request once, with the default headers. Unsuccessful HTTP
+    // statuses are returned as errors.
+    fn send(&self, request: HttpRequest) -> Result {
+        check_status(self.transport.send(with_headers(request, &self.headers))?)
+    }
+
+    // Sends a write request once, after waiting for the write quota of the
+    // rate limits, if any. In dry-run mode, returns the description of the
+    // request instead.
+    fn send_write(&self, request: HttpRequest) -> Result {
+        if self.dry_run {
+            return Err(dry_run_error(with_headers(request, &self.headers)));
+        }
+        if let Some(limiter) = &self.limiter {
+            let delay = limiter.reserve(EndpointClass::Write)?;
+            if !delay.is_zero() {
+                std::thread::sleep(delay);
+            }
+        }
+        self.send(request)
+    }
+}
+
 impl<V> RequestMethods for OffClient<V> {
-    /// Builds and send a GET request.
+    /// Builds and send a GET request. Failed requests are retried according to
+    /// the client's retry policy. Each attempt waits for the client's rate
+    /// limits, if any.
     fn get(&self, url: Url, params: Option<&Params>) -> Result {
-        let mut rb = self.client.get(url);
-        if let Some(p) = params {
-            rb = rb.query(p);
+        let class = EndpointClass::of_read(&url);
+        let mut attempt = 1;
+        loop {
+            if let Some(limiter) = &self.limiter {
+                let delay = limiter.reserve(class)?;
+                if !delay.is_zero() {
+                    std::thread::sleep(delay);
+                }
+            }
+            let request = HttpRequest::new(Method::GET, with_params(url.clone(), params));
+            let result = self.send(request);
+            let delay = match result {
+                Err(ref e) => self.retry.as_ref().and_then(|r| r.delay(attempt, e)),
+                Ok(_) => None,
+            };
+            match delay {
+                Some(delay) => std::thread::sleep(delay),
+                None => return result,
+            }
+            attempt += 1;
         }
-        let response = rb.send()?;
-        Ok(response)
+    }
+
+    /// Builds and sends a POST request, once: write requests are not
+    /// idempotent. Waits for the write quota of the client's rate limits, if
+    /// any.
+    fn post(&self, url: Url, form: &[(&str, &str)]) -> Result {
+        self.send_write(form_request(url, form))
     }
 }
 
@@ -150,13 +647,18 @@ where
     ///     - nova_groups (*)
     ///     - nutrient_levels (*)
     ///     - states
+    ///
     /// (*) Only taxonomy. There is no facet equivalent.
     pub fn taxonomy(&self, taxonomy: &str) -> Result {
-        let base_url = self.base_url_world()?; // force world locale.
-        let url = base_url.join(&format!("data/taxonomies/{}.json", taxonomy))?;
+        let url = self.taxonomy_url(taxonomy)?;
         self.get(url, None)
     }
 
+    /// Gets the given taxonomy as a graph. See [OffClient::taxonomy].
+    pub fn taxonomy_response(&self, taxonomy: &str) -> std::result::Result<Taxonomy, Error> {
+        self.taxonomy(taxonomy)?.json()
+    }
+
     /// Gets the given facet.
     ///
     /// # OFF API request
@@ -179,15 +681,23 @@ where
     ///     - states
     ///     - stores
     ///     - traces
-    ///     The name may be given in english or localized, i.e. additives (world), additifs (fr).
+    ///
+    ///   The name may be given in english or localized, i.e. additives (world), additifs (fr).
+    ///   See [Facet] for the names of the facet types.
     /// * output - Optional output parameters. This call supports only the locale,
-    ///     pagination, fields and nocache parameters.
+    ///   pagination, fields and nocache parameters.
     pub fn facet(&self, facet: &str, output: Option<Output>) -> Result {
-        // Borrow output and extract Option<&Locale>
-        let base_url = self.base_url(output.as_ref().and_then(|o| o.locale.as_ref()))?;
-        let url = base_url.join(&format!("{}.json", facet))?;
-        let params = output.map(|o| o.params(&["page", "page_size", "fields", "nocache"]));
-        self.get(url, params.as_ref())
+        let url = self.facet_url(facet, output)?;
+        self.get(url, None)
+    }
+
+    /// Gets the given facet, deserialized. See [OffClient::facet].
+    pub fn facet_response(
+        &self,
+        facet: Facet,
+        output: Option<Output>,
+    ) -> std::result::Result<FacetResponse, Error> {
+        self.facet(facet.plural(), output)?.json()
     }
 
     /// Gets all the categories.
@@ -200,8 +710,7 @@ where
     ///
     /// * output - Optional output parameters. This call supports only the locale parameter.
     pub fn categories(&self, output: Option<Output>) -> Result {
-        let base_url = self.base_url(output.as_ref().and_then(|o| o.locale.as_ref()))?;
-        let url = base_url.join("categories.json")?;
+        let url = self.categories_url(output)?;
         self.get(url, None)
     }
 
@@ -216,8 +725,7 @@ where
     /// * output - Optional output parameter. This call supports only the locale
     ///   parameter.
     pub fn nutrients(&self, output: Option<Output>) -> Result {
-        let cgi_url = self.cgi_url(output.as_ref().and_then(|o| o.locale.as_ref()))?;
-        let url = cgi_url.join("nutrients.pl")?;
+        let url = self.nutrients_url(output)?;
         self.get(url, None)
     }
 
@@ -230,19 +738,29 @@ where
     /// # Arguments
     ///
     /// * what - A facet name or "category". The facet name is always the singular name
-    ///     of the face type name (i.e. brands -> brand, entry-dates -> entry-date, etc).
-    ///     The facet name or the "category" literal may be given either in english or
-    ///     localized, i.e. additives (world), additifs (fr), category (world), categorie (fr).
+    ///   of the face type name (i.e. brands -> brand, entry-dates -> entry-date, etc).
+    ///   The facet name or the "category" literal may be given either in english or
+    ///   localized, i.e. additives (world), additifs (fr), category (world), categorie (fr).
+    ///   See [Facet::singular].
     /// * id - The localized id of the facet or category. The IDs are returned by calls
-    ///     to the corresponding `facet(<facet_type>)` or `categories()` endpoint. For example,
-    ///     the IDs for the `entry-date` facet are returned by the call `facet("entry-dates")`.
+    ///   to the corresponding `facet(<facet_type>)` or `categories()` endpoint. For example,
+    ///   the IDs for the `entry-date` facet are returned by the call `facet("entry-dates")`.
     /// * output - Optional output parameters. This call supports the locale, pagination
-    ///     and fields parameters.
+    ///   and fields parameters.
     pub fn products_by(&self, what: &str, id: &str, output: Option<Output>) -> Result {
-        let base_url = self.base_url(output.as_ref().and_then(|o| o.locale.as_ref()))?;
-        let url = base_url.join(&format!("{}/{}.json", what, id))?;
-        let params = output.map(|o| o.params(&["page", "page_size", "fields"]));
-        self.get(url, params.as_ref())
+        let url = self.products_by_url(what, id, output)?;
+        self.get(url, None)
+    }
+
+    /// Gets all products for the given facet or category, deserialized. See
+    /// [OffClient::products_by].
+    pub fn products_by_response<P: DeserializeOwned>(
+        &self,
+        what: &str,
+        id: &str,
+        output: Option<Output>,
+    ) -> std::result::Result<SearchResponse<P>, Error> {
+        self.products_by(what, id, output)?.json()
     }
 
     // ------------------------------------------------------------------------
@@ -259,20 +777,246 @@ where
     ///
     /// * barcode - The product barcode.
     /// * output - Optional output parameters. This call only supports the locale
-    ///     and fields parameters.
+    ///   and fields parameters.
     pub fn product(&self, barcode: &str, output: Option<Output>) -> Result {
-        let api_url = self.api_url(output.as_ref().and_then(|o| o.locale.as_ref()))?;
-        let url = api_url.join(&format!("product/{}", barcode))?;
-        let params = output.map(|o| o.params(&["fields"]));
-        self.get(url, params.as_ref())
+        validate_barcode(barcode)?;
+        let url = self.product_url(barcode, output)?;
+        self.get(url, None).map_err(|e| e.for_product(barcode))
     }
 
-    pub(crate) fn new(v: V, locale: Locale, client: HttpClient) -> Self {
-        Self { v, locale, client }
+    /// Looks up the given product in the databases of all flavors, starting with
+    /// the client's flavor, and returns the flavor of the first database having it.
+    /// Returns None if no database has the product.
+    ///
+    /// Returns [Error::Validation] if the client targets a custom host, with an
+    /// explicit domain, port or URL template: the databases of the other flavors
+    /// are only known on the public servers.
+    ///
+    /// # OFF API request
+    ///
+    /// `GET https://{locale}.{flavor domain}/api/{version}/product/{barcode}?fields=code`
+    /// for each flavor until the product is found.
+    pub fn find_flavor(&self, barcode: &str) -> std::result::Result<Option<Flavor>, Error> {
+        validate_barcode(barcode)?;
+        validate_public_host(self.host())?;
+        for flavor in flavors_from(self.flavor()) {
+            let output = Output::new().fields("code");
+            let url = FlavorUrls::new(self, flavor).product_url(barcode, Some(output))?;
+            match self.get(url, None) {
+                Ok(response) => {
+                    if has_product(&serde_json::from_str::<Value>(&response.text()?)?) {
+                        return Ok(Some(flavor));
+                    }
+                }
+                Err(Error::Status { code: 404, .. }) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the image URLs of the given product, on the images server of the
+    /// client's host and flavor, i.e. `https://images.openfoodfacts.org/`. The
+    /// main language of the product is the fallback of the preferred language.
+    ///
+    /// Returns [Error::Validation] if the product has no valid barcode.
+    pub fn product_images<'a>(
+        &self,
+        product: &'a Product,
+    ) -> std::result::Result<ProductImages<'a>, Error> {
+        product_images(self.host(), product)
+    }
+
+    // ------------------------------------------------------------------------
+    // Write
+    // ------------------------------------------------------------------------
+
+    /// Sends the given product edit, creating the product if it does not exist.
+    /// The credentials given to the builder, if any, are sent in the form.
+    ///
+    /// Returns [Error::Validation] if the barcode of the edit is invalid.
+    ///
+    /// # OFF API request
+    ///
+    /// `POST https://{locale}.openfoodfacts.org/cgi/product_jqm2.pl`
+    pub fn edit_product(&self, edit: &ProductEdit) -> Result {
+        let form = edit_form(edit, self.auth.as_ref())?;
+        self.post(self.write_url(PRODUCT_EDIT)?, &form)
+    }
+
+    /// Sends the given product edit and returns the status of the write API.
+    /// The server answers unsaved edits with a successful HTTP status: check
+    /// [EditResponse::is_saved].
+    pub fn edit_product_response(
+        &self,
+        edit: &ProductEdit,
+    ) -> std::result::Result<EditResponse, Error> {
+        self.edit_product(edit)?.json()
+    }
+
+    /// Uploads the given image. The image is selected as the `imagefield` of the
+    /// upload, uncropped, unless the field already has a selected image.
+    ///
+    /// Returns [Error::Validation] if the barcode of the upload is invalid.
+    ///
+    /// # OFF API request
+    ///
+    /// `POST https://{locale}.openfoodfacts.org/cgi/product_image_upload.pl`
+    pub fn upload_image(&self, upload: &ImageUpload) -> Result {
+        let url = self.write_url(IMAGE_UPLOAD)?;
+        self.send_write(upload_request(url, upload, self.auth.as_ref())?)
+    }
+
+    /// Uploads the given image and returns the id of the new image, or the
+    /// error, i.e. for an image already uploaded. See [ImageResponse::is_ok].
+    pub fn upload_image_response(
+        &self,
+        upload: &ImageUpload,
+    ) -> std::result::Result<ImageResponse, Error> {
+        self.upload_image(upload)?.json()
+    }
+
+    /// Selects an uploaded image, cropped, rotated and filtered as given.
+    ///
+    /// Returns [Error::Validation] if the barcode of the crop is invalid.
+    ///
+    /// # OFF API request
+    ///
+    /// `POST https://{locale}.openfoodfacts.org/cgi/product_image_crop.pl`
+    pub fn crop_image(&self, crop: &ImageCrop) -> Result {
+        let form = crop_form(crop, self.auth.as_ref())?;
+        let form: Vec<(&str, &str)> = form.iter().map(|(n, v)| (*n, v.as_str())).collect();
+        self.post(self.write_url(IMAGE_CROP)?, &form)
+    }
+
+    /// Selects an uploaded image and returns the selected image.
+    pub fn crop_image_response(
+        &self,
+        crop: &ImageCrop,
+    ) -> std::result::Result<ImageResponse, Error> {
+        self.crop_image(crop)?.json()
+    }
+
+    /// Unselects the given selected image, i.e. `front_fr`. The uploaded image
+    /// is kept.
+    ///
+    /// Returns [Error::Validation] if the barcode is invalid.
+    ///
+    /// # OFF API request
+    ///
+    /// `POST https://{locale}.openfoodfacts.org/cgi/product_image_unselect.pl`
+    pub fn unselect_image(&self, barcode: &str, imagefield: &str) -> Result {
+        let form = unselect_form(barcode, imagefield, self.auth.as_ref())?;
+        self.post(self.write_url(IMAGE_UNSELECT)?, &form)
+    }
+
+    /// Unselects the given selected image and returns the status.
+    pub fn unselect_image_response(
+        &self,
+        barcode: &str,
+        imagefield: &str,
+    ) -> std::result::Result<ImageResponse, Error> {
+        self.unselect_image(barcode, imagefield)?.json()
+    }
+
+    /// Applies the rows of the given bulk edit, in order. For each row, the
+    /// current product is read and only the fields whose value differs are
+    /// sent in a [ProductEdit]; rows without changes are not sent. The requests
+    /// wait for the client's rate limits, if any.
+    ///
+    /// The rows that cannot be applied, i.e. with an invalid barcode, are
+    /// reported as failed. The run stops with the error on transport errors,
+    /// server errors (5xx statuses) and rate limiting: the rows processed
+    /// before are saved in the checkpoint file, if any, and skipped by the next
+    /// run. In dry-run mode, the edits are reported with their request and the
+    /// checkpoint is not saved.
+    ///
+    /// Returns [Error::Io] if the checkpoint or report file cannot be read or
+    /// written, and [Error::Validation] if the checkpoint does not match the
+    /// rows.
+    ///
+    /// ```no_run
+    /// use openfoodfacts::{self as off, BulkEdit};
+    ///
+    /// # fn main() -> Result<(), off::Error> {
+    /// let client = off::v2().auth("user", "password").build()?;
+    /// let bulk = BulkEdit::load("fixes.csv")?
+    ///     .checkpoint("fixes.checkpoint")
+    ///     .report("fixes.report.csv");
+    /// let report = client.bulk_edit(&bulk)?;
+    /// println!("{} saved, {} unchanged", report.count("saved"), report.count("unchanged"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn bulk_edit(&self, bulk: &BulkEdit) -> std::result::Result<BulkReport, Error> {
+        bulk.run(self)
+    }
+
+    /// Opens a session with the credentials given to the builder. The session
+    /// cookie is kept by the client and sent with the later requests, i.e.
+    /// write operations.
+    ///
+    /// Returns [Error::Validation] if the builder had no credentials and
+    /// [Error::Authentication] if the server did not open a session.
+    ///
+    /// # OFF API request
+    ///
+    /// `POST https://world.openfoodfacts.org/cgi/session.pl`
+    pub fn login(&self) -> std::result::Result<(), Error> {
+        let auth = self
+            .auth
+            .as_ref()
+            .ok_or_else(|| Error::Validation(String::from("no credentials")))?;
+        let response = self.post(self.session_url()?, &auth.login_form())?;
+        if has_session_cookie(response.headers()) {
+            Ok(())
+        } else {
+            Err(Error::Authentication(format!(
+                "no session opened for user '{}'",
+                auth.username
+            )))
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        v: V,
+        locale: Locale,
+        host: Host,
+        retry: Option<RetryPolicy>,
+        limiter: Option<RateLimiter>,
+        headers: HeaderMap,
+        auth: Option<Auth>,
+        transport: Box<dyn Transport>,
+        dry_run: bool,
+    ) -> Self {
+        Self {
+            v,
+            locale,
+            host,
+            retry,
+            limiter,
+            headers,
+            auth,
+            transport,
+            dry_run,
+        }
     }
 }
 
 impl OffClient<V0> {
+    /// Gets the given product, deserialized. See [OffClient::product].
+    ///
+    /// The server answers with a status 0 and no product for unknown products,
+    /// unless it gives a 404 status, reported as [Error::ProductNotFound].
+    pub fn product_response(
+        &self,
+        barcode: &str,
+        output: Option<Output>,
+    ) -> std::result::Result<ProductResponse, Error> {
+        self.product(barcode, output)?.json()
+    }
+
     /// Returns the query builder for API V0.
     pub fn query(&self) -> SearchQueryV0 {
         SearchQueryV0::new()
@@ -280,7 +1024,17 @@ impl OffClient<V0> {
 
     /// Sends the given search query.
     pub fn search(&self, query: SearchQueryV0, output: Option<Output>) -> Result {
-        SearchQueryV0::search(query, self, output)
+        let url = SearchQueryV0::search_url(query, self, output)?;
+        self.get(url, None)
+    }
+
+    /// Sends the given search query and deserializes the response.
+    pub fn search_response<P: DeserializeOwned>(
+        &self,
+        query: SearchQueryV0,
+        output: Option<Output>,
+    ) -> std::result::Result<SearchResponse<P>, Error> {
+        self.search(query, output)?.json()
     }
 }
 
@@ -295,6 +1049,18 @@ impl SearchUrl for OffClient<V0> {
 }
 
 impl OffClient<V2> {
+    /// Gets the given product, deserialized. See [OffClient::product].
+    ///
+    /// The server answers with a status 0 and no product for unknown products,
+    /// unless it gives a 404 status, reported as [Error::ProductNotFound].
+    pub fn product_response(
+        &self,
+        barcode: &str,
+        output: Option<Output>,
+    ) -> std::result::Result<ProductResponse, Error> {
+        self.product(barcode, output)?.json()
+    }
+
     /// Returns the query builder for API V2.
     pub fn query(&self) -> SearchQueryV2 {
         SearchQueryV2::new()
@@ -302,7 +1068,17 @@ impl OffClient<V2> {
 
     /// Sends the search query.
     pub fn search(&self, query: SearchQueryV2, output: Option<Output>) -> Result {
-        SearchQueryV2::search(query, self, output)
+        let url = SearchQueryV2::search_url(query, self, output)?;
+        self.get(url, None)
+    }
+
+    /// Sends the search query and deserializes the response.
+    pub fn search_response<P: DeserializeOwned>(
+        &self,
+        query: SearchQueryV2,
+        output: Option<Output>,
+    ) -> std::result::Result<SearchResponse<P>, Error> {
+        self.search(query, output)?.json()
     }
 
     /// Gets the products given in the `barcodes` list as a string of comma-separated
@@ -314,14 +1090,20 @@ impl OffClient<V2> {
     ///
     /// TODO: Support iterator (FromIter ?)
     pub fn products(&self, barcodes: &str, output: Option<Output>) -> Result {
-        // Borrow output and extract Option<&Locale>
-        let url = self.search_url(output.as_ref().and_then(|o| o.locale.as_ref()))?;
-        let mut params = Params::new();
-        params.push(("code", String::from(barcodes)));
-        if let Some(output_params) = output.map(|o| o.params(&["fields"])) {
-            params.extend(output_params);
+        for barcode in barcodes.split(',') {
+            validate_barcode(barcode)?;
         }
-        self.get(url, Some(&params))
+        let url = self.products_url(barcodes, output)?;
+        self.get(url, None)
+    }
+
+    /// Gets the given products, deserialized. See [OffClient::products].
+    pub fn products_response<P: DeserializeOwned>(
+        &self,
+        barcodes: &str,
+        output: Option<Output>,
+    ) -> std::result::Result<SearchResponse<P>, Error> {
+        self.products(barcodes, output)?.json()
     }
 }
 
@@ -336,6 +1118,52 @@ impl SearchUrl for OffClient<V2> {
     }
 }
 
+impl OffClient<V3> {
+    /// Gets the given product in the API V3 envelope. The errors of the
+    /// request, i.e. an invalid barcode, are returned in the envelope,
+    /// except for unknown products, reported as [Error::ProductNotFound].
+    ///
+    /// # OFF API request
+    ///
+    /// `GET https://{locale}.openfoodfacts.org/api/v3/product/{barcode}`
+    pub fn product_response<P: DeserializeOwned>(
+        &self,
+        barcode: &str,
+        output: Option<Output>,
+    ) -> std::result::Result<ProductResponseV3<P>, Error> {
+        match self.product(barcode, output) {
+            Ok(response) => response.json(),
+            Err(e) => v3_envelope(e),
+        }
+    }
+
+    /// Sends the given product update as a JSON body. The credentials given to
+    /// the builder, if any, are sent in the body.
+    ///
+    /// Returns [Error::Validation] if the barcode of the update is invalid.
+    ///
+    /// # OFF API request
+    ///
+    /// `PATCH https://{locale}.openfoodfacts.org/api/v3/product/{barcode}`
+    pub fn update_product(&self, update: &ProductUpdate) -> Result {
+        let body = update_body(update, self.auth.as_ref())?;
+        let url = self.product_url(update.code(), None)?;
+        self.send_write(json_request(Method::PATCH, url, &body))
+    }
+
+    /// Sends the given product update and returns the API V3 envelope, with the
+    /// errors and warnings of the update.
+    pub fn update_product_response<P: DeserializeOwned>(
+        &self,
+        update: &ProductUpdate,
+    ) -> std::result::Result<ProductResponseV3<P>, Error> {
+        match self.update_product(update) {
+            Ok(response) => response.json(),
+            Err(e) => v3_envelope(e),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests_client {
     use super::*;
@@ -347,6 +1175,9 @@ mod tests_client {
 
         let client_v2 = crate::v2().build().unwrap();
         assert_eq!(client_v2.version(), "v2");
+
+        let client_v3 = crate::v3().build().unwrap();
+        assert_eq!(client_v3.version(), "v3");
     }
 
     #[test]
@@ -390,6 +1221,112 @@ mod tests_client {
             "https://gr.openfoodfacts.org/cgi/"
         );
     }
+
+    #[test]
+    fn client_facet_url() {
+        let client = crate::v0().build().unwrap();
+        let output = Output::new()
+            .locale(Locale::new("fr", None))
+            .page(22)
+            .fields("url")
+            .nocache(true);
+        assert_eq!(
+            client.facet_url("brands", Some(output)).unwrap().as_str(),
+            "https://fr.openfoodfacts.org/brands.json?page=22&fields=url&nocache=true"
+        );
+        assert_eq!(
+            client.facet_url("brands", None).unwrap().as_str(),
+            "https://world.openfoodfacts.org/brands.json"
+        );
+    }
+
+    #[test]
+    fn base_url_domain() {
+        let client = crate::v0().domain("openfoodfacts.net").build().unwrap();
+        assert_eq!(
+            client
+                .base_url(Some(&Locale::new("gr", None)))
+                .unwrap()
+                .as_str(),
+            "https://gr.openfoodfacts.net/"
+        );
+    }
+
+    #[test]
+    fn flavor_urls() {
+        let client = crate::v0().flavor(Flavor::Beauty).build().unwrap();
+        assert_eq!(client.flavor(), Flavor::Beauty);
+        assert_eq!(
+            client.product_url("3600523614455", None).unwrap().as_str(),
+            "https://world.openbeautyfacts.org/api/v0/product/3600523614455"
+        );
+        assert_eq!(
+            FlavorUrls::new(&client, Flavor::PetFood)
+                .product_url("3600523614455", None)
+                .unwrap()
+                .as_str(),
+            "https://world.openpetfoodfacts.org/api/v0/product/3600523614455"
+        );
+    }
+
+    #[test]
+    fn find_flavor_custom_hosts() {
+        let staging = crate::v2()
+            .scheme("http")
+            .domain("localhost")
+            .port(8080)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            staging.find_flavor("3600523614455"),
+            Err(Error::Validation(_))
+        ));
+        let template = crate::v2()
+            .url_template("http://127.0.0.1:8000/")
+            .build()
+            .unwrap();
+        assert!(matches!(
+            template.find_flavor("3600523614455"),
+            Err(Error::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn flavors_lookup_order() {
+        assert_eq!(
+            flavors_from(Flavor::PetFood).collect::<Vec<_>>(),
+            vec![
+                Flavor::PetFood,
+                Flavor::Food,
+                Flavor::Beauty,
+                Flavor::Products
+            ]
+        );
+    }
+
+    #[test]
+    fn locale_params() {
+        let client = crate::v0()
+            .domain("openfoodfacts.net")
+            .locale_subdomains(false)
+            .locale(Locale::new("fr", Some("en")))
+            .build()
+            .unwrap();
+        assert_eq!(
+            client.categories_url(None).unwrap().as_str(),
+            "https://openfoodfacts.net/categories.json?cc=fr&lc=en"
+        );
+        let output = Output::new().locale(Locale::new("gr", None)).page(2);
+        assert_eq!(
+            client.facet_url("brands", Some(output)).unwrap().as_str(),
+            "https://openfoodfacts.net/brands.json?page=2&cc=gr"
+        );
+        // Taxonomies are static files.
+        assert_eq!(
+            client.taxonomy_url("nova_groups").unwrap().as_str(),
+            "https://openfoodfacts.net/data/taxonomies/nova_groups.json"
+        );
+    }
 }
 
 #[cfg(test)]
@@ -416,6 +1353,22 @@ mod tests_client_v0 {
             "https://gr.openfoodfacts.org/cgi/search.pl"
         );
     }
+
+    #[test]
+    fn search_url_template() {
+        let client = crate::v0()
+            .url_template("http://127.0.0.1:8080/")
+            .build()
+            .unwrap();
+        let query = client.query().criteria("brands", "contains", "Nestlé");
+        assert_eq!(
+            SearchQueryV0::search_url(query, &client, None)
+                .unwrap()
+                .as_str(),
+            "http://127.0.0.1:8080/cgi/search.pl?tagtype_1=brands&tag_contains_1=contains\
+             &tag_1=Nestl%C3%A9&action=process&json=true&cc=world"
+        );
+    }
 }
 
 #[cfg(test)]
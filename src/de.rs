@@ -0,0 +1,112 @@
+// Lenient deserializers for the loosely typed fields of the OFF responses,
+// where numbers are sometimes sent as strings.
+use serde::de::{Deserializer, Error as _};
+use serde::Deserialize;
+use serde_json::Value;
+use std::str::FromStr;
+
+/// Deserializes an optional number given either as a JSON number or as a
+/// string. Empty strings and nulls give None.
+pub(crate) fn number_or_string<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    let text = match Value::deserialize(deserializer)? {
+        Value::Null => return Ok(None),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) if s.trim().is_empty() => return Ok(None),
+        Value::String(s) => s.trim().to_string(),
+        other => {
+            return Err(D::Error::custom(format!(
+                "expected a number or a string, found {}",
+                other
+            )))
+        }
+    };
+    text.parse().map(Some).map_err(D::Error::custom)
+}
+
+/// Deserializes a number given either as a JSON number or as a string,
+/// defaulting to zero.
+pub(crate) fn number_or_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr + Default,
+    T::Err: std::fmt::Display,
+{
+    number_or_string(deserializer).map(Option::unwrap_or_default)
+}
+
+/// Deserializes a value, nulls giving the default value, i.e. an empty list.
+pub(crate) fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    Option::<T>::deserialize(deserializer).map(Option::unwrap_or_default)
+}
+
+/// Deserializes a boolean given either as a JSON boolean or as a number, 0
+/// being false. Nulls give false.
+pub(crate) fn bool_or_number<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::Null => Ok(false),
+        Value::Bool(b) => Ok(b),
+        Value::Number(n) => Ok(n.as_f64() != Some(0.0)),
+        other => Err(D::Error::custom(format!(
+            "expected a boolean or a number, found {}",
+            other
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    struct Lenient {
+        #[serde(default, deserialize_with = "number_or_string")]
+        n: Option<u32>,
+    }
+
+    fn parse(json: &str) -> Option<u32> {
+        serde_json::from_str::<Lenient>(json).unwrap().n
+    }
+
+    #[test]
+    fn lenient_numbers() {
+        assert_eq!(parse(r#"{"n": 4}"#), Some(4));
+        assert_eq!(parse(r#"{"n": "4"}"#), Some(4));
+        assert_eq!(parse(r#"{"n": ""}"#), None);
+        assert_eq!(parse(r#"{"n": null}"#), None);
+        assert_eq!(parse("{}"), None);
+        assert!(serde_json::from_str::<Lenient>(r#"{"n": "four"}"#).is_err());
+        assert!(serde_json::from_str::<Lenient>(r#"{"n": [4]}"#).is_err());
+        let mut json = serde_json::Deserializer::from_str("0");
+        assert!(!bool_or_number(&mut json).unwrap());
+        let mut json = serde_json::Deserializer::from_str("true");
+        assert!(bool_or_number(&mut json).unwrap());
+        let n: u32 = number_or_default(&mut serde_json::Deserializer::from_str("null")).unwrap();
+        assert_eq!(n, 0);
+    }
+
+    #[test]
+    fn null_defaults() {
+        #[derive(Debug, Deserialize)]
+        struct Tags {
+            #[serde(default, deserialize_with = "null_as_default")]
+            tags: Vec<String>,
+        }
+        let parse = |json| serde_json::from_str::<Tags>(json).map(|t| t.tags);
+        assert_eq!(parse(r#"{"tags": ["en:a"]}"#).unwrap(), ["en:a"]);
+        assert!(parse(r#"{"tags": null}"#).unwrap().is_empty());
+        assert!(parse("{}").unwrap().is_empty());
+        assert!(parse(r#"{"tags": "en:a"}"#).is_err());
+    }
+}
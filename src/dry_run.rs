@@ -0,0 +1,383 @@
+// The description of the write requests prepared by the clients in dry-run
+// mode. The fields are read back from the encoded body (form, multipart form or
+// JSON), so that the description shows exactly what would have been sent.
+use crate::transport::{HttpRequest, Method};
+use serde_json::Value;
+use std::fmt::{self, Display, Formatter};
+use url::Url;
+
+/// The value shown instead of the passwords and credentials.
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// The headers holding credentials or session tokens.
+const SECRET_HEADERS: [http::header::HeaderName; 4] = [
+    http::header::AUTHORIZATION,
+    http::header::PROXY_AUTHORIZATION,
+    http::header::COOKIE,
+    http::header::SET_COOKIE,
+];
+
+/// The names of the form fields holding credentials.
+const SECRET_FIELDS: [&str; 1] = ["password"];
+
+/// The value of a field of a [PreparedRequest].
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    /// A text value. The numbers and booleans of JSON bodies are given as
+    /// text.
+    Text(String),
+    /// A file of a multipart form.
+    File {
+        file_name: String,
+        content_type: String,
+        /// The size of the file, in bytes.
+        size: usize,
+    },
+}
+
+impl Display for FieldValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text(text) => write!(f, "{}", text),
+            Self::File {
+                file_name,
+                content_type,
+                size,
+            } => write!(f, "<file {}, {}, {} bytes>", file_name, content_type, size),
+        }
+    }
+}
+
+/// A write request prepared by a client in dry-run mode, returned in
+/// [crate::Error::DryRun] instead of being sent.
+///
+/// The credentials are redacted. The fields of JSON bodies are flattened, i.e.
+/// `product.packagings[0].shape`, and sorted by name: their order in the sent
+/// body may differ. The Display output is a diff-like summary:
+///
+/// ```text
+/// POST https://world.openfoodfacts.org/cgi/product_jqm2.pl
+/// authorization: <redacted>
+/// + code: 3850102123681
+/// + product_name_fr: Pâte à tartiner
+/// + user_id: user
+/// + password: <redacted>
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreparedRequest {
+    /// The request method, i.e. POST.
+    pub method: Method,
+    /// The full request URL.
+    pub url: Url,
+    /// The request headers. The sensitive ones (credentials) are redacted.
+    pub headers: Vec<(String, String)>,
+    /// The fields of the body: in the order sent for forms, sorted by name for
+    /// JSON bodies.
+    pub fields: Vec<(String, FieldValue)>,
+}
+
+impl PreparedRequest {
+    /// Describes the given request.
+    pub(crate) fn new(request: &HttpRequest) -> Self {
+        let headers = request
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if is_secret_header(name, value) {
+                    REDACTED.to_string()
+                } else {
+                    String::from_utf8_lossy(value.as_bytes()).into_owned()
+                };
+                (name.to_string(), value)
+            })
+            .collect();
+        let content_type = request
+            .headers
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        let mut fields = match content_type.strip_prefix("multipart/form-data; boundary=") {
+            Some(boundary) => multipart_fields(&request.body, boundary),
+            None if content_type == "application/json" => json_fields(&request.body),
+            None => form_fields(&request.body),
+        };
+        for (name, value) in fields.iter_mut() {
+            let leaf = name.rsplit('.').next().unwrap_or_default();
+            if SECRET_FIELDS.contains(&leaf) {
+                *value = FieldValue::Text(REDACTED.to_string());
+            }
+        }
+        Self {
+            method: request.method.clone(),
+            url: request.url.clone(),
+            headers,
+            fields,
+        }
+    }
+
+    /// Returns the value of the given field.
+    pub fn field(&self, name: &str) -> Option<&FieldValue> {
+        self.fields.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+}
+
+impl Display for PreparedRequest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} {}", self.method, self.url)?;
+        for (name, value) in &self.headers {
+            writeln!(f, "{}: {}", name, value)?;
+        }
+        for (name, value) in &self.fields {
+            writeln!(f, "+ {}: {}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns true if the given header holds credentials or a session token.
+pub(crate) fn is_secret_header(name: &http::header::HeaderName, value: &http::HeaderValue) -> bool {
+    value.is_sensitive() || SECRET_HEADERS.contains(name)
+}
+
+/// Returns the body of the given request with the credentials redacted, for
+/// the cassettes. Only form and JSON bodies are returned: the multipart bodies
+/// (image uploads) and the others are left out.
+pub(crate) fn redacted_body(request: &HttpRequest) -> Option<String> {
+    let content_type = request
+        .headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    if request.body.is_empty() {
+        None
+    } else if content_type == "application/json" {
+        let mut json = serde_json::from_slice::<Value>(&request.body).ok()?;
+        redact_json(&mut json);
+        Some(json.to_string())
+    } else if content_type == "application/x-www-form-urlencoded" {
+        let mut form = serde_urlencoded::from_bytes::<Vec<(String, String)>>(&request.body).ok()?;
+        for (name, value) in form.iter_mut() {
+            if SECRET_FIELDS.contains(&name.as_str()) {
+                *value = REDACTED.to_string();
+            }
+        }
+        serde_urlencoded::to_string(form).ok()
+    } else {
+        None
+    }
+}
+
+// Redacts the secret fields of a JSON value, at any depth.
+fn redact_json(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if SECRET_FIELDS.contains(&key.as_str()) {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+// Reads the fields of an `application/x-www-form-urlencoded` body.
+fn form_fields(body: &[u8]) -> Vec<(String, FieldValue)> {
+    serde_urlencoded::from_bytes::<Vec<(String, String)>>(body)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, value)| (name, FieldValue::Text(value)))
+        .collect()
+}
+
+// Reads the fields of a `multipart/form-data` body.
+fn multipart_fields(body: &[u8], boundary: &str) -> Vec<(String, FieldValue)> {
+    let delimiter = format!("--{}", boundary);
+    let mut fields = Vec::new();
+    for part in split(body, delimiter.as_bytes()).into_iter().skip(1) {
+        let part = part.strip_prefix(b"\r\n").unwrap_or(part);
+        let (head, content) = match find(part, b"\r\n\r\n") {
+            Some(i) => (&part[..i], &part[i + 4..]),
+            None => continue,
+        };
+        let content = content.strip_suffix(b"\r\n").unwrap_or(content);
+        let head = String::from_utf8_lossy(head);
+        let param = |name: &str| {
+            let start = head.find(&format!("{}=\"", name))? + name.len() + 2;
+            let len = head[start..].find('"')?;
+            Some(head[start..start + len].to_string())
+        };
+        let name = match param(" name") {
+            Some(name) => name,
+            None => continue,
+        };
+        let value = match param("filename") {
+            Some(file_name) => FieldValue::File {
+                file_name,
+                content_type: head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Content-Type: "))
+                    .unwrap_or_default()
+                    .to_string(),
+                size: content.len(),
+            },
+            None => FieldValue::Text(String::from_utf8_lossy(content).into_owned()),
+        };
+        fields.push((name, value));
+    }
+    fields
+}
+
+// Reads the fields of a JSON body, flattened.
+fn json_fields(body: &[u8]) -> Vec<(String, FieldValue)> {
+    let mut fields = Vec::new();
+    if let Ok(json) = serde_json::from_slice::<Value>(body) {
+        flatten("", &json, &mut fields);
+    }
+    fields
+}
+
+fn flatten(path: &str, value: &Value, fields: &mut Vec<(String, FieldValue)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let path = match path {
+                    "" => key.clone(),
+                    path => format!("{}.{}", path, key),
+                };
+                flatten(&path, value, fields);
+            }
+        }
+        Value::Array(values) => {
+            for (i, value) in values.iter().enumerate() {
+                flatten(&format!("{}[{}]", path, i), value, fields);
+            }
+        }
+        Value::String(text) => fields.push((path.to_string(), FieldValue::Text(text.clone()))),
+        value => fields.push((path.to_string(), FieldValue::Text(value.to_string()))),
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn split<'a>(mut data: &'a [u8], delimiter: &[u8]) -> Vec<&'a [u8]> {
+    let mut parts = Vec::new();
+    while let Some(i) = find(data, delimiter) {
+        parts.push(&data[..i]);
+        data = &data[i + delimiter.len()..];
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{form_request, json_request, multipart_request};
+    use serde_json::json;
+
+    fn url() -> Url {
+        Url::parse("https://world.openfoodfacts.org/cgi/product_jqm2.pl").unwrap()
+    }
+
+    #[test]
+    fn form() {
+        let mut request = form_request(
+            url(),
+            &[("code", "123"), ("user_id", "user"), ("password", "secret")],
+        );
+        let mut auth = http::HeaderValue::from_static("Basic dXNlcjpzZWNyZXQ=");
+        auth.set_sensitive(true);
+        request.headers.insert(http::header::AUTHORIZATION, auth);
+        let prepared = PreparedRequest::new(&request);
+        assert_eq!(
+            prepared.to_string(),
+            "POST https://world.openfoodfacts.org/cgi/product_jqm2.pl\n\
+             content-type: application/x-www-form-urlencoded\n\
+             authorization: <redacted>\n\
+             + code: 123\n\
+             + user_id: user\n\
+             + password: <redacted>\n"
+        );
+        assert!(!format!("{:?}", prepared).contains("secret"));
+    }
+
+    #[test]
+    fn multipart() {
+        let request = multipart_request(
+            url(),
+            &[("code", "123"), ("password", "secret")],
+            (
+                String::from("imgupload_front_fr"),
+                "front.jpg",
+                "image/jpeg",
+                &[0xff, 0xd8, 0x0d, 0x0a],
+            ),
+        );
+        let prepared = PreparedRequest::new(&request);
+        assert_eq!(
+            prepared.field("code"),
+            Some(&FieldValue::Text(String::from("123")))
+        );
+        assert_eq!(
+            prepared.field("password"),
+            Some(&FieldValue::Text(String::from(REDACTED)))
+        );
+        assert_eq!(
+            prepared.field("imgupload_front_fr").unwrap().to_string(),
+            "<file front.jpg, image/jpeg, 4 bytes>"
+        );
+    }
+
+    #[test]
+    fn json() {
+        let body = json!({
+            "product": {"packagings": [{"shape": "en:box", "number_of_units": 2}]},
+            "password": "secret"
+        });
+        let request = json_request(Method::PATCH, url(), &body);
+        let prepared = PreparedRequest::new(&request);
+        assert_eq!(
+            prepared
+                .fields
+                .iter()
+                .map(|(n, v)| format!("{}={}", n, v))
+                .collect::<Vec<_>>(),
+            vec![
+                "password=<redacted>",
+                "product.packagings[0].number_of_units=2",
+                "product.packagings[0].shape=en:box",
+            ]
+        );
+    }
+
+    #[test]
+    fn redacted_bodies() {
+        let request = form_request(url(), &[("code", "123"), ("password", "secret")]);
+        assert_eq!(
+            redacted_body(&request).as_deref(),
+            Some("code=123&password=%3Credacted%3E")
+        );
+        let body = json!({"product": {"brands": "Kraš"}, "password": "secret"});
+        let request = json_request(Method::PATCH, url(), &body);
+        assert_eq!(
+            redacted_body(&request).as_deref(),
+            Some(r#"{"password":"<redacted>","product":{"brands":"Kraš"}}"#)
+        );
+        let request = multipart_request(
+            url(),
+            &[("password", "secret")],
+            (
+                String::from("imgupload_front"),
+                "front.jpg",
+                "image/jpeg",
+                &[0],
+            ),
+        );
+        assert_eq!(redacted_body(&request), None);
+    }
+}
@@ -0,0 +1,220 @@
+// The product edits sent to the write API, `cgi/product_jqm2.pl`. The edits
+// are forms whose fields are the product fields to set: fields left out are
+// kept unchanged by the server.
+use crate::de::number_or_default;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// The quantity the nutrition facts of a product are given for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NutritionDataPer {
+    /// Per 100g or 100ml.
+    Per100g,
+    /// Per serving.
+    Serving,
+}
+
+impl Display for NutritionDataPer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let per = match self {
+            Self::Per100g => "100g",
+            Self::Serving => "serving",
+        };
+        write!(f, "{}", per)
+    }
+}
+
+/// An edit of a product, creating the product if it does not exist.
+///
+/// ```
+/// use openfoodfacts::{NutritionDataPer, ProductEdit};
+///
+/// let edit = ProductEdit::new("3850102123681")
+///     .name("fr", "Pâte à tartiner")
+///     .brands("Ferrero, Nutella")
+///     .nutrition_data_per(NutritionDataPer::Per100g)
+///     .nutriment("sugars", 56.3, "g")
+///     .comment("Nutrition facts from the label");
+/// assert_eq!(edit.code(), "3850102123681");
+/// assert_eq!(edit.get("nutriment_sugars"), Some("56.3"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProductEdit {
+    // The barcode of the product.
+    code: String,
+    // The form fields, in insertion order.
+    fields: Vec<(String, String)>,
+}
+
+impl ProductEdit {
+    /// Creates an empty edit of the product with the given barcode.
+    pub fn new(barcode: &str) -> Self {
+        Self {
+            code: barcode.to_string(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Returns the barcode of the product.
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Sets the main language of the product.
+    pub fn lang(self, lang: &str) -> Self {
+        self.set("lang", lang)
+    }
+
+    /// Sets the name of the product in the given language.
+    pub fn name(self, lang: &str, name: &str) -> Self {
+        self.set(&format!("product_name_{}", lang), name)
+    }
+
+    /// Sets the generic (legal) name of the product in the given language.
+    pub fn generic_name(self, lang: &str, name: &str) -> Self {
+        self.set(&format!("generic_name_{}", lang), name)
+    }
+
+    /// Sets the brands, comma-separated.
+    pub fn brands(self, brands: &str) -> Self {
+        self.set("brands", brands)
+    }
+
+    /// Sets the quantity, i.e. "400 g".
+    pub fn quantity(self, quantity: &str) -> Self {
+        self.set("quantity", quantity)
+    }
+
+    /// Sets the categories, comma-separated. Names are read in the main language
+    /// of the product, or may be prefixed by a language, i.e. `en:sodas`.
+    pub fn categories(self, categories: &str) -> Self {
+        self.set("categories", categories)
+    }
+
+    /// Sets the labels, comma-separated, as the categories.
+    pub fn labels(self, labels: &str) -> Self {
+        self.set("labels", labels)
+    }
+
+    /// Sets the quantity the nutrition facts are given for.
+    pub fn nutrition_data_per(self, per: NutritionDataPer) -> Self {
+        self.set("nutrition_data_per", &per.to_string())
+    }
+
+    /// Sets the value of a nutrient, i.e. `sugars`, and its unit, i.e. `g`.
+    pub fn nutriment(self, id: &str, value: f64, unit: &str) -> Self {
+        self.set(&format!("nutriment_{}", id), &value.to_string())
+            .set(&format!("nutriment_{}_unit", id), unit)
+    }
+
+    /// Sets the comment of the edit, shown in the product history.
+    pub fn comment(self, comment: &str) -> Self {
+        self.set("comment", comment)
+    }
+
+    /// Sets the name and version of the application sending the edit.
+    pub fn app(self, name: &str, version: &str) -> Self {
+        self.set("app_name", name).set("app_version", version)
+    }
+
+    /// Sets the id of the application user, for the applications sending the
+    /// edits of their users with a shared account.
+    pub fn app_uuid(self, uuid: &str) -> Self {
+        self.set("app_uuid", uuid)
+    }
+
+    /// Sets a form field, replacing its previous value. For the fields without a
+    /// dedicated method.
+    pub fn set(mut self, name: &str, value: &str) -> Self {
+        match self.fields.iter_mut().find(|(n, _)| n == name) {
+            Some(field) => field.1 = value.to_string(),
+            None => self.fields.push((name.to_string(), value.to_string())),
+        }
+        self
+    }
+
+    /// Returns the value of the given form field.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the form fields: the barcode first, then the fields in the order
+    /// they were set.
+    pub fn form(&self) -> Vec<(&str, &str)> {
+        std::iter::once(("code", self.code.as_str()))
+            .chain(self.fields.iter().map(|(n, v)| (n.as_str(), v.as_str())))
+            .collect()
+    }
+}
+
+/// The response of the write API.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EditResponse {
+    /// 1 if the edit was saved, 0 otherwise.
+    #[serde(deserialize_with = "number_or_default")]
+    pub status: u32,
+    /// The status message, i.e. "fields saved".
+    pub status_verbose: String,
+}
+
+impl EditResponse {
+    /// Returns true if the edit was saved.
+    pub fn is_saved(&self) -> bool {
+        self.status == 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn form() {
+        let edit = ProductEdit::new("3850102123681")
+            .lang("fr")
+            .name("fr", "Sirop")
+            .name("en", "Syrup")
+            .quantity("1 l")
+            .categories("Sirops, en:syrups")
+            .labels("Bio")
+            .nutrition_data_per(NutritionDataPer::Serving)
+            .nutriment("energy-kcal", 120.0, "kcal")
+            .nutriment("salt", 0.02, "g")
+            .app("MyApp", "1.2")
+            .quantity("1.5 l");
+        assert_eq!(
+            edit.form(),
+            vec![
+                ("code", "3850102123681"),
+                ("lang", "fr"),
+                ("product_name_fr", "Sirop"),
+                ("product_name_en", "Syrup"),
+                ("quantity", "1.5 l"),
+                ("categories", "Sirops, en:syrups"),
+                ("labels", "Bio"),
+                ("nutrition_data_per", "serving"),
+                ("nutriment_energy-kcal", "120"),
+                ("nutriment_energy-kcal_unit", "kcal"),
+                ("nutriment_salt", "0.02"),
+                ("nutriment_salt_unit", "g"),
+                ("app_name", "MyApp"),
+                ("app_version", "1.2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn response() {
+        let response: EditResponse =
+            serde_json::from_str(r#"{"status": 1, "status_verbose": "fields saved"}"#).unwrap();
+        assert!(response.is_saved());
+        let response: EditResponse =
+            serde_json::from_str(r#"{"status": "0", "status_verbose": "no code or invalid code"}"#)
+                .unwrap();
+        assert!(!response.is_saved());
+    }
+}
@@ -0,0 +1,252 @@
+use crate::dry_run::PreparedRequest;
+use crate::ratelimit::EndpointClass;
+use crate::transport::TransportError;
+use std::fmt::{self, Display, Formatter};
+use std::time::{Duration, SystemTime};
+
+/// The error type of all OffClient methods.
+///
+/// The error is `Send + Sync` and can be used across threads and with error
+/// handling libraries such as `anyhow`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An URL could not be built or parsed.
+    Url(url::ParseError),
+    /// The request could not be sent or the response could not be received.
+    Transport(TransportError),
+    /// The server returned an unsuccessful HTTP status.
+    Status {
+        /// The HTTP status code.
+        code: u16,
+        /// The delay requested by the server in the `Retry-After` header, if
+        /// any, i.e. with the 503 (Service Unavailable) status.
+        retry_after: Option<Duration>,
+        /// The response body.
+        body: String,
+    },
+    /// The server returned the 429 (Too Many Requests) HTTP status.
+    RateLimited {
+        /// The delay requested by the server in the `Retry-After` header, if any.
+        retry_after: Option<Duration>,
+    },
+    /// The client-side quota of the endpoint class is exhausted. Only returned
+    /// by clients whose rate limits fail fast.
+    QuotaExceeded {
+        /// The endpoint class of the request.
+        class: EndpointClass,
+        /// The delay before a request of this class is allowed.
+        retry_after: Duration,
+    },
+    /// The login failed: the server did not open a session. Holds a
+    /// description of the failure.
+    Authentication(String),
+    /// The requested product does not exist. Holds the product barcode.
+    ProductNotFound(String),
+    /// The response body could not be deserialized.
+    Decode(serde_json::Error),
+    /// An argument given to a client method is not valid.
+    Validation(String),
+    /// A file could not be read or written.
+    Io(std::io::Error),
+    /// A text file, i.e. a taxonomy source, could not be parsed.
+    Syntax {
+        /// The line number, starting at 1.
+        line: usize,
+        /// The description of the error.
+        message: String,
+    },
+    /// The client is in dry-run mode: the write request was prepared but not
+    /// sent. Holds its description.
+    DryRun(Box<PreparedRequest>),
+}
+
+impl Error {
+    /// Returns the error matching an unsuccessful HTTP response, given its
+    /// status code, `Retry-After` header value and body.
+    pub(crate) fn from_status(code: u16, retry_after: Option<&str>, body: String) -> Self {
+        match code {
+            429 => Self::RateLimited {
+                retry_after: retry_after.and_then(parse_retry_after),
+            },
+            _ => Self::Status {
+                code,
+                retry_after: retry_after.and_then(parse_retry_after),
+                body,
+            },
+        }
+    }
+
+    /// Converts a 404 (Not Found) status error of a product request to
+    /// [Error::ProductNotFound].
+    pub(crate) fn for_product(self, barcode: &str) -> Self {
+        match self {
+            Self::Status { code: 404, .. } => Self::ProductNotFound(barcode.to_string()),
+            e => e,
+        }
+    }
+
+    /// Returns the HTTP status code of the response that caused the error, if any.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::Status { code, .. } => Some(*code),
+            Self::RateLimited { .. } => Some(429),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Url(e) => write!(f, "invalid URL: {}", e),
+            Self::Transport(e) => write!(f, "transport error: {}", e),
+            Self::Status { code, .. } => write!(f, "unsuccessful HTTP status {}", code),
+            Self::RateLimited {
+                retry_after: Some(delay),
+            } => write!(f, "rate limited, retry after {}s", delay.as_secs()),
+            Self::RateLimited { retry_after: None } => write!(f, "rate limited"),
+            Self::QuotaExceeded { class, retry_after } => write!(
+                f,
+                "{:?} quota exceeded, retry after {}s",
+                class,
+                retry_after.as_secs_f64().ceil()
+            ),
+            Self::Authentication(msg) => write!(f, "authentication failed: {}", msg),
+            Self::ProductNotFound(barcode) => write!(f, "product {} not found", barcode),
+            Self::Decode(e) => write!(f, "invalid response body: {}", e),
+            Self::Validation(msg) => write!(f, "invalid argument: {}", msg),
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+            Self::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            Self::DryRun(request) => write!(f, "dry run, request not sent:\n{}", request),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Url(e) => Some(e),
+            Self::Transport(e) => Some(e),
+            Self::Decode(e) => Some(e),
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<url::ParseError> for Error {
+    fn from(e: url::ParseError) -> Self {
+        Self::Url(e)
+    }
+}
+
+impl From<TransportError> for Error {
+    fn from(e: TransportError) -> Self {
+        Self::Transport(e)
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Transport(TransportError::from(e))
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Decode(e)
+    }
+}
+
+/// Parses a `Retry-After` header value, given either in seconds or as an
+/// HTTP date.
+pub(crate) fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    // A date in the past means no delay.
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// Checks that the given string is a valid barcode: a non-empty sequence of digits.
+pub(crate) fn validate_barcode(barcode: &str) -> Result<(), Error> {
+    if !barcode.is_empty() && barcode.chars().all(|c| c.is_ascii_digit()) {
+        Ok(())
+    } else {
+        Err(Error::Validation(format!("invalid barcode '{}'", barcode)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn send_sync() {
+        fn assert_send_sync<T: Send + Sync + 'static>() {}
+        assert_send_sync::<Error>();
+    }
+
+    #[test]
+    fn from_status() {
+        match Error::from_status(503, None, String::from("down")) {
+            Error::Status {
+                code,
+                retry_after,
+                body,
+            } => {
+                assert_eq!(code, 503);
+                assert_eq!(retry_after, None);
+                assert_eq!(body, "down");
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+        match Error::from_status(503, Some("30"), String::new()) {
+            Error::Status { retry_after, .. } => {
+                assert_eq!(retry_after, Some(Duration::from_secs(30)))
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+        match Error::from_status(429, Some("120"), String::new()) {
+            Error::RateLimited { retry_after } => {
+                assert_eq!(retry_after, Some(Duration::from_secs(120)))
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn retry_after() {
+        assert_eq!(parse_retry_after(" 5 "), Some(Duration::from_secs(5)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(3600));
+        let delay = parse_retry_after(&date).unwrap();
+        assert!(delay > Duration::from_secs(3500) && delay <= Duration::from_secs(3600));
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+
+    #[test]
+    fn barcode() {
+        assert!(validate_barcode("3850102123681").is_ok());
+        assert!(matches!(validate_barcode(""), Err(Error::Validation(_))));
+        assert!(matches!(
+            validate_barcode("385/0102"),
+            Err(Error::Validation(_))
+        ));
+    }
+}
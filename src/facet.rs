@@ -0,0 +1,231 @@
+use crate::de::{bool_or_number, number_or_default};
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// The facet types.
+///
+/// The facet endpoints use the plural name of the facet type, i.e. `brands`,
+/// the product listings its singular name, i.e. `brand`. Both names may be
+/// given in English, which all the servers accept, or localized. The string
+/// representation is the English plural name.
+///
+/// ```
+/// use openfoodfacts::Facet;
+///
+/// assert_eq!(Facet::Brands.plural(), "brands");
+/// assert_eq!(Facet::Brands.singular(), "brand");
+/// assert_eq!(Facet::Brands.localized_singular("fr"), "marque");
+/// assert_eq!("marques".parse::<Facet>().unwrap(), Facet::Brands);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Facet {
+    Additives,
+    Allergens,
+    Brands,
+    Categories,
+    Countries,
+    EntryDates,
+    Ingredients,
+    Labels,
+    Languages,
+    Packaging,
+    PurchasePlaces,
+    States,
+    Stores,
+    Traces,
+}
+
+impl Facet {
+    /// All the facet types.
+    pub const ALL: [Facet; 14] = [
+        Facet::Additives,
+        Facet::Allergens,
+        Facet::Brands,
+        Facet::Categories,
+        Facet::Countries,
+        Facet::EntryDates,
+        Facet::Ingredients,
+        Facet::Labels,
+        Facet::Languages,
+        Facet::Packaging,
+        Facet::PurchasePlaces,
+        Facet::States,
+        Facet::Stores,
+        Facet::Traces,
+    ];
+
+    /// Returns the English plural name, used by the facet endpoints.
+    pub fn plural(&self) -> &'static str {
+        self.names("en").0
+    }
+
+    /// Returns the English singular name, used by the product listings.
+    pub fn singular(&self) -> &'static str {
+        self.names("en").1
+    }
+
+    /// Returns the plural name in the given language, or the English name if
+    /// the language is not supported.
+    pub fn localized_plural(&self, lang: &str) -> &'static str {
+        self.names(lang).0
+    }
+
+    /// Returns the singular name in the given language, or the English name if
+    /// the language is not supported.
+    pub fn localized_singular(&self, lang: &str) -> &'static str {
+        self.names(lang).1
+    }
+
+    // Returns the plural and singular names. Only English and French are
+    // supported.
+    fn names(&self, lang: &str) -> (&'static str, &'static str) {
+        match (self, lang) {
+            (Self::Additives, "fr") => ("additifs", "additif"),
+            (Self::Additives, _) => ("additives", "additive"),
+            (Self::Allergens, "fr") => ("allergenes", "allergene"),
+            (Self::Allergens, _) => ("allergens", "allergen"),
+            (Self::Brands, "fr") => ("marques", "marque"),
+            (Self::Brands, _) => ("brands", "brand"),
+            (Self::Categories, "fr") => ("categories", "categorie"),
+            (Self::Categories, _) => ("categories", "category"),
+            (Self::Countries, "fr") => ("pays", "pays"),
+            (Self::Countries, _) => ("countries", "country"),
+            (Self::EntryDates, "fr") => ("dates-d-ajout", "date-d-ajout"),
+            (Self::EntryDates, _) => ("entry-dates", "entry-date"),
+            (Self::Ingredients, "fr") => ("ingredients", "ingredient"),
+            (Self::Ingredients, _) => ("ingredients", "ingredient"),
+            (Self::Labels, "fr") => ("labels", "label"),
+            (Self::Labels, _) => ("labels", "label"),
+            (Self::Languages, "fr") => ("langues", "langue"),
+            (Self::Languages, _) => ("languages", "language"),
+            (Self::Packaging, "fr") => ("emballages", "emballage"),
+            (Self::Packaging, _) => ("packaging", "packaging"),
+            (Self::PurchasePlaces, "fr") => ("lieux-de-vente", "lieu-de-vente"),
+            (Self::PurchasePlaces, _) => ("purchase-places", "purchase-place"),
+            (Self::States, "fr") => ("etats", "etat"),
+            (Self::States, _) => ("states", "state"),
+            (Self::Stores, "fr") => ("magasins", "magasin"),
+            (Self::Stores, _) => ("stores", "store"),
+            (Self::Traces, "fr") => ("traces", "trace"),
+            (Self::Traces, _) => ("traces", "trace"),
+        }
+    }
+}
+
+impl Display for Facet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.plural())
+    }
+}
+
+impl FromStr for Facet {
+    type Err = Error;
+
+    /// Parses a plural or singular name, in English or French.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|facet| {
+                ["en", "fr"].iter().any(|lang| {
+                    let (plural, singular) = facet.names(lang);
+                    name == plural || name == singular
+                })
+            })
+            .ok_or_else(|| Error::Validation(format!("unknown facet '{}'", name)))
+    }
+}
+
+/// The response of the facet endpoints.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FacetResponse {
+    /// The number of tags.
+    #[serde(deserialize_with = "number_or_default")]
+    pub count: u64,
+    pub tags: Vec<FacetTag>,
+}
+
+/// A tag of a facet, i.e. a brand.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FacetTag {
+    /// The tag id, i.e. `en:milk`.
+    pub id: String,
+    /// The tag name in the language of the request.
+    pub name: String,
+    /// The number of products having the tag.
+    #[serde(deserialize_with = "number_or_default")]
+    pub products: u64,
+    /// The URL of the product listing of the tag.
+    pub url: String,
+    /// True if the tag is in the taxonomy of the facet.
+    #[serde(deserialize_with = "bool_or_number")]
+    pub known: bool,
+    /// The URLs of the same tag in other databases, i.e. Wikidata.
+    #[serde(rename = "sameAs", skip_serializing_if = "Vec::is_empty")]
+    pub same_as: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn names() {
+        assert_eq!(Facet::EntryDates.plural(), "entry-dates");
+        assert_eq!(Facet::EntryDates.singular(), "entry-date");
+        assert_eq!(Facet::Categories.localized_singular("fr"), "categorie");
+        assert_eq!(Facet::Categories.localized_singular("de"), "category");
+        assert_eq!(Facet::Traces.to_string(), "traces");
+    }
+
+    #[test]
+    fn parse() {
+        for facet in Facet::ALL.iter() {
+            assert_eq!(facet.plural().parse::<Facet>().unwrap(), *facet);
+            assert_eq!(facet.singular().parse::<Facet>().unwrap(), *facet);
+            assert_eq!(
+                facet.localized_plural("fr").parse::<Facet>().unwrap(),
+                *facet
+            );
+        }
+        assert!(matches!(
+            "brandz".parse::<Facet>(),
+            Err(Error::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn response() {
+        let response: FacetResponse = serde_json::from_value(json!({
+            "count": 2,
+            "tags": [
+                {
+                    "id": "en:milk",
+                    "known": 1,
+                    "name": "Milk",
+                    "products": 157456,
+                    "sameAs": ["https://www.wikidata.org/wiki/Q8495"],
+                    "url": "https://world.openfoodfacts.org/allergen/milk"
+                },
+                {
+                    "id": "en:lait",
+                    "known": 0,
+                    "name": "lait",
+                    "products": "12",
+                    "url": "https://world.openfoodfacts.org/allergen/en:lait"
+                }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(response.count, 2);
+        assert!(response.tags[0].known);
+        assert_eq!(response.tags[0].same_as.len(), 1);
+        assert!(!response.tags[1].known);
+        assert_eq!(response.tags[1].products, 12);
+    }
+}
@@ -0,0 +1,85 @@
+use std::fmt::{self, Display, Formatter};
+
+/// The Product Opener databases sharing the same API.
+///
+/// The flavor selects the default domain of the client. Its string
+/// representation is the short code used by OFF, i.e. "off" or "obf".
+///
+/// ```
+/// use openfoodfacts::{self as off, Flavor};
+///
+/// # fn main() -> Result<(), off::Error> {
+/// let client = off::v2().flavor(Flavor::Beauty).build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Flavor {
+    /// Open Food Facts.
+    #[default]
+    Food,
+    /// Open Beauty Facts.
+    Beauty,
+    /// Open Pet Food Facts.
+    PetFood,
+    /// Open Products Facts.
+    Products,
+}
+
+impl Flavor {
+    /// All the flavors.
+    pub const ALL: [Flavor; 4] = [
+        Flavor::Food,
+        Flavor::Beauty,
+        Flavor::PetFood,
+        Flavor::Products,
+    ];
+
+    /// Returns the domain of the flavor's public server.
+    pub fn domain(&self) -> &'static str {
+        match self {
+            Self::Food => "openfoodfacts.org",
+            Self::Beauty => "openbeautyfacts.org",
+            Self::PetFood => "openpetfoodfacts.org",
+            Self::Products => "openproductsfacts.org",
+        }
+    }
+}
+
+impl Display for Flavor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let code = match self {
+            Self::Food => "off",
+            Self::Beauty => "obf",
+            Self::PetFood => "opff",
+            Self::Products => "opf",
+        };
+        write!(f, "{}", code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default() {
+        assert_eq!(Flavor::default(), Flavor::Food);
+    }
+
+    #[test]
+    fn domain() {
+        assert_eq!(Flavor::Food.domain(), "openfoodfacts.org");
+        assert_eq!(Flavor::Beauty.domain(), "openbeautyfacts.org");
+        assert_eq!(Flavor::PetFood.domain(), "openpetfoodfacts.org");
+        assert_eq!(Flavor::Products.domain(), "openproductsfacts.org");
+    }
+
+    #[test]
+    fn to_string() {
+        assert_eq!(Flavor::Food.to_string(), "off");
+        assert_eq!(Flavor::Beauty.to_string(), "obf");
+        assert_eq!(Flavor::PetFood.to_string(), "opff");
+        assert_eq!(Flavor::Products.to_string(), "opf");
+    }
+}
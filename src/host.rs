@@ -0,0 +1,299 @@
+use crate::flavor::Flavor;
+use crate::locale::Locale;
+use crate::types::Params;
+use std::net::IpAddr;
+use url::{ParseError, Url};
+
+/// The placeholder replaced by the locale in URL templates.
+const LOCALE_PLACEHOLDER: &str = "{locale}";
+
+/// The subdomain of the static images server.
+const IMAGES_SUBDOMAIN: &str = "images";
+
+/// The server targeted by a client.
+///
+/// By default, the host is `https://{locale}.{domain}/`, where the domain is the
+/// one of the selected [Flavor], i.e. `openfoodfacts.org`, and the country and
+/// language are selected by the locale subdomain. Hosts that cannot use
+/// subdomains (IP addresses, `localhost`, hosts without wildcard DNS or URL
+/// templates without the `{locale}` placeholder) select the country and language
+/// with the `cc` and `lc` query parameters instead.
+///
+/// Hosts are configured with the [crate::OffBuilder] methods `flavor()`,
+/// `scheme()`, `domain()`, `port()`, `locale_subdomains()` and `url_template()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Host {
+    // The URL scheme, "https" by default.
+    scheme: String,
+    // The database flavor.
+    flavor: Flavor,
+    // The base domain, without locale subdomain. If not given, the domain of the
+    // flavor.
+    domain: Option<String>,
+    // Optional port.
+    port: Option<u16>,
+    // Whether the locale is selected with a subdomain.
+    locale_subdomains: bool,
+    // Optional URL template. Overrides the scheme, domain and port.
+    template: Option<String>,
+}
+
+impl Default for Host {
+    /// Returns the `https://{locale}.openfoodfacts.org/` host.
+    fn default() -> Self {
+        Self {
+            scheme: String::from("https"),
+            flavor: Flavor::default(),
+            domain: None,
+            port: None,
+            locale_subdomains: true,
+            template: None,
+        }
+    }
+}
+
+impl Host {
+    /// Sets the URL scheme.
+    pub(crate) fn set_scheme(&mut self, scheme: &str) {
+        self.scheme = scheme.to_string();
+    }
+
+    /// Sets the database flavor.
+    pub(crate) fn set_flavor(&mut self, flavor: Flavor) {
+        self.flavor = flavor;
+    }
+
+    /// Sets the base domain, i.e. "openfoodfacts.net".
+    pub(crate) fn set_domain(&mut self, domain: &str) {
+        self.domain = Some(domain.to_string());
+    }
+
+    /// Returns the database flavor.
+    pub fn flavor(&self) -> Flavor {
+        self.flavor
+    }
+
+    /// Returns the base domain.
+    pub fn domain(&self) -> &str {
+        self.domain
+            .as_deref()
+            .unwrap_or_else(|| self.flavor.domain())
+    }
+
+    /// Returns the public server of the given flavor, i.e.
+    /// `https://{locale}.openbeautyfacts.org/`. The scheme, domain, port, locale
+    /// subdomains and URL template of this host are not kept.
+    pub fn with_flavor(&self, flavor: Flavor) -> Self {
+        Self {
+            flavor,
+            ..Self::default()
+        }
+    }
+
+    /// Returns true if the host is not a public server: it has an explicit
+    /// domain, port or URL template.
+    pub(crate) fn is_custom(&self) -> bool {
+        self.domain.is_some() || self.port.is_some() || self.template.is_some()
+    }
+
+    /// Sets the port.
+    pub(crate) fn set_port(&mut self, port: u16) {
+        self.port = Some(port);
+    }
+
+    /// Enables or disables the locale subdomains.
+    pub(crate) fn set_locale_subdomains(&mut self, enabled: bool) {
+        self.locale_subdomains = enabled;
+    }
+
+    /// Sets the URL template.
+    pub(crate) fn set_template(&mut self, template: &str) {
+        self.template = Some(template.to_string());
+    }
+
+    /// Returns true if the country and language are selected via the URL host,
+    /// false if they must be given as query parameters.
+    pub fn has_locale_subdomains(&self) -> bool {
+        match self.template {
+            Some(ref template) => template.contains(LOCALE_PLACEHOLDER),
+            None => {
+                let domain = self.domain();
+                self.locale_subdomains && domain != "localhost" && domain.parse::<IpAddr>().is_err()
+            }
+        }
+    }
+
+    /// Returns the base URL of the host for the given locale.
+    pub fn url(&self, locale: &Locale) -> Result<Url, ParseError> {
+        self.url_with_subdomain(&locale.to_string())
+    }
+
+    /// Returns the base URL of the static images server, i.e.
+    /// `https://images.openfoodfacts.org/`. Hosts without locale subdomains
+    /// serve the images themselves.
+    pub fn images_url(&self) -> Result<Url, ParseError> {
+        self.url_with_subdomain(IMAGES_SUBDOMAIN)
+    }
+
+    // Returns the base URL of the host with the given subdomain, if the host
+    // supports subdomains.
+    fn url_with_subdomain(&self, subdomain: &str) -> Result<Url, ParseError> {
+        let url = match self.template {
+            Some(ref template) => template.replace(LOCALE_PLACEHOLDER, subdomain),
+            None => {
+                let port = self.port.map_or(String::new(), |p| format!(":{}", p));
+                if self.has_locale_subdomains() {
+                    format!("{}://{}.{}{}/", self.scheme, subdomain, self.domain(), port)
+                } else {
+                    format!("{}://{}{}/", self.scheme, self.domain(), port)
+                }
+            }
+        };
+        Url::parse(&url)
+    }
+
+    /// Returns the `cc` and `lc` query parameters selecting the given locale, or
+    /// no parameters if the locale is selected via subdomain.
+    pub fn locale_params(&self, locale: &Locale) -> Params<'static> {
+        let mut params = Params::new();
+        if !self.has_locale_subdomains() {
+            params.push(("cc", locale.cc.clone()));
+            if let Some(ref lc) = locale.lc {
+                params.push(("lc", lc.clone()));
+            }
+        }
+        params
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default() {
+        let host = Host::default();
+        assert!(host.has_locale_subdomains());
+        assert_eq!(
+            host.url(&Locale::new("fr", Some("ca"))).unwrap().as_str(),
+            "https://fr-ca.openfoodfacts.org/"
+        );
+        assert!(host.locale_params(&Locale::from("fr")).is_empty());
+    }
+
+    #[test]
+    fn domain_scheme_port() {
+        let mut host = Host::default();
+        host.set_scheme("http");
+        host.set_domain("openfoodfacts.localhost");
+        host.set_port(8080);
+        assert_eq!(
+            host.url(&Locale::default()).unwrap().as_str(),
+            "http://world.openfoodfacts.localhost:8080/"
+        );
+    }
+
+    #[test]
+    fn no_locale_subdomains() {
+        let mut host = Host::default();
+        host.set_domain("openfoodfacts.net");
+        host.set_locale_subdomains(false);
+        let locale = Locale::new("fr", Some("en"));
+        assert_eq!(
+            host.url(&locale).unwrap().as_str(),
+            "https://openfoodfacts.net/"
+        );
+        assert_eq!(
+            host.locale_params(&locale),
+            vec![("cc", String::from("fr")), ("lc", String::from("en"))]
+        );
+    }
+
+    #[test]
+    fn ip_address_and_localhost() {
+        let mut host = Host::default();
+        host.set_scheme("http");
+        host.set_domain("127.0.0.1");
+        host.set_port(8080);
+        assert!(!host.has_locale_subdomains());
+        assert_eq!(
+            host.url(&Locale::from("fr")).unwrap().as_str(),
+            "http://127.0.0.1:8080/"
+        );
+        host.set_domain("localhost");
+        assert!(!host.has_locale_subdomains());
+    }
+
+    #[test]
+    fn flavor() {
+        let mut host = Host::default();
+        host.set_flavor(Flavor::Beauty);
+        assert_eq!(host.domain(), "openbeautyfacts.org");
+        assert_eq!(
+            host.url(&Locale::from("fr")).unwrap().as_str(),
+            "https://fr.openbeautyfacts.org/"
+        );
+        // An explicit domain overrides the flavor's domain.
+        host.set_domain("openbeautyfacts.net");
+        assert_eq!(host.domain(), "openbeautyfacts.net");
+
+        assert!(host.is_custom());
+
+        host.set_scheme("http");
+        host.set_port(8080);
+        let host = host.with_flavor(Flavor::PetFood);
+        assert_eq!(host.flavor(), Flavor::PetFood);
+        assert_eq!(host.domain(), "openpetfoodfacts.org");
+        assert!(!host.is_custom());
+        assert_eq!(
+            host.url(&Locale::from("fr")).unwrap().as_str(),
+            "https://fr.openpetfoodfacts.org/"
+        );
+    }
+
+    #[test]
+    fn images_url() {
+        let mut host = Host::default();
+        assert_eq!(
+            host.images_url().unwrap().as_str(),
+            "https://images.openfoodfacts.org/"
+        );
+        host.set_flavor(Flavor::Beauty);
+        assert_eq!(
+            host.images_url().unwrap().as_str(),
+            "https://images.openbeautyfacts.org/"
+        );
+        host.set_template("http://{locale}.off.localhost:8080/");
+        assert_eq!(
+            host.images_url().unwrap().as_str(),
+            "http://images.off.localhost:8080/"
+        );
+        host.set_template("http://127.0.0.1:8080/off/");
+        assert_eq!(
+            host.images_url().unwrap().as_str(),
+            "http://127.0.0.1:8080/off/"
+        );
+    }
+
+    #[test]
+    fn template() {
+        let mut host = Host::default();
+        host.set_template("http://{locale}.off.localhost:8080/");
+        assert!(host.has_locale_subdomains());
+        assert_eq!(
+            host.url(&Locale::from("gr")).unwrap().as_str(),
+            "http://gr.off.localhost:8080/"
+        );
+
+        host.set_template("http://127.0.0.1:8080/off/");
+        assert!(!host.has_locale_subdomains());
+        assert_eq!(
+            host.url(&Locale::from("gr")).unwrap().as_str(),
+            "http://127.0.0.1:8080/off/"
+        );
+        assert_eq!(
+            host.locale_params(&Locale::from("gr")),
+            vec![("cc", String::from("gr"))]
+        );
+    }
+}
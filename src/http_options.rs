@@ -0,0 +1,77 @@
+use reqwest::{Certificate, ClientBuilder, NoProxy, Proxy};
+use std::time::Duration;
+
+/// Options of the underlying reqwest client, shared by the blocking and async
+/// builders. Unset options keep the reqwest defaults.
+#[derive(Debug, Clone)]
+pub(crate) struct HttpOptions {
+    // The timeout of the connect phase.
+    pub(crate) connect_timeout: Option<Duration>,
+    // The timeout of each read operation.
+    pub(crate) read_timeout: Option<Duration>,
+    // The timeout of a whole request, from connecting until the response body
+    // has been received.
+    pub(crate) timeout: Option<Duration>,
+    // Decompress gzip responses.
+    pub(crate) gzip: bool,
+    // Decompress brotli responses.
+    pub(crate) brotli: bool,
+    // The URL of the HTTP(S) proxy.
+    pub(crate) proxy: Option<String>,
+    // The hosts not using the proxy, comma separated.
+    pub(crate) no_proxy: Option<String>,
+    // Additional trusted root certificates.
+    pub(crate) root_certificates: Vec<Certificate>,
+    // The timeout of idle pooled connections.
+    pub(crate) pool_idle_timeout: Option<Duration>,
+    // The maximum number of idle pooled connections per host.
+    pub(crate) pool_max_idle_per_host: Option<usize>,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            connect_timeout: None,
+            read_timeout: None,
+            timeout: None,
+            gzip: true,
+            brotli: true,
+            proxy: None,
+            no_proxy: None,
+            root_certificates: Vec::new(),
+            pool_idle_timeout: None,
+            pool_max_idle_per_host: None,
+        }
+    }
+}
+
+impl HttpOptions {
+    /// Applies the options to the given (async) client builder. Fails if the
+    /// proxy URL is not valid.
+    pub(crate) fn apply(&self, mut cb: ClientBuilder) -> reqwest::Result<ClientBuilder> {
+        if let Some(timeout) = self.connect_timeout {
+            cb = cb.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.read_timeout {
+            cb = cb.read_timeout(timeout);
+        }
+        if let Some(timeout) = self.timeout {
+            cb = cb.timeout(timeout);
+        }
+        cb = cb.gzip(self.gzip).brotli(self.brotli);
+        if let Some(ref url) = self.proxy {
+            let no_proxy = self.no_proxy.as_deref().and_then(NoProxy::from_string);
+            cb = cb.proxy(Proxy::all(url.as_str())?.no_proxy(no_proxy));
+        }
+        for certificate in &self.root_certificates {
+            cb = cb.add_root_certificate(certificate.clone());
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            cb = cb.pool_idle_timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            cb = cb.pool_max_idle_per_host(max);
+        }
+        Ok(cb)
+    }
+}
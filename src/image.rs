@@ -0,0 +1,390 @@
+// The URLs of the product images. The images of a product are stored in a
+// folder named after its barcode, split in groups of 3 digits:
+//
+//     images/products/301/762/042/2003/1.jpg            raw image 1
+//     images/products/301/762/042/2003/1.400.jpg        raw image 1, 400 px
+//     images/products/301/762/042/2003/front_fr.12.400.jpg
+//
+// The selected images (`front_fr`, `ingredients_de`...) are crops of the raw
+// images. Their file names include a revision number, changed on each new
+// selection, so that they can be cached forever.
+use crate::error::{validate_barcode, Error};
+use serde_json::{Map, Value};
+use std::fmt::{self, Display, Formatter};
+use url::Url;
+
+/// The size of an image, by its largest dimension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageSize {
+    /// 100 px.
+    Thumb,
+    /// 200 px. Not available for the raw images.
+    Small,
+    /// 400 px.
+    Display,
+    /// The full size.
+    Full,
+}
+
+impl Display for ImageSize {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let size = match self {
+            Self::Thumb => "100",
+            Self::Small => "200",
+            Self::Display => "400",
+            Self::Full => "full",
+        };
+        write!(f, "{}", size)
+    }
+}
+
+/// The fields of the selected images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageField {
+    Front,
+    Ingredients,
+    Nutrition,
+    Packaging,
+}
+
+impl ImageField {
+    /// All the image fields.
+    pub const ALL: [ImageField; 4] = [
+        ImageField::Front,
+        ImageField::Ingredients,
+        ImageField::Nutrition,
+        ImageField::Packaging,
+    ];
+
+    /// Returns the name of the field, i.e. `front`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Front => "front",
+            Self::Ingredients => "ingredients",
+            Self::Nutrition => "nutrition",
+            Self::Packaging => "packaging",
+        }
+    }
+
+    /// Returns the key of the image selected for this field in the given
+    /// language, i.e. `front_fr`. Used as `imagefield` by the image requests.
+    pub fn key(&self, lang: &str) -> String {
+        format!("{}_{}", self.name(), lang)
+    }
+}
+
+impl Display for ImageField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A selected image of a product, i.e. the `front_fr` entry of the `images`
+/// object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectedImage {
+    /// The key of the image, i.e. `front_fr`.
+    pub key: String,
+    pub field: ImageField,
+    /// The language of the image. None for the images selected before the
+    /// images were localized, whose key is the field name.
+    pub lang: Option<String>,
+    /// The revision of the selection.
+    pub rev: String,
+    /// The id of the raw image the selection was cropped from.
+    pub imgid: String,
+}
+
+impl SelectedImage {
+    // Reads the entry of the `images` object with the given key. Returns None if
+    // the key is not the one of a selected image.
+    fn from_entry(key: &str, value: &Value) -> Option<Self> {
+        let (name, lang) = match key.split_once('_') {
+            Some((name, lang)) => (name, Some(lang.to_string())),
+            None => (key, None),
+        };
+        let field = ImageField::ALL.iter().copied().find(|f| f.name() == name)?;
+        Some(Self {
+            key: key.to_string(),
+            field,
+            lang,
+            rev: text(&value["rev"])?,
+            imgid: text(&value["imgid"]).unwrap_or_default(),
+        })
+    }
+}
+
+/// Returns the folder of the images of a product: the barcode split in groups of
+/// 3 digits, the last group holding the remaining digits. Barcodes of 8 digits
+/// or less are not split.
+///
+/// ```
+/// use openfoodfacts::barcode_path;
+///
+/// assert_eq!(barcode_path("3850102123681"), "385/010/212/3681");
+/// assert_eq!(barcode_path("20004293"), "20004293");
+/// ```
+pub fn barcode_path(barcode: &str) -> String {
+    if barcode.len() <= 8 || !barcode.is_ascii() {
+        return barcode.to_string();
+    }
+    let (head, rest) = barcode.split_at(9);
+    let path = format!("{}/{}/{}/{}", &head[..3], &head[3..6], &head[6..], rest);
+    path.trim_end_matches('/').to_string()
+}
+
+/// The image URLs of a product, computed from its barcode and `images` object.
+///
+/// The client returns the images of a product on its own server with
+/// [crate::OffClient::product_images].
+///
+/// ```
+/// use openfoodfacts::{ImageField, ImageSize, ProductImages};
+/// use serde_json::json;
+///
+/// let images = json!({
+///     "1": {"sizes": {"100": {"w": 75, "h": 100}, "full": {"w": 1500, "h": 2000}}},
+///     "front_fr": {"imgid": "1", "rev": "12"}
+/// });
+/// let base = "https://images.openfoodfacts.org/".parse().unwrap();
+/// let images = ProductImages::new(base, "3850102123681", images.as_object().unwrap()).unwrap();
+/// assert_eq!(
+///     images.display_url(ImageField::Front, "de").unwrap().as_str(),
+///     "https://images.openfoodfacts.org/images/products/385/010/212/3681/front_fr.12.400.jpg"
+/// );
+/// assert_eq!(
+///     images.raw_url("1", ImageSize::Full).as_str(),
+///     "https://images.openfoodfacts.org/images/products/385/010/212/3681/1.jpg"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProductImages<'a> {
+    // The folder of the images.
+    folder: Url,
+    // The `images` object of the product.
+    images: &'a Map<String, Value>,
+    // The main language of the product, the first fallback.
+    main_lang: Option<String>,
+}
+
+impl<'a> ProductImages<'a> {
+    /// Returns the images of the given product, served by the given images
+    /// server.
+    ///
+    /// Returns [Error::Validation] if the barcode is invalid.
+    pub fn new(
+        images_url: Url,
+        barcode: &str,
+        images: &'a Map<String, Value>,
+    ) -> Result<Self, Error> {
+        validate_barcode(barcode)?;
+        let mut folder = images_url;
+        if let Ok(mut segments) = folder.path_segments_mut() {
+            segments.pop_if_empty().extend(&["images", "products"]);
+            segments.extend(barcode_path(barcode).split('/'));
+        }
+        Ok(Self {
+            folder,
+            images,
+            main_lang: None,
+        })
+    }
+
+    /// Sets the main language of the product, tried after the preferred
+    /// language by [ProductImages::best].
+    pub fn main_lang(mut self, lang: &str) -> Self {
+        self.main_lang = Some(lang.to_string());
+        self
+    }
+
+    /// Returns the selected images, sorted by key.
+    pub fn selected_images(&self) -> Vec<SelectedImage> {
+        let mut selected: Vec<SelectedImage> = self
+            .images
+            .iter()
+            .filter_map(|(key, value)| SelectedImage::from_entry(key, value))
+            .collect();
+        selected.sort_by(|a, b| a.key.cmp(&b.key));
+        selected
+    }
+
+    /// Returns the image selected for the given field and language.
+    pub fn selected(&self, field: ImageField, lang: &str) -> Option<SelectedImage> {
+        let key = format!("{}_{}", field, lang);
+        SelectedImage::from_entry(&key, self.images.get(&key)?)
+    }
+
+    /// Returns the best image selected for the given field: the one of the
+    /// preferred language, else of the main language of the product, else of
+    /// English, else the unlocalized one, else the first one by language.
+    pub fn best(&self, field: ImageField, lang: &str) -> Option<SelectedImage> {
+        let mut langs = vec![lang];
+        langs.extend(self.main_lang.as_deref());
+        langs.push("en");
+        if let Some(image) = langs.into_iter().find_map(|l| self.selected(field, l)) {
+            return Some(image);
+        }
+        let mut others: Vec<SelectedImage> = self
+            .selected_images()
+            .into_iter()
+            .filter(|image| image.field == field)
+            .collect();
+        others.sort_by(|a, b| a.lang.cmp(&b.lang));
+        others.into_iter().next()
+    }
+
+    /// Returns the URL of the given selected image in the given size.
+    pub fn url(&self, image: &SelectedImage, size: ImageSize) -> Url {
+        self.file_url(&format!("{}.{}.{}.jpg", image.key, image.rev, size))
+    }
+
+    /// Returns the 400 px URL of the best image selected for the given field.
+    pub fn display_url(&self, field: ImageField, lang: &str) -> Option<Url> {
+        self.best_url(field, lang, ImageSize::Display)
+    }
+
+    /// Returns the 200 px URL of the best image selected for the given field.
+    pub fn small_url(&self, field: ImageField, lang: &str) -> Option<Url> {
+        self.best_url(field, lang, ImageSize::Small)
+    }
+
+    /// Returns the 100 px URL of the best image selected for the given field.
+    pub fn thumb_url(&self, field: ImageField, lang: &str) -> Option<Url> {
+        self.best_url(field, lang, ImageSize::Thumb)
+    }
+
+    /// Returns the ids of the raw (uploaded) images, in upload order.
+    pub fn raw_images(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self
+            .images
+            .keys()
+            .map(String::as_str)
+            .filter(|key| key.chars().all(|c| c.is_ascii_digit()))
+            .collect();
+        ids.sort_by_key(|id| id.parse::<u64>().unwrap_or_default());
+        ids
+    }
+
+    /// Returns the URL of the given raw image. The raw images are available in
+    /// thumb, display and full size.
+    pub fn raw_url(&self, imgid: &str, size: ImageSize) -> Url {
+        match size {
+            ImageSize::Full => self.file_url(&format!("{}.jpg", imgid)),
+            size => self.file_url(&format!("{}.{}.jpg", imgid, size)),
+        }
+    }
+
+    fn best_url(&self, field: ImageField, lang: &str, size: ImageSize) -> Option<Url> {
+        self.best(field, lang).map(|image| self.url(&image, size))
+    }
+
+    // Returns the URL of the given file of the image folder.
+    fn file_url(&self, name: &str) -> Url {
+        let mut url = self.folder.clone();
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments.push(name);
+        }
+        url
+    }
+}
+
+// Returns a string or number JSON value as a string.
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn images() -> Value {
+        json!({
+            "1": {"uploaded_t": 1457680652},
+            "2": {"uploaded_t": "1457680653"},
+            "10": {"uploaded_t": 1457680654},
+            "front_fr": {"imgid": "2", "rev": "12"},
+            "front_de": {"imgid": 10, "rev": 15},
+            "ingredients": {"imgid": "1", "rev": "4"},
+            "nutrition_it": {"imgid": "1", "rev": "5"},
+            "nutrition_es": {"imgid": "1", "rev": "6"},
+            "other": {"rev": "1"}
+        })
+    }
+
+    fn base() -> Url {
+        Url::parse("https://images.openfoodfacts.org/").unwrap()
+    }
+
+    #[test]
+    fn paths() {
+        assert_eq!(barcode_path("3017620422003"), "301/762/042/2003");
+        assert_eq!(barcode_path("123456789"), "123/456/789");
+        assert_eq!(barcode_path("12345678"), "12345678");
+        assert_eq!(barcode_path("0000000001234"), "000/000/000/1234");
+    }
+
+    #[test]
+    fn sizes() {
+        let images = images();
+        let images =
+            ProductImages::new(base(), "3017620422003", images.as_object().unwrap()).unwrap();
+        let front = images.selected(ImageField::Front, "fr").unwrap();
+        let folder = "https://images.openfoodfacts.org/images/products/301/762/042/2003/";
+        assert_eq!(
+            images.url(&front, ImageSize::Full).as_str(),
+            format!("{}front_fr.12.full.jpg", folder)
+        );
+        assert_eq!(
+            images.small_url(ImageField::Front, "fr").unwrap().as_str(),
+            format!("{}front_fr.12.200.jpg", folder)
+        );
+        assert_eq!(
+            images.thumb_url(ImageField::Front, "de").unwrap().as_str(),
+            format!("{}front_de.15.100.jpg", folder)
+        );
+        assert_eq!(
+            images.raw_url("2", ImageSize::Display).as_str(),
+            format!("{}2.400.jpg", folder)
+        );
+        assert_eq!(images.raw_images(), ["1", "2", "10"]);
+    }
+
+    #[test]
+    fn short_barcode() {
+        let images = images();
+        let images = ProductImages::new(base(), "20004293", images.as_object().unwrap()).unwrap();
+        assert_eq!(
+            images.raw_url("1", ImageSize::Full).as_str(),
+            "https://images.openfoodfacts.org/images/products/20004293/1.jpg"
+        );
+        assert!(ProductImages::new(base(), "2000 4293", &Map::new()).is_err());
+    }
+
+    #[test]
+    fn best() {
+        let images = images();
+        let images = ProductImages::new(base(), "3017620422003", images.as_object().unwrap())
+            .unwrap()
+            .main_lang("de");
+        let best = |field, lang| images.best(field, lang).map(|image| image.key);
+        assert_eq!(best(ImageField::Front, "fr").unwrap(), "front_fr");
+        // The main language, then English.
+        assert_eq!(best(ImageField::Front, "it").unwrap(), "front_de");
+        // The unlocalized image.
+        assert_eq!(best(ImageField::Ingredients, "fr").unwrap(), "ingredients");
+        // The first language.
+        assert_eq!(best(ImageField::Nutrition, "fr").unwrap(), "nutrition_es");
+        assert_eq!(best(ImageField::Packaging, "fr"), None);
+
+        let front = images.best(ImageField::Front, "de").unwrap();
+        assert_eq!(front.imgid, "10");
+        assert_eq!(front.rev, "15");
+        assert_eq!(front.lang.as_deref(), Some("de"));
+        assert_eq!(images.selected_images().len(), 5);
+    }
+}
@@ -0,0 +1,373 @@
+// The image requests of the write API: uploads (multipart forms sent to
+// `cgi/product_image_upload.pl`), selections of a crop of an uploaded image
+// (`cgi/product_image_crop.pl`) and unselections
+// (`cgi/product_image_unselect.pl`).
+use crate::de::number_or_string;
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The `status` of the successful image requests.
+const STATUS_OK: &str = "status ok";
+
+/// The rotations of the images accepted by the server, in degrees.
+const ANGLES: [i32; 4] = [0, 90, 180, 270];
+
+/// An image upload.
+///
+/// ```
+/// use openfoodfacts::{ImageField, ImageUpload};
+///
+/// let upload = ImageUpload::new("3850102123681", &ImageField::Front.key("fr"), vec![0xff, 0xd8])
+///     .file_name("front.jpg");
+/// assert_eq!(upload.imagefield(), "front_fr");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageUpload {
+    // The barcode of the product.
+    code: String,
+    // The selected image the upload is for, i.e. `front_fr`, or `other`.
+    imagefield: String,
+    // The file name sent with the image.
+    file_name: String,
+    // The image file contents.
+    bytes: Vec<u8>,
+}
+
+impl ImageUpload {
+    /// Creates an upload of the given image file contents. The `imagefield` is
+    /// the key of the image to select, i.e. `front_fr`, or `other` to upload
+    /// the image without selecting it.
+    pub fn new(barcode: &str, imagefield: &str, bytes: Vec<u8>) -> Self {
+        Self {
+            code: barcode.to_string(),
+            imagefield: imagefield.to_string(),
+            file_name: format!("{}.jpg", imagefield),
+            bytes,
+        }
+    }
+
+    /// Creates an upload of the given image file. The file name is kept.
+    ///
+    /// Returns [Error::Io] if the file cannot be read.
+    pub fn from_file<P: AsRef<Path>>(
+        barcode: &str,
+        imagefield: &str,
+        path: P,
+    ) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let upload = Self::new(barcode, imagefield, std::fs::read(path)?);
+        Ok(match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) => upload.file_name(name),
+            None => upload,
+        })
+    }
+
+    /// Sets the file name sent with the image. Its extension gives the content
+    /// type, JPEG if unknown.
+    pub fn file_name(mut self, name: &str) -> Self {
+        self.file_name = name.to_string();
+        self
+    }
+
+    /// Returns the barcode of the product.
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Returns the key of the image to select.
+    pub fn imagefield(&self) -> &str {
+        &self.imagefield
+    }
+
+    /// Returns the form fields, without the image.
+    pub(crate) fn form(&self) -> Vec<(&str, &str)> {
+        vec![("code", &self.code), ("imagefield", &self.imagefield)]
+    }
+
+    /// Returns the name of the form field of the image, its file name, content
+    /// type and contents.
+    pub(crate) fn file(&self) -> (String, &str, &str, &[u8]) {
+        (
+            format!("imgupload_{}", self.imagefield),
+            &self.file_name,
+            content_type(&self.file_name),
+            &self.bytes,
+        )
+    }
+}
+
+/// The selection of an uploaded image, optionally cropped, rotated and
+/// filtered, as a selected image of a product.
+///
+/// ```
+/// use openfoodfacts::{ImageCrop, ImageField};
+///
+/// let crop = ImageCrop::new("3850102123681", "2", &ImageField::Nutrition.key("fr"))
+///     .crop(100, 150, 900, 1200)
+///     .angle(90)
+///     .normalize(true);
+/// assert_eq!(crop.imagefield(), "nutrition_fr");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageCrop {
+    code: String,
+    imgid: String,
+    imagefield: String,
+    // The crop box: x1, y1, x2, y2.
+    crop: Option<[u32; 4]>,
+    angle: i32,
+    normalize: bool,
+    white_magic: bool,
+}
+
+impl ImageCrop {
+    /// Creates the selection of the uploaded image `imgid` as the given
+    /// selected image, i.e. `front_fr`. Without crop box, the whole image is
+    /// selected.
+    pub fn new(barcode: &str, imgid: &str, imagefield: &str) -> Self {
+        Self {
+            code: barcode.to_string(),
+            imgid: imgid.to_string(),
+            imagefield: imagefield.to_string(),
+            crop: None,
+            angle: 0,
+            normalize: false,
+            white_magic: false,
+        }
+    }
+
+    /// Sets the crop box, in pixels of the full size image: top left (x1, y1)
+    /// and bottom right (x2, y2) corners.
+    pub fn crop(mut self, x1: u32, y1: u32, x2: u32, y2: u32) -> Self {
+        self.crop = Some([x1, y1, x2, y2]);
+        self
+    }
+
+    /// Sets the clockwise rotation of the image, in degrees: 0, 90, 180 or 270.
+    /// The crop box applies to the rotated image. Other angles are rejected
+    /// with [Error::Validation] when the selection is sent.
+    pub fn angle(mut self, angle: i32) -> Self {
+        self.angle = angle;
+        self
+    }
+
+    /// Enables the normalization of the colors.
+    pub fn normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    /// Enables the removal of the white background.
+    pub fn white_magic(mut self, white_magic: bool) -> Self {
+        self.white_magic = white_magic;
+        self
+    }
+
+    /// Returns the barcode of the product.
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Returns the key of the selected image.
+    pub fn imagefield(&self) -> &str {
+        &self.imagefield
+    }
+
+    /// Returns [Error::Validation] if the angle is not a quarter turn.
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        if ANGLES.contains(&self.angle) {
+            Ok(())
+        } else {
+            Err(Error::Validation(format!(
+                "invalid angle {}: 0, 90, 180 or 270 expected",
+                self.angle
+            )))
+        }
+    }
+
+    /// Returns the form fields.
+    pub(crate) fn form(&self) -> Vec<(&'static str, String)> {
+        let mut form = vec![
+            ("code", self.code.clone()),
+            ("imgid", self.imgid.clone()),
+            ("id", self.imagefield.clone()),
+        ];
+        if let Some(crop) = self.crop {
+            for (name, value) in ["x1", "y1", "x2", "y2"].iter().zip(crop.iter()) {
+                form.push((name, value.to_string()));
+            }
+            form.push(("coordinates_image_size", String::from("full")));
+        }
+        form.push(("angle", self.angle.to_string()));
+        form.push(("normalize", self.normalize.to_string()));
+        form.push(("white_magic", self.white_magic.to_string()));
+        form
+    }
+}
+
+/// The response of the image requests.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageResponse {
+    /// `status ok` or `status not ok`.
+    pub status: String,
+    /// The key of the selected image, i.e. `front_fr`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imagefield: Option<String>,
+    /// The uploaded or selected image.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<ImageInfo>,
+    /// The error message of failed requests.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The error code of failed requests.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub debug: Option<String>,
+}
+
+/// The image of an [ImageResponse]. The URLs are relative to the folder of the
+/// product images.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageInfo {
+    /// The id of the uploaded image. For duplicate uploads, the id of the
+    /// image uploaded before.
+    #[serde(
+        deserialize_with = "number_or_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub imgid: Option<u64>,
+    /// The thumbnail of an uploaded image, i.e. `2.100.jpg`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumb_url: Option<String>,
+    /// The image to crop of an uploaded image, i.e. `2.400.jpg`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crop_url: Option<String>,
+    /// The display image of a selected image, i.e. `front_fr.5.400.jpg`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_url: Option<String>,
+}
+
+impl ImageResponse {
+    /// Returns true if the request succeeded.
+    pub fn is_ok(&self) -> bool {
+        self.status == STATUS_OK
+    }
+
+    /// Returns the id of the uploaded image.
+    pub fn imgid(&self) -> Option<u64> {
+        self.image.as_ref()?.imgid
+    }
+
+    /// Returns true if the upload failed because the image was already
+    /// uploaded for the product.
+    pub fn is_duplicate(&self) -> bool {
+        !self.is_ok()
+            && [&self.error, &self.debug]
+                .iter()
+                .filter_map(|text| text.as_deref())
+                .any(|text| text.contains("already"))
+    }
+}
+
+// Returns the content type of an image file, from its extension.
+fn content_type(file_name: &str) -> &'static str {
+    let extension = Path::new(file_name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "heic" => "image/heic",
+        _ => "image/jpeg",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upload() {
+        let upload = ImageUpload::new("3850102123681", "front_fr", vec![1, 2, 3]);
+        assert_eq!(
+            upload.form(),
+            vec![("code", "3850102123681"), ("imagefield", "front_fr")]
+        );
+        let (name, file_name, content_type, bytes) = upload.file();
+        assert_eq!(name, "imgupload_front_fr");
+        assert_eq!(file_name, "front_fr.jpg");
+        assert_eq!(content_type, "image/jpeg");
+        assert_eq!(bytes, [1, 2, 3]);
+
+        let upload = upload.file_name("photo.PNG");
+        assert_eq!(upload.file().2, "image/png");
+        assert!(matches!(
+            ImageUpload::from_file("1", "other", "/no/such/file.jpg"),
+            Err(Error::Io(_))
+        ));
+    }
+
+    #[test]
+    fn crop() {
+        let form = |crop: ImageCrop| {
+            crop.form()
+                .into_iter()
+                .map(|(n, v)| format!("{}={}", n, v))
+                .collect::<Vec<_>>()
+                .join("&")
+        };
+        assert_eq!(
+            form(ImageCrop::new("123", "2", "front_fr")),
+            "code=123&imgid=2&id=front_fr&angle=0&normalize=false&white_magic=false"
+        );
+        assert_eq!(
+            form(
+                ImageCrop::new("123", "2", "front_fr")
+                    .crop(10, 20, 300, 400)
+                    .angle(270)
+                    .white_magic(true)
+            ),
+            "code=123&imgid=2&id=front_fr&x1=10&y1=20&x2=300&y2=400\
+             &coordinates_image_size=full&angle=270&normalize=false&white_magic=true"
+        );
+        assert!(ImageCrop::new("123", "2", "front_fr").validate().is_ok());
+        assert!(matches!(
+            ImageCrop::new("123", "2", "front_fr").angle(45).validate(),
+            Err(Error::Validation(message)) if message == "invalid angle 45: 0, 90, 180 or 270 expected"
+        ));
+    }
+
+    #[test]
+    fn responses() {
+        let uploaded: ImageResponse = serde_json::from_str(
+            r#"{
+                "status": "status ok",
+                "imagefield": "front_fr",
+                "image": {"imgid": 2, "thumb_url": "2.100.jpg", "crop_url": "2.400.jpg"}
+            }"#,
+        )
+        .unwrap();
+        assert!(uploaded.is_ok());
+        assert_eq!(uploaded.imgid(), Some(2));
+        assert!(!uploaded.is_duplicate());
+
+        let duplicate: ImageResponse = serde_json::from_str(
+            r#"{
+                "status": "status not ok",
+                "imagefield": "front_fr",
+                "error": "This picture has already been sent.",
+                "debug": "imgid_already_exists",
+                "image": {"imgid": "1"}
+            }"#,
+        )
+        .unwrap();
+        assert!(!duplicate.is_ok());
+        assert!(duplicate.is_duplicate());
+        assert_eq!(duplicate.imgid(), Some(1));
+    }
+}
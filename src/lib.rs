@@ -1,7 +1,9 @@
 //! # Openfoodfacts Rust client
 //!
-//! This crate provides two client implementations, one for V0 and one V2 of the API.
-//! Endpoints common to all versions work exactly in the same manner.
+//! This crate provides client implementations for the V0, V2 and V3 versions of the
+//! API. Endpoints common to all versions work exactly in the same manner. The V3
+//! client only adds the V3 product endpoints, with JSON updates and structured
+//! errors and warnings.
 //!
 //! It is recommended to use the V2 client.
 //!
@@ -12,25 +14,110 @@
 //! set, the builder produces a client with
 //!
 //! * locale: "world"
+//! * flavor: [Flavor::Food]
+//! * host: `https://{locale}.openfoodfacts.org/`
+//! * retry policy: None (requests are sent once)
+//! * rate limits: None (requests are sent immediately)
 //! * auth: None (only needed for write operations)
 //! * user agent: "OffRustClient - {OS name} - Version {lib version} - {github repo URL}"
+//! * HTTP options: gzip and brotli decompression, system proxy, reqwest defaults
+//!   otherwise
 //!
 //! ```
 //! use openfoodfacts::{self as off, Locale};
 //!
-//! # fn main() -> Result<(), reqwest::Error> {
+//! # fn main() -> Result<(), off::Error> {
 //! let client = off::v0().locale(Locale::from("fr")).build()?;
 //! # Ok(())
 //! # }
 //! ```
 //!
+//! ## Async client
+//!
+//! With the `async` feature enabled, `async_v0()` and `async_v2()` return
+//! builders producing an `AsyncOffClient`. It supports the same options and
+//! methods as the blocking client, the methods returning futures.
+//!
+//! ```toml
+//! openfoodfacts = { git = "https://github.com/openfoodfacts/openfoodfacts-rust.git", features = ["async"] }
+//! ```
+//!
+//! ## Selecting the database
+//!
+//! The same API serves Open Food Facts, Open Beauty Facts, Open Pet Food Facts
+//! and Open Products Facts. The [Flavor] option selects the database.
+//!
+//! ```
+//! use openfoodfacts::{self as off, Flavor};
+//!
+//! # fn main() -> Result<(), off::Error> {
+//! let client = off::v2().flavor(Flavor::Beauty).build()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Selecting the server
+//!
+//! The client can target another server, i.e. the staging server, a self-hosted
+//! instance or a local mock, by setting its domain, scheme and port, or a full URL
+//! template where `{locale}` is replaced by the locale. If the host does not
+//! support locale subdomains, the locale is sent in the `cc` and `lc` query
+//! parameters.
+//!
+//! ```
+//! use openfoodfacts as off;
+//!
+//! # fn main() -> Result<(), off::Error> {
+//! let staging = off::v2().domain("openfoodfacts.net").build()?;
+//! let mock = off::v2().url_template("http://127.0.0.1:8080/").build()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## HTTP options
+//!
+//! The builder exposes the main options of the underlying reqwest client:
+//! timeouts, response decompression, proxy, root certificates and connection
+//! pool. For anything else, a pre-configured reqwest client can be given with
+//! [OffBuilder::http_client].
+//!
+//! The clients are not tied to reqwest: they send their requests through a
+//! [Transport] (`AsyncTransport` for the async client), reqwest by default.
+//! Another HTTP library or an in-memory fake can be plugged in with
+//! [OffBuilder::transport]. A [RecordingTransport] saves the interactions of
+//! another transport to a cassette file, which a [ReplayTransport] serves back
+//! without network access.
+//!
+//! ```
+//! use openfoodfacts as off;
+//! use std::time::Duration;
+//!
+//! # fn main() -> Result<(), off::Error> {
+//! let client = off::v2()
+//!     .connect_timeout(Duration::from_secs(5))
+//!     .timeout(Duration::from_secs(30))
+//!     .proxy("http://proxy.example.com:3128")
+//!     .no_proxy("localhost,.internal")
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! # Processing client responses
 //!
 //! Contrary to other client implementations, the rust client returns the HTTP response
 //! returned by the OFF server unchanged. It is up to the caller to deserialize the response
 //! in a JSON object that suits its use case.
 //!
-//! ```rust
+//! Typed variants are available for the most common calls: for instance,
+//! [OffClient::product_response] returns a [ProductResponse], whose [Product]
+//! has the commonly used fields typed and keeps the others as JSON values.
+//!
+//! Failures are reported with the [Error] enum: invalid URLs, transport errors,
+//! unsuccessful HTTP statuses (with a dedicated variant for rate limiting), unknown
+//! products, undecodable responses and invalid arguments.
+//!
+//! ```no_run
 //! use openfoodfacts as off;
 //! use std::collections::HashMap;
 //! use serde_json::Value;
@@ -56,7 +143,7 @@
 //! input a query object, which must be build with the appropriate query
 //! builder.
 //!
-//! ```
+//! ```no_run
 //! use openfoodfacts as off;
 //!
 //! # fn main() -> Result<(), off::Error> {
@@ -71,16 +158,83 @@
 //! # }
 //! ```
 #![allow(dead_code)]
-pub use crate::client::{Error, HttpClient, HttpResponse, OffClient, Result};
+#[cfg(feature = "async")]
+pub use crate::async_client::{AsyncHttpClient, AsyncOffClient, AsyncResult};
+#[cfg(feature = "async")]
+pub use crate::async_transport::{
+    AsyncHttpResponse, AsyncReqwestTransport, AsyncTransport, BodyStream, BoxFuture,
+};
+use crate::auth::Auth;
+pub use crate::bulk::{BulkEdit, BulkReport, BulkRow, RowOutcome, RowResult};
+pub use crate::canonical::{normalize_tag, Canonical, Canonicalizer};
+pub use crate::cassette::{
+    Cassette, Interaction, RecordedRequest, RecordedResponse, RecordingTransport, ReplayTransport,
+};
+pub use crate::client::{HttpClient, OffClient, Result};
+pub use crate::dry_run::{FieldValue, PreparedRequest};
+pub use crate::edit::{EditResponse, NutritionDataPer, ProductEdit};
+pub use crate::error::Error;
+pub use crate::facet::{Facet, FacetResponse, FacetTag};
+pub use crate::flavor::Flavor;
+pub use crate::host::Host;
+use crate::http_options::HttpOptions;
+pub use crate::image::{barcode_path, ImageField, ImageSize, ProductImages, SelectedImage};
+pub use crate::image_edit::{ImageCrop, ImageInfo, ImageResponse, ImageUpload};
 pub use crate::locale::Locale;
+pub use crate::nutriments::{Nutrient, NutrientValues, Nutriments};
 pub use crate::output::Output;
-use crate::types::{Version, V0, V2};
+pub use crate::packaging::{PackagingComponent, PackagingValidator, PACKAGING_TAXONOMIES};
+pub use crate::product::{Product, ProductResponse, SearchResponse};
+pub use crate::product_v3::{
+    ApiMessage, Impact, MessageField, MessageId, ProductResponseV3, ProductUpdate, ResponseStatus,
+};
+use crate::ratelimit::RateLimiter;
+pub use crate::ratelimit::{EndpointClass, Quota, RateLimits};
+pub use crate::retry::RetryPolicy;
+pub use crate::taxonomy::{Taxonomy, TaxonomyEntry};
+pub use crate::transport::{
+    HeaderMap, HttpRequest, HttpResponse, Method, ReqwestTransport, StatusCode, Transport,
+    TransportError, TransportErrorKind,
+};
+#[cfg(feature = "async")]
+use crate::types::Async;
+use crate::types::{Blocking, Mode, Version, V0, V2, V3};
+pub use reqwest::Certificate;
 use std::env::consts::OS;
+use std::marker::PhantomData;
+use std::time::Duration;
 
+#[cfg(feature = "async")]
+mod async_client;
+#[cfg(feature = "async")]
+mod async_transport;
+mod auth;
+mod bulk;
+mod canonical;
+mod cassette;
 mod client;
+mod de;
+mod dry_run;
+mod edit;
+mod error;
+mod facet;
+mod flavor;
+mod host;
+mod http_options;
+mod image;
+mod image_edit;
 mod locale;
+mod nutriments;
 mod output;
+mod packaging;
+mod product;
+mod product_v3;
+mod ratelimit;
+mod retry;
 pub mod search;
+mod taxonomy;
+mod taxonomy_text;
+mod transport;
 mod types;
 
 /// The version of this library.
@@ -91,7 +245,7 @@ pub const VERSION: &str = "alpha";
 /// ```
 /// use openfoodfacts as off;
 ///
-/// # fn main() -> Result<(), reqwest::Error> {
+/// # fn main() -> Result<(), off::Error> {
 /// let client = off::v0().locale(off::Locale::new("fr", None)).build()?;
 /// # Ok(())
 /// # }
@@ -105,7 +259,7 @@ pub fn v0() -> OffBuilder<V0> {
 /// ```
 /// use openfoodfacts as off;
 ///
-/// # fn main() -> Result<(), reqwest::Error> {
+/// # fn main() -> Result<(), off::Error> {
 /// let client = off::v2().locale(off::Locale::new("fr", None)).build()?;
 /// # Ok(())
 /// # }
@@ -114,27 +268,107 @@ pub fn v2() -> OffBuilder<V2> {
     OffBuilder::new(V2 {})
 }
 
-/// Authentication tuple (username, password).
-#[derive(Debug, PartialEq)]
-struct Auth(String, String);
+/// Returns a builder to build an OffClient supporting the API V3. API V3 only
+/// implements the product endpoints: reads and JSON updates.
+///
+/// ```
+/// use openfoodfacts as off;
+///
+/// # fn main() -> Result<(), off::Error> {
+/// let client = off::v3().locale(off::Locale::new("fr", None)).build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn v3() -> OffBuilder<V3> {
+    OffBuilder::new(V3 {})
+}
+
+/// Returns a builder to build an AsyncOffClient supporting the API V0.
+///
+/// ```
+/// use openfoodfacts as off;
+///
+/// # fn main() -> Result<(), off::Error> {
+/// let client = off::async_v0().locale(off::Locale::new("fr", None)).build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "async")]
+pub fn async_v0() -> AsyncOffBuilder<V0> {
+    OffBuilder::new(V0 {})
+}
+
+/// Returns a builder to build an AsyncOffClient supporting the API V2.
+///
+/// ```
+/// use openfoodfacts as off;
+///
+/// # fn main() -> Result<(), off::Error> {
+/// let client = off::async_v2().locale(off::Locale::new("fr", None)).build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "async")]
+pub fn async_v2() -> AsyncOffBuilder<V2> {
+    OffBuilder::new(V2 {})
+}
+
+/// Returns a builder to build an AsyncOffClient supporting the API V3.
+///
+/// ```
+/// use openfoodfacts as off;
+///
+/// # fn main() -> Result<(), off::Error> {
+/// let client = off::async_v3().locale(off::Locale::new("fr", None)).build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "async")]
+pub fn async_v3() -> AsyncOffBuilder<V3> {
+    OffBuilder::new(V3 {})
+}
 
 /// The Open Food Facts API client builder.
+///
+/// The `M` marker selects the kind of client produced by `build()`:
+/// a blocking [OffClient] by default, or an `AsyncOffClient` when the `async`
+/// feature is enabled (see `AsyncOffBuilder`).
 #[derive(Debug)]
-pub struct OffBuilder<V> {
+pub struct OffBuilder<V, M: Mode = Blocking> {
     // The version marker
     v: V,
+    // The client kind marker.
+    mode: PhantomData<M>,
     // The default locale.
     locale: Locale,
+    // The server.
+    host: Host,
+    // The retry policy of idempotent requests. Optional.
+    retry: Option<RetryPolicy>,
+    // The client-side rate limits. Optional.
+    rate_limits: Option<RateLimits>,
     // Optional. Only needed for write operations.
     auth: Option<Auth>,
     // The User-Agent header value to send on each request. Optional.
     // If not given, use the default user agent.
     user_agent: Option<String>,
+    // The options of the underlying HTTP client.
+    http: HttpOptions,
+    // The transport, replacing the reqwest one built from the HTTP options.
+    transport: Option<Box<M::Transport>>,
+    // If true, the write requests are described instead of being sent.
+    dry_run: bool,
 }
 
-impl<V> OffBuilder<V>
+/// The builder of [AsyncOffClient] objects. Supports the same options as the
+/// blocking [OffBuilder].
+#[cfg(feature = "async")]
+pub type AsyncOffBuilder<V> = OffBuilder<V, Async>;
+
+impl<V, M> OffBuilder<V, M>
 where
     V: Version + Copy,
+    M: Mode,
 {
     /// Sets the default locale.
     pub fn locale(mut self, value: Locale) -> Self {
@@ -142,9 +376,101 @@ where
         self
     }
 
-    /// Sets the authentication credentials.
+    /// Sets the database flavor. The flavor selects the default domain of the
+    /// server, i.e. "openbeautyfacts.org" for [Flavor::Beauty].
+    pub fn flavor(mut self, flavor: Flavor) -> Self {
+        self.host.set_flavor(flavor);
+        self
+    }
+
+    /// Sets the base domain of the server, i.e. "openfoodfacts.net". Overrides the
+    /// domain of the flavor. The locale
+    /// subdomain is prepended to the domain unless disabled with
+    /// [OffBuilder::locale_subdomains] or if the domain is an IP address or
+    /// "localhost".
+    pub fn domain(mut self, domain: &str) -> Self {
+        self.host.set_domain(domain);
+        self
+    }
+
+    /// Sets the URL scheme, "https" by default.
+    pub fn scheme(mut self, scheme: &str) -> Self {
+        self.host.set_scheme(scheme);
+        self
+    }
+
+    /// Sets the server port.
+    pub fn port(mut self, port: u16) -> Self {
+        self.host.set_port(port);
+        self
+    }
+
+    /// Enables or disables the locale subdomains. If disabled, the locale is
+    /// selected with the `cc` and `lc` query parameters.
+    pub fn locale_subdomains(mut self, enabled: bool) -> Self {
+        self.host.set_locale_subdomains(enabled);
+        self
+    }
+
+    /// Sets a full URL template of the server, overriding the domain, scheme and
+    /// port. The optional `{locale}` placeholder is replaced by the locale. Without
+    /// it, the locale is selected with the `cc` and `lc` query parameters. The
+    /// template must end with a slash.
+    ///
+    /// ```
+    /// use openfoodfacts as off;
+    ///
+    /// let builder = off::v2().url_template("http://{locale}.off.localhost:8080/");
+    /// ```
+    pub fn url_template(mut self, template: &str) -> Self {
+        self.host.set_template(template);
+        self
+    }
+
+    /// Sets the retry policy of idempotent requests. By default, requests are
+    /// not retried.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Sets the client-side rate limits. By default, requests are not limited.
+    /// Use `RateLimits::default()` to stay under the quotas published by OFF.
+    pub fn rate_limits(mut self, limits: RateLimits) -> Self {
+        self.rate_limits = Some(limits);
+        self
+    }
+
+    /// Sets the authentication credentials. They are sent in the Basic
+    /// `Authorization` header of each request and used by the client's
+    /// `login()` method. The password is redacted in the Debug output of the
+    /// builder and the client.
     pub fn auth(mut self, username: &str, password: &str) -> Self {
-        self.auth = Some(Auth(username.to_string(), password.to_string()));
+        self.auth = Some(Auth::new(username, password));
+        self
+    }
+
+    /// Enables or disables the dry-run mode. In dry-run mode, the write
+    /// methods (edits, image requests, V3 updates and `login()`) do not send
+    /// their request: they return [Error::DryRun] with a description of it,
+    /// credentials redacted. Read requests are sent as usual. Disabled by
+    /// default.
+    ///
+    /// ```
+    /// use openfoodfacts::{self as off, ProductEdit};
+    ///
+    /// let client = off::v2().auth("user", "pwd").dry_run(true).build().unwrap();
+    /// let edit = ProductEdit::new("3850102123681").brands("Kraš");
+    /// match client.edit_product(&edit) {
+    ///     Err(off::Error::DryRun(request)) => {
+    ///         assert!(request.to_string().contains("+ brands: Kraš\n"));
+    ///         assert!(!request.to_string().contains("pwd"));
+    ///     }
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn dry_run(mut self, enabled: bool) -> Self {
+        self.dry_run = enabled;
         self
     }
 
@@ -154,54 +480,237 @@ where
         self
     }
 
-    /// Creates a new OffClient for the `V` version of the API, with the current
-    /// builder options. Consumes the builder.
-    pub fn build(self) -> std::result::Result<OffClient<V>, reqwest::Error> {
-        let client = self.build_http_client()?;
-        Ok(OffClient::new(self.v, self.locale, client))
+    /// Sets the timeout of the connect phase. No timeout by default.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.http.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout of each read operation. No timeout by default.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.http.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout of a whole request, from connecting until the response
+    /// body has been received. No timeout by default for the async client, 30s
+    /// for the blocking client.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.http.timeout = Some(timeout);
+        self
+    }
+
+    /// Enables or disables the gzip decompression of responses. Enabled by
+    /// default.
+    pub fn gzip(mut self, enabled: bool) -> Self {
+        self.http.gzip = enabled;
+        self
+    }
+
+    /// Enables or disables the brotli decompression of responses. Enabled by
+    /// default.
+    pub fn brotli(mut self, enabled: bool) -> Self {
+        self.http.brotli = enabled;
+        self
+    }
+
+    /// Sends all requests through the given HTTP(S) proxy URL, i.e.
+    /// "http://proxy.example.com:3128". By default, the system proxy given by
+    /// the `HTTP_PROXY`/`HTTPS_PROXY` environment variables is used. An invalid
+    /// URL fails the build.
+    pub fn proxy(mut self, url: &str) -> Self {
+        self.http.proxy = Some(url.to_string());
+        self
+    }
+
+    /// Sets the hosts bypassing the proxy given with [OffBuilder::proxy], as a
+    /// comma separated list of domains, IP addresses or CIDR blocks, i.e.
+    /// "localhost,.internal,10.0.0.0/8".
+    pub fn no_proxy(mut self, hosts: &str) -> Self {
+        self.http.no_proxy = Some(hosts.to_string());
+        self
+    }
+
+    /// Adds a trusted root certificate, i.e. for a server using a private
+    /// certificate authority.
+    ///
+    /// ```no_run
+    /// use openfoodfacts::{self as off, Certificate};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let pem = std::fs::read("ca.pem")?;
+    /// let client = off::v2()
+    ///     .add_root_certificate(Certificate::from_pem(&pem)?)
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn add_root_certificate(mut self, certificate: Certificate) -> Self {
+        self.http.root_certificates.push(certificate);
+        self
+    }
+
+    /// Sets the timeout after which idle pooled connections are closed.
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.http.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the maximum number of idle pooled connections per host.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.http.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Uses the given pre-configured reqwest client: a `reqwest::blocking::Client`
+    /// for the blocking client or a `reqwest::Client` for the async client. The
+    /// HTTP options set on the builder are then ignored. The client needs a
+    /// cookie store to keep the login session.
+    pub fn http_client(mut self, client: M::HttpClient) -> Self {
+        self.transport = Some(M::reqwest_transport(client));
+        self
     }
 
     // Creates a new builder for the given API version with the following
     // defaults:
     //
     // * The default locale is set to `Locale::default()`.
+    // * The host is set to `Host::default()`.
+    // * No retry policy
+    // * No rate limits
     // * No authentication credentials
+    // * The default HTTP options
+    // * Dry-run mode disabled
     // * The user agent is set to
     //   `OffRustClient - {OS name} - Version {lib version} - {github repo URL}`
     fn new(v: V) -> Self {
         Self {
             v,
+            mode: PhantomData,
             locale: Locale::default(),
+            host: Host::default(),
+            retry: None,
+            rate_limits: None,
             auth: None,
+            transport: None,
             // TODO: Get version and URL from somewhere else ?
             user_agent: Some(format!(
                 "OffRustClient - {} - Version {} - {}",
                 OS, VERSION, "https://github.com/openfoodfacts/openfoodfacts-rust"
             )),
+            http: HttpOptions::default(),
+            dry_run: false,
         }
     }
 
-    fn build_http_client(&self) -> reqwest::Result<HttpClient> {
-        // Default headers
-        let mut headers = reqwest::header::HeaderMap::new();
-        if let Some(ref auth) = self.auth {
-            // TODO: Needs to be encoded !
-            let basic_auth = format!("Basic {}:{}", auth.0, auth.1);
-            headers.insert(
-                reqwest::header::AUTHORIZATION,
-                reqwest::header::HeaderValue::from_str(&basic_auth).unwrap(),
-            );
+    // The default headers sent by the client on each request, whatever its
+    // transport.
+    fn default_headers(&self) -> std::result::Result<HeaderMap, Error> {
+        let mut headers = HeaderMap::new();
+        if let Some(ref user_agent) = self.user_agent {
+            let value = user_agent
+                .parse()
+                .map_err(|_| Error::Validation(format!("invalid user agent '{}'", user_agent)))?;
+            headers.insert(http::header::USER_AGENT, value);
         }
-        let mut cb = HttpClient::builder();
-        if !headers.is_empty() {
-            cb = cb.default_headers(headers);
+        if let Some(ref auth) = self.auth {
+            headers.insert(http::header::AUTHORIZATION, auth.basic_header()?);
         }
-        if let Some(ref user_agent) = self.user_agent {
-            cb = cb.user_agent(user_agent);
+        Ok(headers)
+    }
+
+    // Returns an async reqwest client builder with the HTTP options. The
+    // blocking client builder wraps it. The cookie store keeps the session
+    // cookie set by the login.
+    fn http_client_builder(&self) -> std::result::Result<reqwest::ClientBuilder, Error> {
+        let cb = reqwest::ClientBuilder::new().cookie_store(true);
+        Ok(self.http.apply(cb)?)
+    }
+}
+
+impl<V> OffBuilder<V, Blocking>
+where
+    V: Version + Copy,
+{
+    /// Creates a new OffClient for the `V` version of the API, with the current
+    /// builder options. Consumes the builder.
+    pub fn build(mut self) -> std::result::Result<OffClient<V>, Error> {
+        let headers = self.default_headers()?;
+        let transport = match self.transport.take() {
+            Some(transport) => transport,
+            None => Box::new(ReqwestTransport::new(self.build_http_client()?)),
+        };
+        Ok(OffClient::new(
+            self.v,
+            self.locale,
+            self.host,
+            self.retry,
+            self.rate_limits.map(RateLimiter::new),
+            headers,
+            self.auth,
+            transport,
+            self.dry_run,
+        ))
+    }
+
+    /// Sends the requests through the given transport instead of the default
+    /// reqwest one. The HTTP options set on the builder are then ignored.
+    pub fn transport<T>(mut self, transport: T) -> Self
+    where
+        T: Transport + 'static,
+    {
+        self.transport = Some(Box::new(transport));
+        self
+    }
+
+    fn build_http_client(&self) -> std::result::Result<HttpClient, Error> {
+        let mut cb = reqwest::blocking::ClientBuilder::from(self.http_client_builder()?);
+        // The blocking client has its own (30s) timeout.
+        if let Some(timeout) = self.http.timeout {
+            cb = cb.timeout(timeout);
         }
-        // TODO: gzip compression
-        // TODO: Timeouts
-        cb.build()
+        Ok(cb.build()?)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<V> OffBuilder<V, Async>
+where
+    V: Version + Copy,
+{
+    /// Creates a new AsyncOffClient for the `V` version of the API, with the
+    /// current builder options. Consumes the builder.
+    pub fn build(mut self) -> std::result::Result<AsyncOffClient<V>, Error> {
+        let headers = self.default_headers()?;
+        let transport = match self.transport.take() {
+            Some(transport) => transport,
+            None => Box::new(AsyncReqwestTransport::new(self.build_http_client()?)),
+        };
+        Ok(AsyncOffClient::new(
+            self.v,
+            self.locale,
+            self.host,
+            self.retry,
+            self.rate_limits.map(RateLimiter::new),
+            headers,
+            self.auth,
+            transport,
+            self.dry_run,
+        ))
+    }
+
+    /// Sends the requests through the given transport instead of the default
+    /// reqwest one. The HTTP options set on the builder are then ignored.
+    pub fn transport<T>(mut self, transport: T) -> Self
+    where
+        T: AsyncTransport + 'static,
+    {
+        self.transport = Some(Box::new(transport));
+        self
+    }
+
+    fn build_http_client(&self) -> std::result::Result<AsyncHttpClient, Error> {
+        Ok(self.http_client_builder()?.build()?)
     }
 }
 
@@ -213,6 +722,9 @@ mod tests {
     fn default() {
         let builder = v0();
         assert_eq!(builder.locale, Locale::default());
+        assert_eq!(builder.host, Host::default());
+        assert_eq!(builder.retry, None);
+        assert_eq!(builder.rate_limits, None);
         assert_eq!(builder.auth, None);
         assert_eq!(
             builder.user_agent,
@@ -230,10 +742,41 @@ mod tests {
             .auth("user", "pwd")
             .user_agent("user agent");
         assert_eq!(builder.locale, Locale::new("gr", None));
+        assert_eq!(builder.auth, Some(Auth::new("user", "pwd")));
+        assert!(!format!("{:?}", builder).contains("pwd"));
+        assert_eq!(builder.user_agent, Some(String::from("user agent")));
+    }
+
+    #[test]
+    fn http_options() {
+        let builder = v0()
+            .connect_timeout(Duration::from_secs(5))
+            .read_timeout(Duration::from_secs(10))
+            .timeout(Duration::from_secs(30))
+            .brotli(false)
+            .proxy("http://proxy:3128")
+            .no_proxy("localhost")
+            .pool_max_idle_per_host(4);
+        assert_eq!(builder.http.connect_timeout, Some(Duration::from_secs(5)));
+        assert_eq!(builder.http.read_timeout, Some(Duration::from_secs(10)));
+        assert_eq!(builder.http.timeout, Some(Duration::from_secs(30)));
+        assert!(builder.http.gzip);
+        assert!(!builder.http.brotli);
+        assert_eq!(builder.http.proxy.as_deref(), Some("http://proxy:3128"));
+        assert_eq!(builder.http.no_proxy.as_deref(), Some("localhost"));
+        assert_eq!(builder.http.pool_max_idle_per_host, Some(4));
+        assert!(builder.build().is_ok());
+    }
+
+    #[test]
+    fn host_options() {
+        let builder = v0().scheme("http").domain("openfoodfacts.net").port(8080);
         assert_eq!(
-            builder.auth,
-            Some(Auth(String::from("user"), String::from("pwd")))
+            builder.host.url(&Locale::default()).unwrap().as_str(),
+            "http://world.openfoodfacts.net:8080/"
         );
-        assert_eq!(builder.user_agent, Some(String::from("user agent")));
+
+        let builder = v0().url_template("http://127.0.0.1:8080/");
+        assert!(!builder.host.has_locale_subdomains());
     }
 }
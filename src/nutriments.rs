@@ -0,0 +1,391 @@
+// The typed model of the `nutriments` object of the products. The server sends
+// a flat object whose keys combine a nutrient id and a suffix, i.e.
+// `sugars_100g` or `energy-kcal_prepared_unit`. Nutriments groups these keys by
+// nutrient and serializes back to the flat shape, keeping the original JSON
+// representation of the values that were not changed.
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use serde_json::{Map, Number, Value};
+use std::collections::BTreeMap;
+
+/// The values of a nutrient, either as sold or as prepared.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NutrientValues {
+    /// The value in the standard unit of the nutrient (`{id}` key).
+    pub standard: Option<f64>,
+    /// The value as entered by the contributor, in [NutrientValues::unit]
+    /// (`{id}_value` key).
+    pub value: Option<f64>,
+    /// The unit of the entered value, i.e. `g` or `kcal` (`{id}_unit` key).
+    pub unit: Option<String>,
+    /// The value per 100g or 100ml (`{id}_100g` key).
+    pub per_100g: Option<f64>,
+    /// The value per serving (`{id}_serving` key).
+    pub per_serving: Option<f64>,
+    /// The modifier of the entered value: `~` for estimated values, `<` or `>`
+    /// for bounds (`{id}_modifier` key).
+    pub modifier: Option<String>,
+}
+
+impl NutrientValues {
+    /// Returns true if no value is set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns true if the entered value is an estimate.
+    pub fn is_estimated(&self) -> bool {
+        self.modifier.as_deref() == Some("~")
+    }
+}
+
+/// A nutrient: its values as sold and as prepared (`{id}_prepared_*` keys).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Nutrient {
+    pub as_sold: NutrientValues,
+    pub prepared: NutrientValues,
+}
+
+/// The nutrition facts of a product, by nutrient id.
+///
+/// The keys that do not follow the `{id}_{suffix}` scheme, or whose value is
+/// not of the expected type, are kept as they are in [Nutriments::other]. The
+/// values read from JSON and left unchanged are serialized as they were read,
+/// i.e. `"56.3"` stays a string and `539.0` a float.
+///
+/// ```
+/// use openfoodfacts::Nutriments;
+///
+/// let nutriments: Nutriments = serde_json::from_str(r#"{
+///     "sugars": 56.3,
+///     "sugars_100g": 56.3,
+///     "sugars_serving": 8.4,
+///     "sugars_unit": "g",
+///     "energy-kcal_prepared_100g": 120
+/// }"#).unwrap();
+/// let sugars = nutriments.get("sugars").unwrap();
+/// assert_eq!(sugars.as_sold.per_serving, Some(8.4));
+/// assert_eq!(sugars.as_sold.unit.as_deref(), Some("g"));
+/// let energy = nutriments.get("energy-kcal").unwrap();
+/// assert_eq!(energy.prepared.per_100g, Some(120.0));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Nutriments {
+    nutrients: BTreeMap<String, Nutrient>,
+    /// The keys that are not nutrient values.
+    pub other: Map<String, Value>,
+    // The nutrient values as read, by key.
+    raw: Map<String, Value>,
+}
+
+// The suffixes of the values of a nutrient, without the `_prepared` part.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Standard,
+    Value,
+    Unit,
+    Per100g,
+    PerServing,
+    Modifier,
+}
+
+impl Field {
+    const ALL: [Field; 6] = [
+        Field::Standard,
+        Field::Value,
+        Field::Unit,
+        Field::Per100g,
+        Field::PerServing,
+        Field::Modifier,
+    ];
+
+    fn suffix(self) -> &'static str {
+        match self {
+            Field::Standard => "",
+            Field::Value => "_value",
+            Field::Unit => "_unit",
+            Field::Per100g => "_100g",
+            Field::PerServing => "_serving",
+            Field::Modifier => "_modifier",
+        }
+    }
+
+    // Returns the field, if the suffix of a key is a known one.
+    fn of(suffix: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|f| f.suffix() == suffix)
+    }
+}
+
+const PREPARED: &str = "_prepared";
+
+impl Nutriments {
+    /// Creates empty nutrition facts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the nutrient with the given id, i.e. `sugars` or `energy-kcal`.
+    pub fn get(&self, id: &str) -> Option<&Nutrient> {
+        self.nutrients.get(id)
+    }
+
+    /// Returns a mutable reference to the nutrient with the given id, inserting
+    /// an empty nutrient if missing.
+    pub fn entry(&mut self, id: &str) -> &mut Nutrient {
+        self.nutrients.entry(id.to_string()).or_default()
+    }
+
+    /// Removes the nutrient with the given id and returns it.
+    pub fn remove(&mut self, id: &str) -> Option<Nutrient> {
+        self.nutrients.remove(id)
+    }
+
+    /// Returns the nutrients by id, sorted by id.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Nutrient)> {
+        self.nutrients.iter().map(|(id, n)| (id.as_str(), n))
+    }
+
+    /// Returns the number of nutrients.
+    pub fn len(&self) -> usize {
+        self.nutrients.len()
+    }
+
+    /// Returns true if there are neither nutrients nor other keys.
+    pub fn is_empty(&self) -> bool {
+        self.nutrients.is_empty() && self.other.is_empty()
+    }
+
+    /// Returns the flat JSON object of the nutrition facts.
+    pub fn to_map(&self) -> Map<String, Value> {
+        let mut map = self.other.clone();
+        for (id, nutrient) in &self.nutrients {
+            for (values, prepared) in [(&nutrient.as_sold, ""), (&nutrient.prepared, PREPARED)] {
+                for field in Field::ALL.iter().copied() {
+                    if let Some(value) = get_field(values, field) {
+                        let key = format!("{}{}{}", id, prepared, field.suffix());
+                        let value = match self.raw.get(&key) {
+                            Some(raw) if same_field(field, raw, &value) => raw.clone(),
+                            _ => value,
+                        };
+                        map.insert(key, value);
+                    }
+                }
+            }
+        }
+        map
+    }
+
+    // Sets the value of the given key, returning false if the key is not a
+    // nutrient value.
+    fn set(&mut self, key: &str, value: &Value) -> bool {
+        let (id, suffix) = match key.find('_') {
+            Some(i) => key.split_at(i),
+            None => (key, ""),
+        };
+        let (prepared, suffix) = match suffix.strip_prefix(PREPARED) {
+            Some(suffix) => (true, suffix),
+            None => (false, suffix),
+        };
+        let field = match Field::of(suffix) {
+            Some(field) if !id.is_empty() => field,
+            _ => return false,
+        };
+        let mut values = self.get(id).cloned().unwrap_or_default();
+        let target = if prepared {
+            &mut values.prepared
+        } else {
+            &mut values.as_sold
+        };
+        if !set_field(target, field, value) {
+            return false;
+        }
+        self.nutrients.insert(id.to_string(), values);
+        self.raw.insert(key.to_string(), value.clone());
+        true
+    }
+}
+
+impl From<Map<String, Value>> for Nutriments {
+    fn from(map: Map<String, Value>) -> Self {
+        let mut nutriments = Self::new();
+        for (key, value) in map {
+            if !nutriments.set(&key, &value) {
+                nutriments.other.insert(key, value);
+            }
+        }
+        nutriments
+    }
+}
+
+impl<'de> Deserialize<'de> for Nutriments {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Map::deserialize(deserializer).map(Self::from)
+    }
+}
+
+impl Serialize for Nutriments {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_map().serialize(serializer)
+    }
+}
+
+// Sets a field from a JSON value. Numbers may be given as strings. Returns
+// false if the value has not the expected type.
+fn set_field(values: &mut NutrientValues, field: Field, value: &Value) -> bool {
+    let text = |value: &Value| value.as_str().map(String::from);
+    let number = |value: &Value| match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    };
+    match field {
+        Field::Unit => values.unit = text(value),
+        Field::Modifier => values.modifier = text(value),
+        Field::Standard => values.standard = number(value),
+        Field::Value => values.value = number(value),
+        Field::Per100g => values.per_100g = number(value),
+        Field::PerServing => values.per_serving = number(value),
+    }
+    get_field(values, field).is_some()
+}
+
+fn get_field(values: &NutrientValues, field: Field) -> Option<Value> {
+    let number = |n: Option<f64>| n.and_then(to_number).map(Value::Number);
+    match field {
+        Field::Unit => values.unit.clone().map(Value::String),
+        Field::Modifier => values.modifier.clone().map(Value::String),
+        Field::Standard => number(values.standard),
+        Field::Value => number(values.value),
+        Field::Per100g => number(values.per_100g),
+        Field::PerServing => number(values.per_serving),
+    }
+}
+
+// Returns true if a value as read gives the given serialized value of a field.
+fn same_field(field: Field, raw: &Value, value: &Value) -> bool {
+    let mut values = NutrientValues::default();
+    set_field(&mut values, field, raw) && get_field(&values, field).as_ref() == Some(value)
+}
+
+// Converts a value to a JSON number, integral values to integers.
+fn to_number(n: f64) -> Option<Number> {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        Some(Number::from(n as i64))
+    } else {
+        Number::from_f64(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample() -> Value {
+        json!({
+            "energy-kcal": 539,
+            "energy-kcal_100g": 539,
+            "energy-kcal_serving": 80.9,
+            "energy-kcal_unit": "kcal",
+            "energy-kcal_value": 539,
+            "sugars_100g": 56.3,
+            "sugars_modifier": "~",
+            "sugars_prepared_100g": 12.5,
+            "sugars_prepared_unit": "g",
+            "nova-group": 4,
+            "nutrition-score-fr_100g": 26,
+            "fruits-vegetables-nuts-estimate-from-ingredients_100g": 13,
+            "energy-kcal_value_computed": 538.5,
+            "salt_unit": 12
+        })
+    }
+
+    #[test]
+    fn group_by_nutrient() {
+        let nutriments: Nutriments = serde_json::from_value(sample()).unwrap();
+        let energy = nutriments.get("energy-kcal").unwrap();
+        assert_eq!(energy.as_sold.standard, Some(539.0));
+        assert_eq!(energy.as_sold.value, Some(539.0));
+        assert_eq!(energy.as_sold.per_serving, Some(80.9));
+        assert_eq!(energy.as_sold.unit.as_deref(), Some("kcal"));
+        assert!(energy.prepared.is_empty());
+
+        let sugars = nutriments.get("sugars").unwrap();
+        assert!(sugars.as_sold.is_estimated());
+        assert_eq!(sugars.prepared.per_100g, Some(12.5));
+        assert_eq!(sugars.prepared.unit.as_deref(), Some("g"));
+
+        assert_eq!(
+            nutriments.iter().map(|(id, _)| id).collect::<Vec<_>>(),
+            vec![
+                "energy-kcal",
+                "fruits-vegetables-nuts-estimate-from-ingredients",
+                "nova-group",
+                "nutrition-score-fr",
+                "sugars"
+            ]
+        );
+        // Unknown suffix and unexpected type.
+        assert_eq!(nutriments.other.len(), 2);
+        assert!(nutriments.get("salt").is_none());
+    }
+
+    #[test]
+    fn string_numbers() {
+        let nutriments: Nutriments =
+            serde_json::from_value(json!({"fat_100g": "17.5", "fat_serving": ""})).unwrap();
+        let fat = nutriments.get("fat").unwrap();
+        assert_eq!(fat.as_sold.per_100g, Some(17.5));
+        assert_eq!(fat.as_sold.per_serving, None);
+        assert_eq!(nutriments.other["fat_serving"], "");
+    }
+
+    #[test]
+    fn round_trip() {
+        let nutriments: Nutriments = serde_json::from_value(sample()).unwrap();
+        assert_eq!(serde_json::to_value(&nutriments).unwrap(), sample());
+    }
+
+    #[test]
+    fn round_trip_representation() {
+        // As sent by the server for a product edited with the old web form.
+        let json = json!({
+            "carbohydrates": "57.5",
+            "carbohydrates_100g": 57.5,
+            "carbohydrates_unit": "g",
+            "carbohydrates_value": "57.5",
+            "energy-kcal": 539.0,
+            "energy-kcal_100g": 539.0,
+            "energy-kcal_unit": "kcal",
+            "energy-kcal_value": 539,
+            "sugars": 56.3,
+            "sugars_100g": 56.3,
+            "sugars_serving": "8.4",
+            "sugars_unit": "g",
+            "sugars_value": "56.3"
+        });
+        let mut nutriments: Nutriments = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(nutriments.get("sugars").unwrap().as_sold.value, Some(56.3));
+        assert_eq!(serde_json::to_value(&nutriments).unwrap(), json);
+
+        // Changed values are serialized as numbers.
+        nutriments.entry("sugars").as_sold.value = Some(50.0);
+        let changed = serde_json::to_value(&nutriments).unwrap();
+        assert_eq!(changed["sugars_value"], json!(50));
+        assert_eq!(changed["sugars_serving"], "8.4");
+    }
+
+    #[test]
+    fn edit() {
+        let mut nutriments = Nutriments::new();
+        let salt = &mut nutriments.entry("salt").as_sold;
+        salt.per_100g = Some(0.25);
+        salt.unit = Some(String::from("g"));
+        assert_eq!(
+            serde_json::to_value(&nutriments).unwrap(),
+            json!({"salt_100g": 0.25, "salt_unit": "g"})
+        );
+        assert!(nutriments.remove("salt").is_some());
+        assert!(nutriments.is_empty());
+    }
+}
@@ -0,0 +1,413 @@
+// The packaging components of the products: the `packagings` array, each
+// component having a shape, a material and a recycling instruction from the
+// packaging_shapes, packaging_materials and packaging_recycling taxonomies.
+//
+// API V2 sends the taxonomy values as tag ids, i.e. `"shape": "en:bottle"`.
+// API V3 sends and expects objects: `"shape": {"id": "en:bottle"}` or, for
+// free text in the language of the request, `"shape": {"lc_name": "bouteille"}`.
+use crate::canonical::{is_lang, Canonical, Canonicalizer};
+use crate::de::number_or_string;
+use crate::error::Error;
+use crate::product::Product;
+use crate::taxonomy::Taxonomy;
+use serde::de::{Deserializer, Error as _};
+use serde::ser::{SerializeMap, Serializer};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// The names of the packaging taxonomies: shapes, materials and recycling
+/// instructions, as given to [crate::OffClient::taxonomy_response].
+pub const PACKAGING_TAXONOMIES: [&str; 3] = [
+    "packaging_shapes",
+    "packaging_materials",
+    "packaging_recycling",
+];
+
+/// A packaging component of a product, i.e. 6 PET bottles of 25 cl to recycle.
+///
+/// The shape, material and recycling are tag ids of the packaging taxonomies,
+/// i.e. `en:bottle`, or free text in the language of the update, validated and
+/// converted to tag ids by a [PackagingValidator].
+///
+/// ```
+/// use openfoodfacts::PackagingComponent;
+///
+/// let bottle = PackagingComponent::new()
+///     .number_of_units(6)
+///     .shape("en:bottle")
+///     .material("en:pet-1-polyethylene-terephthalate")
+///     .recycling("en:recycle")
+///     .quantity_per_unit("25 cl")
+///     .weight_measured(12.5);
+/// assert_eq!(
+///     serde_json::to_value(&bottle).unwrap()["shape"],
+///     serde_json::json!({"id": "en:bottle"})
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PackagingComponent {
+    /// The number of units of the component, i.e. 6 bottles.
+    #[serde(
+        deserialize_with = "number_or_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub number_of_units: Option<u32>,
+    /// The shape, i.e. `en:bottle`.
+    #[serde(
+        deserialize_with = "tag",
+        serialize_with = "tag_object",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub shape: Option<String>,
+    /// The material, i.e. `en:glass`.
+    #[serde(
+        deserialize_with = "tag",
+        serialize_with = "tag_object",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub material: Option<String>,
+    /// The recycling instruction, i.e. `en:recycle`.
+    #[serde(
+        deserialize_with = "tag",
+        serialize_with = "tag_object",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub recycling: Option<String>,
+    /// The quantity of product in each unit, i.e. "25 cl".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quantity_per_unit: Option<String>,
+    /// The weight of one unit given by the producer, in grams.
+    #[serde(
+        deserialize_with = "number_or_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub weight_specified: Option<f64>,
+    /// The weight of one unit measured by a contributor, in grams.
+    #[serde(
+        deserialize_with = "number_or_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub weight_measured: Option<f64>,
+    /// The other fields, i.e. the ones computed by the server.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl PackagingComponent {
+    /// Creates an empty component.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of units.
+    pub fn number_of_units(mut self, units: u32) -> Self {
+        self.number_of_units = Some(units);
+        self
+    }
+
+    /// Sets the shape: a tag id or a name.
+    pub fn shape(mut self, shape: &str) -> Self {
+        self.shape = Some(shape.to_string());
+        self
+    }
+
+    /// Sets the material: a tag id or a name.
+    pub fn material(mut self, material: &str) -> Self {
+        self.material = Some(material.to_string());
+        self
+    }
+
+    /// Sets the recycling instruction: a tag id or a name.
+    pub fn recycling(mut self, recycling: &str) -> Self {
+        self.recycling = Some(recycling.to_string());
+        self
+    }
+
+    /// Sets the quantity of product in each unit, i.e. "25 cl".
+    pub fn quantity_per_unit(mut self, quantity: &str) -> Self {
+        self.quantity_per_unit = Some(quantity.to_string());
+        self
+    }
+
+    /// Sets the weight of one unit given by the producer, in grams.
+    pub fn weight_specified(mut self, grams: f64) -> Self {
+        self.weight_specified = Some(grams);
+        self
+    }
+
+    /// Sets the weight of one unit measured by a contributor, in grams.
+    pub fn weight_measured(mut self, grams: f64) -> Self {
+        self.weight_measured = Some(grams);
+        self
+    }
+
+    /// Returns the weight of one unit, in grams: the measured weight, or the
+    /// weight given by the producer.
+    pub fn weight(&self) -> Option<f64> {
+        self.weight_measured.or(self.weight_specified)
+    }
+}
+
+impl Product {
+    /// Returns the packaging components of the product, from the `packagings`
+    /// field. Empty if the product has none or if the field was not requested.
+    ///
+    /// Returns [Error::Decode] if a component is invalid.
+    ///
+    /// ```
+    /// use openfoodfacts::Product;
+    ///
+    /// let product: Product = serde_json::from_str(r#"{
+    ///     "code": "3017620422003",
+    ///     "packagings": [
+    ///         {"number_of_units": 1, "shape": "en:jar", "material": "en:glass"},
+    ///         {"shape": {"id": "en:lid", "lc_name": "Couvercle"}, "weight_measured": "2.5"}
+    ///     ]
+    /// }"#).unwrap();
+    /// let packagings = product.packagings().unwrap();
+    /// assert_eq!(packagings[0].material.as_deref(), Some("en:glass"));
+    /// assert_eq!(packagings[1].shape.as_deref(), Some("en:lid"));
+    /// assert_eq!(packagings[1].weight(), Some(2.5));
+    /// ```
+    pub fn packagings(&self) -> Result<Vec<PackagingComponent>, Error> {
+        match self.other.get("packagings") {
+            None | Some(Value::Null) => Ok(Vec::new()),
+            Some(packagings) => Ok(Vec::<PackagingComponent>::deserialize(packagings)?),
+        }
+    }
+}
+
+/// Validates packaging components against the packaging taxonomies, and
+/// converts their names to tag ids.
+///
+/// ```
+/// use openfoodfacts::{PackagingComponent, PackagingValidator, Taxonomy};
+///
+/// let shapes = Taxonomy::from_txt("en: Bottle\nfr: Bouteille").unwrap();
+/// let materials = Taxonomy::from_txt("en: Glass\nfr: Verre").unwrap();
+/// let recycling = Taxonomy::from_txt("en: Recycle\nfr: À recycler").unwrap();
+/// let validator = PackagingValidator::new(&shapes, &materials, &recycling);
+///
+/// let bottle = PackagingComponent::new().shape("Bouteille").material("verre");
+/// let bottle = validator.validate(&bottle, "fr").unwrap();
+/// assert_eq!(bottle.shape.as_deref(), Some("en:bottle"));
+/// assert_eq!(bottle.material.as_deref(), Some("en:glass"));
+///
+/// let can = PackagingComponent::new().shape("Canette");
+/// assert!(validator.validate(&can, "fr").is_err());
+/// ```
+#[derive(Debug)]
+pub struct PackagingValidator<'a> {
+    shapes: Canonicalizer<'a>,
+    materials: Canonicalizer<'a>,
+    recycling: Canonicalizer<'a>,
+}
+
+impl<'a> PackagingValidator<'a> {
+    /// Creates a validator for the given taxonomies, i.e. loaded with
+    /// [crate::OffClient::taxonomy_response] and the names of
+    /// [PACKAGING_TAXONOMIES].
+    pub fn new(shapes: &'a Taxonomy, materials: &'a Taxonomy, recycling: &'a Taxonomy) -> Self {
+        Self {
+            shapes: Canonicalizer::new(shapes),
+            materials: Canonicalizer::new(materials),
+            recycling: Canonicalizer::new(recycling),
+        }
+    }
+
+    /// Returns the given component with its shape, material and recycling
+    /// converted to tag ids. The names are read in the given language, unless
+    /// prefixed by a language, i.e. `fr:bouteille`.
+    ///
+    /// Returns [Error::Validation] listing the values missing from the
+    /// taxonomies, and the zero numbers of units and negative weights.
+    pub fn validate(
+        &self,
+        component: &PackagingComponent,
+        lang: &str,
+    ) -> Result<PackagingComponent, Error> {
+        let mut component = component.clone();
+        let mut errors = Vec::new();
+        for (name, value, canonicalizer) in [
+            ("shape", &mut component.shape, &self.shapes),
+            ("material", &mut component.material, &self.materials),
+            ("recycling", &mut component.recycling, &self.recycling),
+        ] {
+            if let Some(text) = value.as_mut() {
+                match canonicalizer.canonicalize(text, lang) {
+                    Canonical::Known(id) => *text = id,
+                    Canonical::Unknown(_) => errors.push(format!("unknown {} '{}'", name, text)),
+                }
+            }
+        }
+        if component.number_of_units == Some(0) {
+            errors.push(String::from("zero number of units"));
+        }
+        for (name, weight) in [
+            ("weight_specified", component.weight_specified),
+            ("weight_measured", component.weight_measured),
+        ] {
+            if matches!(weight, Some(w) if !(w >= 0.0 && w.is_finite())) {
+                errors.push(format!("invalid {}", name));
+            }
+        }
+        if errors.is_empty() {
+            Ok(component)
+        } else {
+            Err(Error::Validation(errors.join(", ")))
+        }
+    }
+
+    /// Validates all the given components. See [PackagingValidator::validate].
+    /// The errors give the index of the invalid components.
+    pub fn validate_all(
+        &self,
+        components: &[PackagingComponent],
+        lang: &str,
+    ) -> Result<Vec<PackagingComponent>, Error> {
+        let mut valid = Vec::new();
+        let mut errors = Vec::new();
+        for (i, component) in components.iter().enumerate() {
+            match self.validate(component, lang) {
+                Ok(component) => valid.push(component),
+                Err(Error::Validation(message)) => {
+                    errors.push(format!("component {}: {}", i, message))
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        if errors.is_empty() {
+            Ok(valid)
+        } else {
+            Err(Error::Validation(errors.join("; ")))
+        }
+    }
+}
+
+// Deserializes a taxonomy value given as a tag id (API V2) or as an object
+// with an `id` or an `lc_name` (API V3).
+fn tag<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let text = match Value::deserialize(deserializer)? {
+        Value::Null => None,
+        Value::String(s) => Some(s),
+        Value::Object(object) => ["id", "lc_name"]
+            .iter()
+            .find_map(|key| object.get(*key).and_then(Value::as_str))
+            .map(String::from),
+        other => {
+            return Err(D::Error::custom(format!(
+                "expected a tag or an object, found {}",
+                other
+            )))
+        }
+    };
+    Ok(text.filter(|s| !s.trim().is_empty()))
+}
+
+// Serializes a taxonomy value as the objects expected by API V3: `id` for the
+// tag ids, `lc_name` for the names.
+fn tag_object<S>(value: &Option<String>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let text = value.as_deref().unwrap_or_default();
+    let key = match text.split_once(':') {
+        Some((lang, _)) if is_lang(lang) => "id",
+        _ => "lc_name",
+    };
+    let mut map = serializer.serialize_map(Some(1))?;
+    map.serialize_entry(key, text)?;
+    map.end()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn component_json() {
+        let component: PackagingComponent = serde_json::from_value(json!({
+            "number_of_units": "2",
+            "shape": {"id": "en:bottle", "lc_name": "bouteille"},
+            "material": "",
+            "recycling": {"lc_name": "à recycler"},
+            "quantity_per_unit": "1 L",
+            "weight_specified": 30,
+            "material_score": 75
+        }))
+        .unwrap();
+        assert_eq!(component.number_of_units, Some(2));
+        assert_eq!(component.shape.as_deref(), Some("en:bottle"));
+        assert_eq!(component.material, None);
+        assert_eq!(component.recycling.as_deref(), Some("à recycler"));
+        assert_eq!(component.weight(), Some(30.0));
+        assert_eq!(
+            serde_json::to_value(&component).unwrap(),
+            json!({
+                "number_of_units": 2,
+                "shape": {"id": "en:bottle"},
+                "recycling": {"lc_name": "à recycler"},
+                "quantity_per_unit": "1 L",
+                "weight_specified": 30.0,
+                "material_score": 75
+            })
+        );
+        assert!(serde_json::from_value::<PackagingComponent>(json!({"shape": 1})).is_err());
+
+        // Tag ids in all the languages of the taxonomies, free text otherwise.
+        let component = PackagingComponent::new()
+            .shape("pt_br:garrafa")
+            .material("ast:vidru")
+            .recycling("Note: recycle");
+        assert_eq!(
+            serde_json::to_value(&component).unwrap(),
+            json!({
+                "shape": {"id": "pt_br:garrafa"},
+                "material": {"id": "ast:vidru"},
+                "recycling": {"lc_name": "Note: recycle"}
+            })
+        );
+    }
+
+    #[test]
+    fn validate() {
+        let shapes =
+            Taxonomy::from_txt("en: Bottle\nfr: Bouteille\n\nen: Lid\nfr: Couvercle").unwrap();
+        let materials = Taxonomy::from_txt("en: Glass\nfr: Verre").unwrap();
+        let recycling = Taxonomy::from_txt("en: Recycle").unwrap();
+        let validator = PackagingValidator::new(&shapes, &materials, &recycling);
+        let components = [
+            PackagingComponent::new()
+                .shape("fr:couvercle")
+                .recycling("en:recycle"),
+            PackagingComponent::new()
+                .number_of_units(0)
+                .shape("Bottle")
+                .material("Plastique")
+                .weight_measured(-1.0),
+        ];
+        assert_eq!(
+            validator
+                .validate(&components[0], "de")
+                .unwrap()
+                .shape
+                .as_deref(),
+            Some("en:lid")
+        );
+        match validator.validate_all(&components, "fr") {
+            Err(Error::Validation(message)) => assert_eq!(
+                message,
+                "component 1: unknown shape 'Bottle', unknown material 'Plastique', \
+                 zero number of units, invalid weight_measured"
+            ),
+            r => panic!("unexpected result {:?}", r),
+        }
+        let valid = validator.validate_all(&components[..1], "fr").unwrap();
+        assert_eq!(valid[0].recycling.as_deref(), Some("en:recycle"));
+    }
+}
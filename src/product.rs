@@ -0,0 +1,373 @@
+// The typed model of the products returned by the product, search and
+// listing endpoints. Only the commonly used fields are typed: the others are
+// kept as JSON values.
+use crate::de::{null_as_default, number_or_default, number_or_string};
+use crate::nutriments::Nutriments;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// The key prefix of the localized product names, i.e. `product_name_fr`.
+const PRODUCT_NAME_PREFIX: &str = "product_name_";
+
+/// A product.
+///
+/// All the fields are optional: depending on the `fields` output parameter and
+/// on the data entered by the contributors, any field may be missing. The
+/// fields that are not typed are kept in [Product::other], so that a product
+/// serializes back to the JSON object it was read from.
+///
+/// ```
+/// use openfoodfacts::Product;
+///
+/// let product: Product = serde_json::from_str(r#"{
+///     "code": "3017620422003",
+///     "product_name": "Nutella",
+///     "product_name_fr": "Nutella",
+///     "brands_tags": ["ferrero"],
+///     "nutriscore_grade": "e",
+///     "nova_group": 4
+/// }"#).unwrap();
+/// assert_eq!(product.code, "3017620422003");
+/// assert_eq!(product.name("fr"), Some("Nutella"));
+/// assert_eq!(product.nova_group, Some(4));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Product {
+    /// The barcode.
+    pub code: String,
+    /// The main language of the product, i.e. `fr`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    /// The name in the main language.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub product_name: Option<String>,
+    /// The generic name (legal name) in the main language.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generic_name: Option<String>,
+    /// The brands, as entered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brands: Option<String>,
+    /// The brand tags.
+    #[serde(
+        deserialize_with = "null_as_default",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub brands_tags: Vec<String>,
+    /// The quantity, as entered, i.e. "400 g".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<String>,
+    /// The category tags, i.e. `en:breakfast-cereals`.
+    #[serde(
+        deserialize_with = "null_as_default",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub categories_tags: Vec<String>,
+    /// The label tags, i.e. `en:organic`.
+    #[serde(
+        deserialize_with = "null_as_default",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub labels_tags: Vec<String>,
+    /// The allergen tags, i.e. `en:milk`.
+    #[serde(
+        deserialize_with = "null_as_default",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub allergens_tags: Vec<String>,
+    /// The nutrition facts.
+    #[serde(
+        deserialize_with = "null_as_default",
+        skip_serializing_if = "Nutriments::is_empty"
+    )]
+    pub nutriments: Nutriments,
+    /// The Nutri-Score grade, `a` to `e`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nutriscore_grade: Option<String>,
+    /// The Nutri-Score score.
+    #[serde(
+        deserialize_with = "number_or_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub nutriscore_score: Option<i32>,
+    /// The NOVA group, 1 to 4.
+    #[serde(
+        deserialize_with = "number_or_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub nova_group: Option<u8>,
+    /// The Eco-Score grade, `a` to `e`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ecoscore_grade: Option<String>,
+    /// The Eco-Score score.
+    #[serde(
+        deserialize_with = "number_or_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub ecoscore_score: Option<i32>,
+    /// The URL of the front image in the main language.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+    /// The URL of the small front image in the main language.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_small_url: Option<String>,
+    /// The URL of the ingredients image in the main language.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_ingredients_url: Option<String>,
+    /// The URL of the nutrition facts image in the main language.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_nutrition_url: Option<String>,
+    /// The uploaded and selected images, by image id (`1`, `2`, ...,
+    /// `front_fr`, ...).
+    #[serde(
+        deserialize_with = "null_as_default",
+        skip_serializing_if = "Map::is_empty"
+    )]
+    pub images: Map<String, Value>,
+    /// The state tags, i.e. `en:complete`.
+    #[serde(
+        deserialize_with = "null_as_default",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub states_tags: Vec<String>,
+    /// The other fields.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl Product {
+    /// Returns the name of the product in the given language, falling back to
+    /// `product_name` if the language is the main language.
+    pub fn name(&self, lang: &str) -> Option<&str> {
+        let name = self
+            .other
+            .get(&format!("{}{}", PRODUCT_NAME_PREFIX, lang))
+            .and_then(Value::as_str)
+            .filter(|name| !name.is_empty());
+        match name {
+            Some(name) => Some(name),
+            None if self.lang.as_deref() == Some(lang) => self.product_name.as_deref(),
+            None => None,
+        }
+    }
+
+    /// Returns the non-empty names of the product by language.
+    pub fn names(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.other.iter().filter_map(|(key, value)| {
+            let lang = key.strip_prefix(PRODUCT_NAME_PREFIX)?;
+            let name = value.as_str().filter(|name| !name.is_empty())?;
+            Some((lang, name))
+        })
+    }
+}
+
+/// The response of the product endpoint.
+///
+/// `status` is 1 if the product was found, 0 otherwise.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProductResponse {
+    pub status: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_verbose: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub product: Option<Product>,
+}
+
+/// The response of the search and listing endpoints: `search`, `products` and
+/// `products_by`.
+///
+/// The response is generic over the product type, [Product] by default. A
+/// projection struct can be used instead, together with the `fields` output
+/// parameter. The numbers sent as strings by API V0 are normalized.
+///
+/// ```
+/// use openfoodfacts::SearchResponse;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Name {
+///     product_name: String,
+/// }
+///
+/// let response: SearchResponse<Name> = serde_json::from_str(r#"{
+///     "count": "1",
+///     "page": "1",
+///     "page_size": 24,
+///     "skip": 0,
+///     "products": [{"product_name": "Nutella"}]
+/// }"#).unwrap();
+/// assert_eq!(response.count, 1);
+/// assert_eq!(response.products[0].product_name, "Nutella");
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "P: DeserializeOwned"))]
+pub struct SearchResponse<P = Product> {
+    /// The total number of products matching the query.
+    #[serde(default, deserialize_with = "number_or_default")]
+    pub count: u64,
+    /// The page number, starting at 1.
+    #[serde(default, deserialize_with = "number_or_default")]
+    pub page: u64,
+    /// The number of pages. Zero if not sent by the server.
+    #[serde(default, deserialize_with = "number_or_default")]
+    pub page_count: u64,
+    /// The maximum number of products per page.
+    #[serde(default, deserialize_with = "number_or_default")]
+    pub page_size: u64,
+    /// The number of products of the previous pages.
+    #[serde(default, deserialize_with = "number_or_default")]
+    pub skip: u64,
+    /// The products of the page.
+    #[serde(default = "Vec::new")]
+    pub products: Vec<P>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample() -> Value {
+        json!({
+            "code": "3017620422003",
+            "lang": "fr",
+            "product_name": "Nutella",
+            "product_name_en": "Nutella hazelnut spread",
+            "product_name_de": "",
+            "brands": "Ferrero",
+            "brands_tags": ["ferrero"],
+            "quantity": "400 g",
+            "categories_tags": ["en:spreads", "en:sweet-spreads"],
+            "labels_tags": [],
+            "allergens_tags": ["en:milk", "en:nuts"],
+            "nutriments": {"sugars_100g": 56.3, "sugars_unit": "g"},
+            "nutriscore_grade": "e",
+            "nutriscore_score": 26,
+            "nova_group": "4",
+            "ecoscore_grade": "d",
+            "image_url": "https://images.openfoodfacts.org/images/products/301/762/042/2003/front_fr.442.400.jpg",
+            "images": {"1": {"uploaded_t": 1457680652}},
+            "states_tags": ["en:complete"],
+            "unique_scans_n": 3456
+        })
+    }
+
+    #[test]
+    fn deserialize() {
+        let product: Product = serde_json::from_value(sample()).unwrap();
+        assert_eq!(product.code, "3017620422003");
+        assert_eq!(product.brands_tags, vec!["ferrero"]);
+        assert_eq!(product.quantity.as_deref(), Some("400 g"));
+        assert_eq!(product.allergens_tags.len(), 2);
+        let sugars = product.nutriments.get("sugars").unwrap();
+        assert_eq!(sugars.as_sold.per_100g, Some(56.3));
+        assert_eq!(product.nutriscore_score, Some(26));
+        assert_eq!(product.nova_group, Some(4));
+        assert_eq!(product.ecoscore_score, None);
+        assert_eq!(product.images.len(), 1);
+        assert_eq!(product.other["unique_scans_n"], 3456);
+    }
+
+    #[test]
+    fn null_fields() {
+        // Some old products have null lists.
+        let product: Product = serde_json::from_value(json!({
+            "code": "3017620422003",
+            "brands_tags": null,
+            "categories_tags": null,
+            "nutriments": null,
+            "images": null
+        }))
+        .unwrap();
+        assert!(product.brands_tags.is_empty());
+        assert!(product.categories_tags.is_empty());
+        assert!(product.nutriments.is_empty());
+        assert!(product.images.is_empty());
+    }
+
+    #[test]
+    fn names() {
+        let product: Product = serde_json::from_value(sample()).unwrap();
+        assert_eq!(product.name("en"), Some("Nutella hazelnut spread"));
+        assert_eq!(product.name("fr"), Some("Nutella"));
+        assert_eq!(product.name("de"), None);
+        assert_eq!(
+            product.names().collect::<Vec<_>>(),
+            vec![("en", "Nutella hazelnut spread")]
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let product: Product = serde_json::from_value(sample()).unwrap();
+        let mut expected = sample();
+        // Normalized or skipped when serialized.
+        expected["nova_group"] = json!(4);
+        expected.as_object_mut().unwrap().remove("labels_tags");
+        assert_eq!(serde_json::to_value(&product).unwrap(), expected);
+    }
+
+    #[test]
+    fn response() {
+        let response: ProductResponse = serde_json::from_value(json!({
+            "code": "3017620422003",
+            "product": {"code": "3017620422003"},
+            "status": 1,
+            "status_verbose": "product found"
+        }))
+        .unwrap();
+        assert_eq!(response.status, 1);
+        assert_eq!(response.product.unwrap().code, "3017620422003");
+
+        let response: ProductResponse = serde_json::from_value(json!({
+            "code": "0000000000000",
+            "status": 0,
+            "status_verbose": "product not found"
+        }))
+        .unwrap();
+        assert_eq!(response.status, 0);
+        assert!(response.product.is_none());
+    }
+
+    #[test]
+    fn search_response() {
+        // API V0.
+        let response: SearchResponse = serde_json::from_value(json!({
+            "count": "2",
+            "page": "2",
+            "page_count": 1,
+            "page_size": "1",
+            "skip": 1,
+            "products": [{"code": "3017620422003", "nova_group": 4}]
+        }))
+        .unwrap();
+        assert_eq!(response.count, 2);
+        assert_eq!(response.page, 2);
+        assert_eq!(response.page_size, 1);
+        assert_eq!(response.skip, 1);
+        assert_eq!(response.products[0].nova_group, Some(4));
+
+        // API V2, with a projection.
+        #[derive(Debug, Deserialize)]
+        struct Code {
+            code: String,
+        }
+        let response: SearchResponse<Code> = serde_json::from_value(json!({
+            "count": 0,
+            "page": 1,
+            "page_count": 0,
+            "page_size": 24,
+            "products": []
+        }))
+        .unwrap();
+        assert_eq!(response.count, 0);
+        assert_eq!(response.skip, 0);
+        assert!(response.products.is_empty());
+
+        let response: SearchResponse<Code> =
+            serde_json::from_value(json!({"count": 1, "products": [{"code": "1"}]})).unwrap();
+        assert_eq!(response.products[0].code, "1");
+    }
+}
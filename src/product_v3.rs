@@ -0,0 +1,311 @@
+// The product API V3: `GET /api/v3/product/{barcode}` and
+// `PATCH /api/v3/product/{barcode}` with a JSON body. Both return an envelope
+// with a status, the product and the structured errors and warnings of the
+// request.
+use crate::packaging::PackagingComponent;
+use crate::product::Product;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// The status of an API V3 response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseStatus {
+    Success,
+    /// The request succeeded, the response has warnings.
+    SuccessWithWarnings,
+    /// The request succeeded partially, i.e. some fields were not updated.
+    SuccessWithErrors,
+    Failure,
+    /// A status unknown to this version of the crate.
+    #[default]
+    #[serde(other)]
+    Unknown,
+}
+
+/// The id and names of a result, message or impact.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MessageId {
+    /// The id, i.e. `product_found`.
+    pub id: String,
+    /// The English name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The name in the language of the request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lc_name: Option<String>,
+}
+
+/// The impact of an error or warning on the request.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Impact {
+    /// The request failed.
+    Failure,
+    /// The field was not updated, the rest of the request was processed.
+    FieldIgnored,
+    /// No impact, i.e. a value was normalized.
+    None,
+    /// An impact unknown to this version of the crate.
+    Other(String),
+}
+
+impl From<&str> for Impact {
+    fn from(id: &str) -> Self {
+        match id {
+            "failure" => Self::Failure,
+            "field_ignored" => Self::FieldIgnored,
+            "none" => Self::None,
+            id => Self::Other(id.to_string()),
+        }
+    }
+}
+
+/// The field an error or warning is about, with the value that was sent.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MessageField {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+/// An error or warning of an API V3 response.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiMessage {
+    /// The message, i.e. `invalid_code`.
+    pub message: MessageId,
+    /// The field the message is about, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<MessageField>,
+    /// The impact on the request.
+    pub impact: MessageId,
+}
+
+impl ApiMessage {
+    /// Returns the impact level.
+    pub fn impact(&self) -> Impact {
+        Impact::from(self.impact.id.as_str())
+    }
+}
+
+/// The response of the API V3 product endpoints, generic over the product type.
+///
+/// ```
+/// use openfoodfacts::{Impact, ProductResponseV3, ResponseStatus};
+///
+/// let response: ProductResponseV3 = serde_json::from_str(r#"{
+///     "code": "3850102123681",
+///     "status": "success_with_warnings",
+///     "result": {"id": "product_updated", "name": "Product updated"},
+///     "warnings": [{
+///         "message": {"id": "unrecognized_field"},
+///         "field": {"id": "nutella", "value": "yes"},
+///         "impact": {"id": "field_ignored"}
+///     }],
+///     "product": {"code": "3850102123681"}
+/// }"#).unwrap();
+/// assert!(response.is_success());
+/// assert_eq!(response.warnings[0].impact(), Impact::FieldIgnored);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "P: DeserializeOwned"))]
+pub struct ProductResponseV3<P = Product> {
+    /// The barcode of the request, normalized.
+    #[serde(default)]
+    pub code: String,
+    #[serde(default)]
+    pub status: ResponseStatus,
+    /// The result of the request, i.e. `product_found`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<MessageId>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ApiMessage>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<ApiMessage>,
+    /// The product, with the fields requested.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub product: Option<P>,
+}
+
+impl<P> ProductResponseV3<P> {
+    /// Returns true if the request succeeded, possibly with warnings.
+    pub fn is_success(&self) -> bool {
+        matches!(
+            self.status,
+            ResponseStatus::Success | ResponseStatus::SuccessWithWarnings
+        )
+    }
+}
+
+/// A JSON update of a product, sent with the V3 `PATCH` request. The product is
+/// created if it does not exist.
+///
+/// ```
+/// use openfoodfacts::ProductUpdate;
+/// use serde_json::json;
+///
+/// let update = ProductUpdate::new("3850102123681")
+///     .lang("hr")
+///     .name("hr", "Čokolada")
+///     .set("categories_tags", json!(["en:chocolates"]))
+///     .fields("updated");
+/// assert_eq!(update.to_json()["product"]["product_name_hr"], "Čokolada");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProductUpdate {
+    // The barcode of the product.
+    code: String,
+    // The product fields to update.
+    product: Map<String, Value>,
+    // The fields of the product returned in the response.
+    fields: Option<String>,
+    // The language of the tags given without language prefix.
+    tags_lc: Option<String>,
+}
+
+impl ProductUpdate {
+    /// Creates an empty update of the product with the given barcode.
+    pub fn new(barcode: &str) -> Self {
+        Self {
+            code: barcode.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the barcode of the product.
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Sets a product field, replacing its previous value.
+    pub fn set<T: Into<Value>>(mut self, field: &str, value: T) -> Self {
+        self.product.insert(field.to_string(), value.into());
+        self
+    }
+
+    /// Sets the main language of the product.
+    pub fn lang(self, lang: &str) -> Self {
+        self.set("lang", lang)
+    }
+
+    /// Sets the name of the product in the given language.
+    pub fn name(self, lang: &str, name: &str) -> Self {
+        self.set(&format!("product_name_{}", lang), name)
+    }
+
+    /// Sets the packaging components, replacing the existing ones.
+    pub fn packagings(self, packagings: Vec<Value>) -> Self {
+        self.set("packagings", packagings)
+    }
+
+    /// Sets the packaging components, replacing the existing ones. The shapes,
+    /// materials and recycling given as names are read in the language of
+    /// [ProductUpdate::tags_lc].
+    ///
+    /// ```
+    /// use openfoodfacts::{PackagingComponent, ProductUpdate};
+    /// use serde_json::json;
+    ///
+    /// let update = ProductUpdate::new("3850102123681")
+    ///     .packaging_components(&[PackagingComponent::new().shape("en:box").material("carton")])
+    ///     .tags_lc("fr");
+    /// assert_eq!(
+    ///     update.to_json()["product"]["packagings"],
+    ///     json!([{"shape": {"id": "en:box"}, "material": {"lc_name": "carton"}}])
+    /// );
+    /// ```
+    pub fn packaging_components(self, components: &[PackagingComponent]) -> Self {
+        let packagings = components
+            .iter()
+            .filter_map(|c| serde_json::to_value(c).ok())
+            .collect::<Vec<_>>();
+        self.packagings(packagings)
+    }
+
+    /// Sets the language of the tags given without language prefix, i.e. the
+    /// categories and the packaging shapes and materials.
+    pub fn tags_lc(mut self, lang: &str) -> Self {
+        self.tags_lc = Some(lang.to_string());
+        self
+    }
+
+    /// Sets the product fields returned in the response: a comma-separated list
+    /// of fields, `updated` for the updated fields or `none`.
+    pub fn fields(mut self, fields: &str) -> Self {
+        self.fields = Some(fields.to_string());
+        self
+    }
+
+    /// Returns the product fields to update.
+    pub fn product(&self) -> &Map<String, Value> {
+        &self.product
+    }
+
+    /// Returns the JSON body of the request.
+    pub fn to_json(&self) -> Value {
+        let mut body = Map::new();
+        body.insert(String::from("product"), Value::Object(self.product.clone()));
+        if let Some(ref tags_lc) = self.tags_lc {
+            body.insert(String::from("tags_lc"), Value::from(tags_lc.as_str()));
+        }
+        if let Some(ref fields) = self.fields {
+            body.insert(String::from("fields"), Value::from(fields.as_str()));
+        }
+        Value::Object(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn update_body() {
+        let update = ProductUpdate::new("3850102123681")
+            .name("fr", "Chocolat")
+            .packagings(vec![json!({"shape": "en:box", "material": "en:cardboard"})])
+            .set("quantity", "100 g")
+            .set("quantity", "200 g")
+            .tags_lc("fr");
+        assert_eq!(
+            update.to_json(),
+            json!({
+                "product": {
+                    "product_name_fr": "Chocolat",
+                    "packagings": [{"shape": "en:box", "material": "en:cardboard"}],
+                    "quantity": "200 g"
+                },
+                "tags_lc": "fr"
+            })
+        );
+    }
+
+    #[test]
+    fn failure() {
+        let response: ProductResponseV3 = serde_json::from_value(json!({
+            "code": "abc",
+            "status": "failure",
+            "result": {"id": "product_not_found"},
+            "errors": [{
+                "message": {"id": "invalid_code"},
+                "field": {"id": "code", "value": "abc"},
+                "impact": {"id": "failure"}
+            }]
+        }))
+        .unwrap();
+        assert!(!response.is_success());
+        assert_eq!(response.status, ResponseStatus::Failure);
+        assert_eq!(response.errors[0].impact(), Impact::Failure);
+        assert_eq!(response.errors[0].field.as_ref().unwrap().id, "code");
+        assert!(response.product.is_none());
+
+        let response: ProductResponseV3 =
+            serde_json::from_value(json!({"status": "maintenance"})).unwrap();
+        assert_eq!(response.status, ResponseStatus::Unknown);
+        assert_eq!(Impact::from("other"), Impact::Other(String::from("other")));
+    }
+}
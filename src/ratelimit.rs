@@ -0,0 +1,303 @@
+use crate::error::Error;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// The endpoint classes sharing a request quota.
+///
+/// * Product - Product reads (`/api/{version}/product/{barcode}`).
+/// * Search - Search queries (`/cgi/search.pl`, `/api/{version}/search`).
+/// * Facet - Facets, categories, products by facet, nutrients and taxonomies.
+/// * Write - Write operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndpointClass {
+    Product,
+    Search,
+    Facet,
+    Write,
+}
+
+impl EndpointClass {
+    /// Returns the class of the read request sent to the given URL.
+    pub(crate) fn of_read(url: &Url) -> Self {
+        let path = url.path();
+        if path.contains("/api/") && path.contains("/product/") {
+            Self::Product
+        } else if path.ends_with("/search") || path.ends_with("/search.pl") {
+            Self::Search
+        } else {
+            Self::Facet
+        }
+    }
+}
+
+/// A request quota: a number of requests per period.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quota {
+    requests: u32,
+    period: Duration,
+}
+
+impl Quota {
+    /// Creates a quota of `requests` requests per `period`. At least one request
+    /// is allowed per period.
+    pub fn new(requests: u32, period: Duration) -> Self {
+        Self {
+            requests: requests.max(1),
+            period,
+        }
+    }
+
+    /// Creates a quota of `requests` requests per minute.
+    pub fn per_minute(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(60))
+    }
+
+    // The number of tokens added to the bucket per second.
+    fn rate(&self) -> f64 {
+        f64::from(self.requests) / self.period.as_secs_f64()
+    }
+}
+
+/// Client-side rate limits.
+///
+/// Each endpoint class has a token bucket holding up to `requests` tokens and
+/// refilled at the rate of its quota. Each request, including retries, takes a
+/// token. When the bucket is empty, the client either waits for a token (the
+/// default) or fails fast with [Error::QuotaExceeded].
+///
+/// The default quotas are the ones published by OFF:
+///
+/// * Product: 100 requests per minute.
+/// * Search: 10 requests per minute.
+/// * Facet: 2 requests per minute.
+/// * Write: unlimited.
+///
+/// ```
+/// use openfoodfacts::{self as off, EndpointClass, Quota, RateLimits};
+///
+/// # fn main() -> Result<(), off::Error> {
+/// let limits = RateLimits::new()
+///     .quota(EndpointClass::Search, Quota::per_minute(5))
+///     .fail_fast(true);
+/// let client = off::v2().rate_limits(limits).build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimits {
+    quotas: HashMap<EndpointClass, Quota>,
+    fail_fast: bool,
+}
+
+impl Default for RateLimits {
+    /// Returns the limits with the quotas published by OFF, waiting for a token
+    /// when a bucket is empty.
+    fn default() -> Self {
+        let mut quotas = HashMap::new();
+        quotas.insert(EndpointClass::Product, Quota::per_minute(100));
+        quotas.insert(EndpointClass::Search, Quota::per_minute(10));
+        quotas.insert(EndpointClass::Facet, Quota::per_minute(2));
+        Self {
+            quotas,
+            fail_fast: false,
+        }
+    }
+}
+
+impl RateLimits {
+    /// Creates the default limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the quota of the given endpoint class.
+    pub fn quota(mut self, class: EndpointClass, quota: Quota) -> Self {
+        self.quotas.insert(class, quota);
+        self
+    }
+
+    /// Removes the quota of the given endpoint class.
+    pub fn unlimited(mut self, class: EndpointClass) -> Self {
+        self.quotas.remove(&class);
+        self
+    }
+
+    /// If true, requests fail with [Error::QuotaExceeded] instead of waiting
+    /// when the bucket of their class is empty.
+    pub fn fail_fast(mut self, fail_fast: bool) -> Self {
+        self.fail_fast = fail_fast;
+        self
+    }
+}
+
+// A token bucket. The number of tokens may be negative: a negative count is
+// the number of reserved tokens not yet refilled.
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// The rate limiter of a client, holding a token bucket per endpoint class.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    limits: RateLimits,
+    buckets: Mutex<HashMap<EndpointClass, Bucket>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limits: RateLimits) -> Self {
+        Self {
+            limits,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Reserves a token for a request of the given class and returns the delay
+    /// to wait before sending the request. In fail-fast mode, returns
+    /// [Error::QuotaExceeded] instead of a non-zero delay, without reserving a
+    /// token.
+    pub(crate) fn reserve(&self, class: EndpointClass) -> Result<Duration, Error> {
+        let quota = match self.limits.quotas.get(&class) {
+            Some(quota) => quota,
+            None => return Ok(Duration::ZERO),
+        };
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = buckets.entry(class).or_insert(Bucket {
+            tokens: f64::from(quota.requests),
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * quota.rate()).min(f64::from(quota.requests));
+        bucket.updated = now;
+
+        let delay = if bucket.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - bucket.tokens) / quota.rate())
+        };
+        if self.limits.fail_fast && delay > Duration::ZERO {
+            return Err(Error::QuotaExceeded {
+                class,
+                retry_after: delay,
+            });
+        }
+        bucket.tokens -= 1.0;
+        Ok(delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoint_class() {
+        let class = |url: &str| EndpointClass::of_read(&Url::parse(url).unwrap());
+        assert_eq!(
+            class("https://world.openfoodfacts.org/api/v0/product/3850102123681"),
+            EndpointClass::Product
+        );
+        assert_eq!(
+            class("https://world.openfoodfacts.org/cgi/search.pl?action=process"),
+            EndpointClass::Search
+        );
+        assert_eq!(
+            class("https://world.openfoodfacts.org/api/v2/search?code=1,2"),
+            EndpointClass::Search
+        );
+        assert_eq!(
+            class("https://world.openfoodfacts.org/category/cheeses.json"),
+            EndpointClass::Facet
+        );
+        assert_eq!(
+            class("https://world.openfoodfacts.org/data/taxonomies/nova_groups.json"),
+            EndpointClass::Facet
+        );
+    }
+
+    #[test]
+    fn default_quotas() {
+        let limits = RateLimits::new();
+        assert_eq!(
+            limits.quotas.get(&EndpointClass::Product),
+            Some(&Quota::per_minute(100))
+        );
+        assert_eq!(
+            limits.quotas.get(&EndpointClass::Search),
+            Some(&Quota::per_minute(10))
+        );
+        assert_eq!(
+            limits.quotas.get(&EndpointClass::Facet),
+            Some(&Quota::per_minute(2))
+        );
+        assert_eq!(limits.quotas.get(&EndpointClass::Write), None);
+        assert!(!limits.fail_fast);
+    }
+
+    #[test]
+    fn wait_for_token() {
+        let limiter = RateLimiter::new(RateLimits::new());
+        assert_eq!(
+            limiter.reserve(EndpointClass::Facet).unwrap(),
+            Duration::ZERO
+        );
+        assert_eq!(
+            limiter.reserve(EndpointClass::Facet).unwrap(),
+            Duration::ZERO
+        );
+        // Empty bucket: wait ~30s for the next token, then ~60s.
+        let delay = limiter.reserve(EndpointClass::Facet).unwrap();
+        assert!(delay > Duration::from_secs(29) && delay <= Duration::from_secs(30));
+        let delay = limiter.reserve(EndpointClass::Facet).unwrap();
+        assert!(delay > Duration::from_secs(59) && delay <= Duration::from_secs(60));
+        // Other classes are not affected.
+        assert_eq!(
+            limiter.reserve(EndpointClass::Search).unwrap(),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn fail_fast() {
+        let limits = RateLimits::new()
+            .quota(EndpointClass::Search, Quota::per_minute(1))
+            .fail_fast(true);
+        let limiter = RateLimiter::new(limits);
+        assert!(limiter.reserve(EndpointClass::Search).is_ok());
+        match limiter.reserve(EndpointClass::Search) {
+            Err(Error::QuotaExceeded { class, retry_after }) => {
+                assert_eq!(class, EndpointClass::Search);
+                assert!(retry_after > Duration::from_secs(59));
+            }
+            r => panic!("unexpected result {:?}", r),
+        }
+    }
+
+    #[test]
+    fn refill() {
+        let limits = RateLimits::new().quota(
+            EndpointClass::Product,
+            Quota::new(1, Duration::from_millis(20)),
+        );
+        let limiter = RateLimiter::new(limits.fail_fast(true));
+        assert!(limiter.reserve(EndpointClass::Product).is_ok());
+        assert!(limiter.reserve(EndpointClass::Product).is_err());
+        std::thread::sleep(Duration::from_millis(25));
+        assert!(limiter.reserve(EndpointClass::Product).is_ok());
+    }
+
+    #[test]
+    fn unlimited() {
+        let limiter = RateLimiter::new(RateLimits::new().unlimited(EndpointClass::Facet));
+        for _ in 0..10 {
+            assert_eq!(
+                limiter.reserve(EndpointClass::Facet).unwrap(),
+                Duration::ZERO
+            );
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use crate::error::Error;
+use std::time::Duration;
+
+/// Retry policy of idempotent (GET) requests.
+///
+/// Failed requests are retried with an exponential backoff: the n-th retry waits
+/// `base_delay * 2^(n-1)`, capped at `max_delay`, minus a random fraction
+/// (the jitter) of the delay. Requests are retried on:
+///
+/// * The HTTP statuses given with [RetryPolicy::statuses], by default 429 (Too
+///   Many Requests), 502 (Bad Gateway), 503 (Service Unavailable) and 504
+///   (Gateway Timeout).
+/// * Connection errors and timeouts.
+///
+/// If enabled, the delay given by the server in the `Retry-After` header of the
+/// retried responses, i.e. 429 or 503, replaces the backoff delay. The request is not retried if the server asks to
+/// wait longer than `max_delay`.
+///
+/// ```
+/// use openfoodfacts::{self as off, RetryPolicy};
+/// use std::time::Duration;
+///
+/// # fn main() -> Result<(), off::Error> {
+/// let policy = RetryPolicy::new()
+///     .max_attempts(5)
+///     .base_delay(Duration::from_millis(200))
+///     .statuses(&[502, 503]);
+/// let client = off::v2().retry(policy).build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: f64,
+    statuses: Vec<u16>,
+    retry_after: bool,
+}
+
+impl Default for RetryPolicy {
+    /// Returns a policy with 3 attempts, a base delay of 500ms, a maximum delay
+    /// of 30s, a jitter of 0.5, retrying on the 429, 502, 503 and 504 statuses
+    /// and honoring the `Retry-After` header.
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: 0.5,
+            statuses: vec![429, 502, 503, 504],
+            retry_after: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a new policy with defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of attempts, including the first one. A value
+    /// of 1 disables retries.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    /// Sets the maximum delay between two attempts.
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Sets the jitter, the maximum fraction of the delay randomly removed from
+    /// it. Clamped to the range [0, 1]. 0 disables the jitter.
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets the HTTP statuses triggering a retry.
+    pub fn statuses(mut self, statuses: &[u16]) -> Self {
+        self.statuses = statuses.to_vec();
+        self
+    }
+
+    /// Honors or ignores the `Retry-After` header.
+    pub fn retry_after(mut self, enabled: bool) -> Self {
+        self.retry_after = enabled;
+        self
+    }
+
+    /// Returns the delay to wait before retrying the failed attempt number
+    /// `attempt` (starting at 1), or None if the request must not be retried.
+    pub(crate) fn delay(&self, attempt: u32, error: &Error) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        let server_delay = match error {
+            Error::Status {
+                code, retry_after, ..
+            } if self.statuses.contains(code) => *retry_after,
+            Error::RateLimited { retry_after } if self.statuses.contains(&429) => *retry_after,
+            Error::Transport(e) if e.is_connect() || e.is_timeout() => None,
+            _ => return None,
+        };
+        match server_delay.filter(|_| self.retry_after) {
+            Some(delay) if delay > self.max_delay => None,
+            Some(delay) => Some(delay),
+            None => Some(self.backoff(attempt)),
+        }
+    }
+
+    // Returns the backoff delay after the attempt number `attempt`.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt - 1);
+        let delay = self
+            .base_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |d| d.min(self.max_delay));
+        delay.mul_f64(1.0 - self.jitter * fastrand::f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(code: u16) -> Error {
+        Error::Status {
+            code,
+            retry_after: None,
+            body: String::new(),
+        }
+    }
+
+    #[test]
+    fn defaults() {
+        let policy = RetryPolicy::new();
+        assert_eq!(policy.max_attempts, 3);
+        assert_eq!(policy.statuses, vec![429, 502, 503, 504]);
+        assert!(policy.retry_after);
+    }
+
+    #[test]
+    fn backoff() {
+        let policy = RetryPolicy::new()
+            .max_attempts(100)
+            .base_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_millis(500))
+            .jitter(0.0);
+        assert_eq!(
+            policy.delay(1, &status(503)),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            policy.delay(2, &status(503)),
+            Some(Duration::from_millis(200))
+        );
+        assert_eq!(
+            policy.delay(3, &status(503)),
+            Some(Duration::from_millis(400))
+        );
+        assert_eq!(
+            policy.delay(4, &status(503)),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(
+            policy.delay(40, &status(503)),
+            Some(Duration::from_millis(500))
+        );
+    }
+
+    #[test]
+    fn jitter() {
+        let policy = RetryPolicy::new()
+            .base_delay(Duration::from_millis(100))
+            .jitter(0.5);
+        for _ in 0..100 {
+            let delay = policy.delay(1, &status(502)).unwrap();
+            assert!(delay > Duration::from_millis(50) && delay <= Duration::from_millis(100));
+        }
+    }
+
+    #[test]
+    fn max_attempts() {
+        let policy = RetryPolicy::new().max_attempts(2);
+        assert!(policy.delay(1, &status(503)).is_some());
+        assert!(policy.delay(2, &status(503)).is_none());
+    }
+
+    #[test]
+    fn statuses() {
+        let policy = RetryPolicy::new().statuses(&[500]);
+        assert!(policy.delay(1, &status(500)).is_some());
+        assert!(policy.delay(1, &status(503)).is_none());
+        let rate_limited = Error::RateLimited { retry_after: None };
+        assert!(policy.delay(1, &rate_limited).is_none());
+        assert!(policy
+            .delay(1, &Error::Validation(String::from("barcode")))
+            .is_none());
+    }
+
+    #[test]
+    fn retry_after() {
+        let policy = RetryPolicy::new().max_delay(Duration::from_secs(10));
+        let error = |secs| Error::RateLimited {
+            retry_after: Some(Duration::from_secs(secs)),
+        };
+        assert_eq!(policy.delay(1, &error(2)), Some(Duration::from_secs(2)));
+        // The server asks to wait too long.
+        assert_eq!(policy.delay(1, &error(60)), None);
+
+        let policy = policy.retry_after(false).jitter(0.0);
+        assert_eq!(
+            policy.delay(1, &error(60)),
+            Some(Duration::from_millis(500))
+        );
+    }
+}
@@ -1,7 +1,10 @@
-use crate::client::{RequestMethods, Result, SearchUrl};
+use crate::client::{with_params, SearchUrl};
+use crate::error::Error;
 use crate::output::Output;
 use crate::types::Params;
 use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use url::{ParseError, Url};
 
 /// Sorting criteria.
 ///
@@ -44,6 +47,287 @@ impl Display for SortBy {
     }
 }
 
+/// The tag types of the criteria of the Search API V0. See
+/// [SearchQueryV0::tag].
+///
+/// ```
+/// use openfoodfacts::search::TagType;
+///
+/// assert_eq!(TagType::ManufacturingPlaces.name(), "manufacturing_places");
+/// assert_eq!("labels".parse::<TagType>().unwrap(), TagType::Labels);
+/// assert!("label".parse::<TagType>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TagType {
+    Brands,
+    Categories,
+    Packaging,
+    Labels,
+    Origins,
+    ManufacturingPlaces,
+    /// The packager codes.
+    EmbCodes,
+    PurchasePlaces,
+    Stores,
+    Countries,
+    Ingredients,
+    Additives,
+    Allergens,
+    Traces,
+    /// The Nutri-Score grades.
+    NutritionGrades,
+    States,
+}
+
+impl TagType {
+    /// All the tag types.
+    pub const ALL: [TagType; 16] = [
+        TagType::Brands,
+        TagType::Categories,
+        TagType::Packaging,
+        TagType::Labels,
+        TagType::Origins,
+        TagType::ManufacturingPlaces,
+        TagType::EmbCodes,
+        TagType::PurchasePlaces,
+        TagType::Stores,
+        TagType::Countries,
+        TagType::Ingredients,
+        TagType::Additives,
+        TagType::Allergens,
+        TagType::Traces,
+        TagType::NutritionGrades,
+        TagType::States,
+    ];
+
+    /// Returns the name sent in the `tagtype_N` parameter.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Brands => "brands",
+            Self::Categories => "categories",
+            Self::Packaging => "packaging",
+            Self::Labels => "labels",
+            Self::Origins => "origins",
+            Self::ManufacturingPlaces => "manufacturing_places",
+            Self::EmbCodes => "emb_codes",
+            Self::PurchasePlaces => "purchase_places",
+            Self::Stores => "stores",
+            Self::Countries => "countries",
+            Self::Ingredients => "ingredients",
+            Self::Additives => "additives",
+            Self::Allergens => "allergens",
+            Self::Traces => "traces",
+            Self::NutritionGrades => "nutrition_grades",
+            Self::States => "states",
+        }
+    }
+}
+
+/// The operators of the criteria of the Search API V0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TagOp {
+    Contains,
+    DoesNotContain,
+}
+
+impl TagOp {
+    /// All the operators.
+    pub const ALL: [TagOp; 2] = [TagOp::Contains, TagOp::DoesNotContain];
+
+    /// Returns the name sent in the `tag_contains_N` parameter.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Contains => "contains",
+            Self::DoesNotContain => "does_not_contain",
+        }
+    }
+}
+
+/// The ingredients of the ingredient filters of the Search API V0. See
+/// [SearchQueryV0::ingredient_filter].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Ingredient {
+    Additives,
+    FromPalmOil,
+    MayBeFromPalmOil,
+    FromOrMayBeFromPalmOil,
+}
+
+impl Ingredient {
+    /// All the ingredients.
+    pub const ALL: [Ingredient; 4] = [
+        Ingredient::Additives,
+        Ingredient::FromPalmOil,
+        Ingredient::MayBeFromPalmOil,
+        Ingredient::FromOrMayBeFromPalmOil,
+    ];
+
+    /// Returns the name of the query parameter.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Additives => "additives",
+            Self::FromPalmOil => "ingredients_from_palm_oil",
+            Self::MayBeFromPalmOil => "ingredients_that_may_be_from_palm_oil",
+            Self::FromOrMayBeFromPalmOil => "ingredients_from_or_that_may_be_from_palm_oil",
+        }
+    }
+}
+
+/// The values of the ingredient filters of the Search API V0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IngredientFilter {
+    /// The products with the ingredient.
+    With,
+    /// The products without the ingredient.
+    Without,
+    /// No filter.
+    Indifferent,
+}
+
+impl IngredientFilter {
+    /// All the filter values.
+    pub const ALL: [IngredientFilter; 3] = [
+        IngredientFilter::With,
+        IngredientFilter::Without,
+        IngredientFilter::Indifferent,
+    ];
+
+    /// Returns the name of the value.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::With => "with",
+            Self::Without => "without",
+            Self::Indifferent => "indifferent",
+        }
+    }
+}
+
+/// The nutrient comparisons of the Search API V0. See
+/// [SearchQueryV0::nutrient_compare].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compare {
+    /// Lower than.
+    Lt,
+    /// Lower than or equal.
+    Lte,
+    /// Greater than.
+    Gt,
+    /// Greater than or equal.
+    Gte,
+    /// Equal.
+    Eq,
+}
+
+impl Compare {
+    /// All the comparisons.
+    pub const ALL: [Compare; 5] = [
+        Compare::Lt,
+        Compare::Lte,
+        Compare::Gt,
+        Compare::Gte,
+        Compare::Eq,
+    ];
+
+    /// Returns the name sent in the `nutriment_compare_N` parameter.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Lt => "lt",
+            Self::Lte => "lte",
+            Self::Gt => "gt",
+            Self::Gte => "gte",
+            Self::Eq => "eq",
+        }
+    }
+}
+
+impl Display for TagType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for TagType {
+    type Err = Error;
+
+    /// Parses a name, i.e. `labels`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        from_name(&Self::ALL, Self::name, name, "tag type")
+    }
+}
+
+impl Display for TagOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for TagOp {
+    type Err = Error;
+
+    /// Parses a name, i.e. `does_not_contain`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        from_name(&Self::ALL, Self::name, name, "tag operator")
+    }
+}
+
+impl Display for Ingredient {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Ingredient {
+    type Err = Error;
+
+    /// Parses a name, i.e. `additives`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        from_name(&Self::ALL, Self::name, name, "ingredient")
+    }
+}
+
+impl Display for IngredientFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for IngredientFilter {
+    type Err = Error;
+
+    /// Parses a name, i.e. `without`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        from_name(&Self::ALL, Self::name, name, "ingredient filter")
+    }
+}
+
+impl Display for Compare {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Compare {
+    type Err = Error;
+
+    /// Parses a name, i.e. `lte`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        from_name(&Self::ALL, Self::name, name, "nutrient comparison")
+    }
+}
+
+// Returns the value of `all` with the given name, or a validation error.
+fn from_name<T: Copy>(
+    all: &[T],
+    name_of: fn(&T) -> &'static str,
+    name: &str,
+    what: &str,
+) -> Result<T, Error> {
+    all.iter()
+        .copied()
+        .find(|v| name_of(v) == name)
+        .ok_or_else(|| Error::Validation(format!("unknown {} '{}'", what, name)))
+}
+
 /// Builds a search query.
 ///
 /// Concrete types must implement the [crate::search::QueryParams] trait.
@@ -82,7 +366,7 @@ impl From<u32> for Value {
 
 /// Converts a SearchQuery<S> object into a [crate::types::Params] object.
 pub trait QueryParams {
-    fn params(&self) -> Params;
+    fn params(&self) -> Params<'_>;
 }
 
 impl<S> SearchQuery<S> {
@@ -92,19 +376,20 @@ impl<S> SearchQuery<S> {
         self
     }
 
-    /// Sends the search query. Relies on the client to obtain the versioned
-    /// search API endpoint and to send the request.
-    pub(crate) fn search(
+    /// Returns the full URL of the search query. Relies on the client to obtain
+    /// the versioned search API endpoint.
+    pub(crate) fn search_url(
         params: impl QueryParams,
-        client: &(impl SearchUrl + RequestMethods),
+        client: &impl SearchUrl,
         output: Option<Output>,
-    ) -> Result {
-        let url = client.search_url(output.as_ref().and_then(|o| o.locale.as_ref()))?;
+    ) -> std::result::Result<Url, ParseError> {
+        let locale = output.as_ref().and_then(|o| o.locale.as_ref());
+        let url = client.search_url(locale)?;
         let mut params = params.params();
-        if let Some(output_params) = output.map(|o| o.params(&["fields"])) {
+        if let Some(output_params) = output.as_ref().map(|o| o.params(&["fields"])) {
             params.extend(output_params);
         }
-        client.get(url, Some(&params))
+        Ok(client.localize(with_params(url, Some(&params)), locale))
     }
 }
 
@@ -114,19 +399,25 @@ impl<S> SearchQuery<S> {
 
 /// A search query builder for the Search API V0.
 ///
+/// The criteria, ingredient filters and nutrient comparisons are given either
+/// with the typed tag types, operators and values ([TagType], [TagOp],
+/// [Ingredient], [IngredientFilter], [Compare]) or as strings, i.e. for the
+/// values not covered by the enums. The server ignores the unknown strings.
+///
 /// # Examples
 ///
-/// ```
+/// ```no_run
 /// use openfoodfacts as off;
+/// use off::search::{Compare, Ingredient, IngredientFilter, TagOp, TagType};
 ///
 /// # fn main() -> Result<(), off::Error> {
 /// let client = off::v0().build().unwrap();
 /// let query = client
 ///     .query()
-///     .criteria("categories", "contains", "cereals")
-///     .criteria("label", "contains", "kosher")
-///     .ingredient("additives", "without")
-///     .nutrient("energy", "lt", 500);
+///     .tag(TagType::Categories, TagOp::Contains, "cereals")
+///     .criteria("labels", "contains", "kosher")
+///     .ingredient_filter(Ingredient::Additives, IngredientFilter::Without)
+///     .nutrient_compare("energy", Compare::Lt, 500);
 /// let response = client.search(query, None)?;
 /// assert!(response.status().is_success());
 /// # Ok(())
@@ -151,8 +442,8 @@ impl SearchQueryV0 {
     ///
     /// # Arguments
     ///
-    /// * criteria - A valid criteria name. See the [`API docs`].
-    /// * op - One of "contains" or "does_not_contain".
+    /// * criteria - A valid criteria name, i.e. a [TagType] name. See the [`API docs`].
+    /// * op - One of "contains" or "does_not_contain", see [TagOp].
     /// * value - The searched criteria value.
     ///
     /// [`API docs`]: https://openfoodfacts.github.io/api-documentation/#5Filtering
@@ -184,7 +475,7 @@ impl SearchQueryV0 {
     ///     - "ingredients_from_palm_oil",
     ///     - "ingredients_that_may_be_from_palm_oil",
     ///     - "ingredients_from_or_that_may_be_from_palm_oil".
-    // * value: One of "with", "without", "indifferent".
+    /// * value: One of "with", "without", "indifferent", see [IngredientFilter].
     ///
     /// If `ingredient` is "additives", the values "with", "without" and "indiferent"
     /// are converted to "with_additives", "without_additives" and "indifferent_additives"
@@ -213,7 +504,7 @@ impl SearchQueryV0 {
     ///
     /// * nutrient - The nutrient name. See the [`API docs`].
     /// * op - The comparation operation to perform. One of "lt", "lte", "gt", "gte",
-    ///        "eq".
+    ///   "eq", see [Compare].
     /// * value - The value to compare.
     ///
     /// [`API docs`]: https://openfoodfacts.github.io/api-documentation/#5Filtering
@@ -234,13 +525,31 @@ impl SearchQueryV0 {
         self
     }
 
+    /// Defines a criteria query parameter with a typed tag type and operator.
+    /// See [SearchQueryV0::criteria].
+    pub fn tag(self, tag_type: TagType, op: TagOp, value: &str) -> Self {
+        self.criteria(tag_type.name(), op.name(), value)
+    }
+
+    /// Defines an ingredient query parameter with a typed ingredient and
+    /// filter. See [SearchQueryV0::ingredient].
+    pub fn ingredient_filter(self, ingredient: Ingredient, filter: IngredientFilter) -> Self {
+        self.ingredient(ingredient.name(), filter.name())
+    }
+
+    /// Defines a nutrient search parameter with a typed comparison. See
+    /// [SearchQueryV0::nutrient].
+    pub fn nutrient_compare(self, nutrient: &str, compare: Compare, value: u32) -> Self {
+        self.nutrient(nutrient, compare.name(), value)
+    }
+
     pub(crate) fn new() -> Self {
         Self::default()
     }
 }
 
 impl QueryParams for SearchQueryV0 {
-    fn params(&self) -> Params {
+    fn params(&self) -> Params<'_> {
         let mut params: Params = Vec::new();
         for (name, value) in &self.params {
             let v = match value {
@@ -286,7 +595,7 @@ impl SearchQueryV2 {
     ///
     /// * criteria - A valid criteria name. See the [`API docs`].
     /// * value - The criteria value. Use comma for AND, colon for OR and tilde for NOT.
-    ///     See the [`Search V2 API docs`].
+    ///   See the [`Search V2 API docs`].
     /// * lc: Optional language code.
     ///
     /// [`openfoodfacts API docs`]: https://openfoodfacts.github.io/api-documentation/#5Filtering
@@ -315,7 +624,7 @@ impl SearchQueryV2 {
     /// * nutrient - The nutrient name. See the [`API docs`].
     /// * unit - One of the "100g" or "serving".
     /// * op - A comparison operator. One of  '=', '<', '>', `<=', '=>`.
-    ///     See the [`Search V2 API docs`].
+    ///   See the [`Search V2 API docs`].
     /// * value - The value to compare.
     ///
     /// TODO: Verify the <= and => operators.
@@ -349,7 +658,7 @@ impl SearchQueryV2 {
 }
 
 impl QueryParams for SearchQueryV2 {
-    fn params(&self) -> Params {
+    fn params(&self) -> Params<'_> {
         let mut params: Params = Vec::new();
         for (name, value) in &self.params {
             let v = match value {
@@ -428,6 +737,49 @@ mod tests_search_v0 {
             ]
         );
     }
+
+    #[test]
+    fn typed_params() {
+        let typed = SearchQueryV0::new()
+            .tag(TagType::Brands, TagOp::Contains, "Nestlé")
+            .tag(TagType::NutritionGrades, TagOp::DoesNotContain, "e")
+            .ingredient_filter(Ingredient::Additives, IngredientFilter::Without)
+            .ingredient_filter(Ingredient::MayBeFromPalmOil, IngredientFilter::Indifferent)
+            .nutrient_compare("fiber", Compare::Gte, 5);
+        let strings = SearchQueryV0::new()
+            .criteria("brands", "contains", "Nestlé")
+            .criteria("nutrition_grades", "does_not_contain", "e")
+            .ingredient("additives", "without")
+            .ingredient("ingredients_that_may_be_from_palm_oil", "indifferent")
+            .nutrient("fiber", "gte", 5);
+        assert_eq!(typed.params(), strings.params());
+    }
+
+    #[test]
+    fn names() {
+        for tag_type in TagType::ALL.iter() {
+            assert_eq!(tag_type.to_string().parse::<TagType>().unwrap(), *tag_type);
+        }
+        for compare in Compare::ALL.iter() {
+            assert_eq!(compare.name().parse::<Compare>().unwrap(), *compare);
+        }
+        assert_eq!(
+            "ingredients_from_palm_oil".parse::<Ingredient>().unwrap(),
+            Ingredient::FromPalmOil
+        );
+        assert_eq!(
+            "indifferent".parse::<IngredientFilter>().unwrap(),
+            IngredientFilter::Indifferent
+        );
+        assert_eq!("contains".parse::<TagOp>().unwrap(), TagOp::Contains);
+        match "contain".parse::<TagOp>() {
+            Err(Error::Validation(message)) => {
+                assert_eq!(message, "unknown tag operator 'contain'")
+            }
+            r => panic!("unexpected result {:?}", r),
+        }
+        assert!("<".parse::<Compare>().is_err());
+    }
 }
 
 #[cfg(test)]
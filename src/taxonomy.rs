@@ -0,0 +1,421 @@
+// The taxonomy graph. Taxonomies are directed acyclic graphs of tags, i.e.
+// categories, each tag having names and synonyms by language, parents and
+// properties. The graph is built from the JSON files served by the
+// OffClient::taxonomy endpoint.
+use crate::error::Error;
+use serde::de::{Deserialize, Deserializer, Error as _};
+use serde::ser::{Serialize, Serializer};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// A tag of a taxonomy.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaxonomyEntry {
+    /// The tag id, i.e. `en:beverages`.
+    pub id: String,
+    /// The names by language.
+    pub names: BTreeMap<String, String>,
+    /// The synonyms by language, the name first.
+    pub synonyms: BTreeMap<String, Vec<String>>,
+    /// The ids of the parent tags.
+    pub parents: Vec<String>,
+    /// The properties by name and language, i.e. `wikidata` -> `en` ->
+    /// `Q40050`.
+    pub properties: BTreeMap<String, BTreeMap<String, String>>,
+    /// The comment lines of the entry in the source format, i.e.
+    /// `# Not a soda.`. Not part of the JSON format.
+    pub comments: Vec<String>,
+}
+
+impl TaxonomyEntry {
+    /// Creates a tag without names nor parents.
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            ..Self::default()
+        }
+    }
+}
+
+/// A taxonomy graph.
+///
+/// The traversals are cycle-safe: each tag is visited once, even if the
+/// taxonomy has (invalid) cycles. Parents that are not tags of the taxonomy
+/// are kept and reported by the traversals.
+///
+/// ```
+/// use openfoodfacts::Taxonomy;
+///
+/// let taxonomy = Taxonomy::from_json(r#"{
+///     "en:beverages": {"name": {"en": "Beverages", "fr": "Boissons"}},
+///     "en:sodas": {"name": {"en": "Sodas"}, "parents": ["en:beverages"]},
+///     "en:colas": {"name": {"en": "Colas"}, "parents": ["en:sodas"]}
+/// }"#).unwrap();
+/// assert!(taxonomy.is_a("en:colas", "en:beverages"));
+/// assert_eq!(taxonomy.ancestors("en:colas"), vec!["en:sodas", "en:beverages"]);
+/// assert_eq!(taxonomy.name("en:beverages", "fr"), Some("Boissons"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Taxonomy {
+    entries: BTreeMap<String, TaxonomyEntry>,
+    // The children of each tag, derived from the parents and sorted by id.
+    children: BTreeMap<String, Vec<String>>,
+    // The stopwords and the synonym groups by language, in declaration order.
+    // Only the taxonomy sources have them.
+    stopwords: Vec<(String, Vec<String>)>,
+    synonyms: Vec<(String, Vec<String>)>,
+}
+
+impl Taxonomy {
+    /// Creates an empty taxonomy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a taxonomy from the JSON served by the taxonomy endpoint.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Adds a tag, replacing the tag with the same id if any.
+    pub fn insert(&mut self, entry: TaxonomyEntry) {
+        if let Some(old) = self.entries.remove(&entry.id) {
+            for parent in &old.parents {
+                if let Some(children) = self.children.get_mut(parent) {
+                    children.retain(|child| *child != old.id);
+                }
+            }
+        }
+        for parent in &entry.parents {
+            let children = self.children.entry(parent.clone()).or_default();
+            if let Err(i) = children.binary_search(&entry.id) {
+                children.insert(i, entry.id.clone());
+            }
+        }
+        self.entries.insert(entry.id.clone(), entry);
+    }
+
+    /// Returns the tag with the given id.
+    pub fn get(&self, id: &str) -> Option<&TaxonomyEntry> {
+        self.entries.get(id)
+    }
+
+    /// Returns true if the taxonomy has the given tag.
+    pub fn contains(&self, id: &str) -> bool {
+        self.entries.contains_key(id)
+    }
+
+    /// Returns the tags, sorted by id.
+    pub fn iter(&self) -> impl Iterator<Item = &TaxonomyEntry> {
+        self.entries.values()
+    }
+
+    /// Returns the number of tags.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the taxonomy has no tags.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the parents of the given tag.
+    pub fn parents(&self, id: &str) -> &[String] {
+        self.entries.get(id).map_or(&[], |e| &e.parents)
+    }
+
+    /// Returns the children of the given tag, sorted by id.
+    pub fn children(&self, id: &str) -> &[String] {
+        self.children.get(id).map_or(&[], |c| c)
+    }
+
+    /// Returns true if the given tag is in the taxonomy and has no parents.
+    pub fn is_root(&self, id: &str) -> bool {
+        matches!(self.entries.get(id), Some(e) if e.parents.is_empty())
+    }
+
+    /// Returns the tags without parents.
+    pub fn roots(&self) -> impl Iterator<Item = &TaxonomyEntry> {
+        self.entries.values().filter(|e| e.parents.is_empty())
+    }
+
+    /// Returns all the ancestors of the given tag, nearest first.
+    pub fn ancestors(&self, id: &str) -> Vec<&str> {
+        self.traverse(id, |id| self.parents(id))
+    }
+
+    /// Returns all the descendants of the given tag, nearest first.
+    pub fn descendants(&self, id: &str) -> Vec<&str> {
+        self.traverse(id, |id| self.children(id))
+    }
+
+    /// Returns true if `tag` is `ancestor` or one of its descendants.
+    pub fn is_a(&self, tag: &str, ancestor: &str) -> bool {
+        tag == ancestor || self.ancestors(tag).contains(&ancestor)
+    }
+
+    /// Returns the name of the tag in the given language.
+    pub fn name(&self, id: &str, lang: &str) -> Option<&str> {
+        self.entries.get(id)?.names.get(lang).map(String::as_str)
+    }
+
+    /// Returns the synonyms of the tag in the given language.
+    pub fn synonyms(&self, id: &str, lang: &str) -> &[String] {
+        self.entries
+            .get(id)
+            .and_then(|e| e.synonyms.get(lang))
+            .map_or(&[], |s| s)
+    }
+
+    /// Returns the stopword lists, as (language, words) pairs. Stopwords are
+    /// ignored when matching names.
+    pub fn stopwords(&self) -> &[(String, Vec<String>)] {
+        &self.stopwords
+    }
+
+    /// Adds a list of stopwords for the given language.
+    pub fn add_stopwords(&mut self, lang: &str, words: Vec<String>) {
+        self.stopwords.push((lang.to_string(), words));
+    }
+
+    /// Returns the synonym groups, as (language, words) pairs. The words of a
+    /// group are interchangeable when matching names.
+    pub fn synonym_groups(&self) -> &[(String, Vec<String>)] {
+        &self.synonyms
+    }
+
+    /// Adds a group of synonyms for the given language.
+    pub fn add_synonym_group(&mut self, lang: &str, words: Vec<String>) {
+        self.synonyms.push((lang.to_string(), words));
+    }
+
+    // Breadth-first traversal from the given tag, excluded, following `next`.
+    fn traverse<'a, F>(&'a self, id: &str, next: F) -> Vec<&'a str>
+    where
+        F: Fn(&str) -> &'a [String],
+    {
+        let mut visited = BTreeSet::new();
+        visited.insert(id);
+        let mut found = Vec::new();
+        let mut queue: VecDeque<&'a str> = next(id).iter().map(String::as_str).collect();
+        while let Some(tag) = queue.pop_front() {
+            if visited.insert(tag) {
+                found.push(tag);
+                queue.extend(next(tag).iter().map(String::as_str));
+            }
+        }
+        found
+    }
+}
+
+// The JSON keys that are not properties.
+const NAME: &str = "name";
+const PARENTS: &str = "parents";
+const CHILDREN: &str = "children";
+const SYNONYMS: &str = "synonyms";
+
+impl<'de> Deserialize<'de> for Taxonomy {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = BTreeMap::<String, BTreeMap<String, Value>>::deserialize(deserializer)?;
+        let mut taxonomy = Taxonomy::new();
+        for (id, fields) in json {
+            let mut entry = TaxonomyEntry::new(&id);
+            // The names, parents and synonyms are needed for the graph: an
+            // invalid value is an error, not a missing one.
+            let invalid = |key: &str, e: serde_json::Error| {
+                D::Error::custom(format!("invalid {} of {}: {}", key, id, e))
+            };
+            for (key, value) in fields {
+                match key.as_str() {
+                    NAME => {
+                        entry.names = serde_json::from_value(value).map_err(|e| invalid(NAME, e))?
+                    }
+                    PARENTS => {
+                        entry.parents =
+                            serde_json::from_value(value).map_err(|e| invalid(PARENTS, e))?
+                    }
+                    SYNONYMS => {
+                        entry.synonyms =
+                            serde_json::from_value(value).map_err(|e| invalid(SYNONYMS, e))?
+                    }
+                    // Derived from the parents.
+                    CHILDREN => {}
+                    _ => {
+                        // Only the properties with a value by language.
+                        if let Ok(values) = serde_json::from_value(value) {
+                            entry.properties.insert(key, values);
+                        }
+                    }
+                }
+            }
+            taxonomy.insert(entry);
+        }
+        Ok(taxonomy)
+    }
+}
+
+impl Serialize for Taxonomy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let json: BTreeMap<&str, BTreeMap<&str, Value>> = self
+            .entries
+            .values()
+            .map(|entry| {
+                let mut fields = BTreeMap::new();
+                fields.insert(NAME, serde_json::json!(entry.names));
+                if !entry.parents.is_empty() {
+                    fields.insert(PARENTS, serde_json::json!(entry.parents));
+                }
+                let children = self.children(&entry.id);
+                if !children.is_empty() {
+                    fields.insert(CHILDREN, serde_json::json!(children));
+                }
+                if !entry.synonyms.is_empty() {
+                    fields.insert(SYNONYMS, serde_json::json!(entry.synonyms));
+                }
+                for (name, values) in &entry.properties {
+                    fields.insert(name.as_str(), serde_json::json!(values));
+                }
+                (entry.id.as_str(), fields)
+            })
+            .collect();
+        json.serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample() -> Value {
+        json!({
+            "en:beverages": {
+                "name": {"en": "Beverages", "fr": "Boissons"},
+                "synonyms": {"en": ["Beverages", "Drinks"]},
+                "children": ["en:sodas", "en:waters"],
+                "wikidata": {"en": "Q40050"}
+            },
+            "en:sodas": {
+                "name": {"en": "Sodas"},
+                "parents": ["en:beverages"],
+                "children": ["en:colas"]
+            },
+            "en:sugared-beverages": {
+                "name": {"en": "Sugared beverages"},
+                "parents": ["en:beverages"],
+                "children": ["en:colas"]
+            },
+            "en:colas": {
+                "name": {"en": "Colas"},
+                "parents": ["en:sodas", "en:sugared-beverages"]
+            },
+            "en:waters": {
+                "name": {"en": "Waters"},
+                "parents": ["en:beverages"],
+                "vegan": {"en": "yes"}
+            }
+        })
+    }
+
+    fn taxonomy() -> Taxonomy {
+        serde_json::from_value(sample()).unwrap()
+    }
+
+    #[test]
+    fn graph() {
+        let taxonomy = taxonomy();
+        assert_eq!(taxonomy.len(), 5);
+        assert_eq!(
+            taxonomy.children("en:beverages"),
+            ["en:sodas", "en:sugared-beverages", "en:waters"]
+        );
+        assert_eq!(
+            taxonomy.parents("en:colas"),
+            ["en:sodas", "en:sugared-beverages"]
+        );
+        assert!(taxonomy.is_root("en:beverages"));
+        assert!(!taxonomy.is_root("en:colas"));
+        assert!(!taxonomy.is_root("en:unknown"));
+        assert_eq!(
+            taxonomy.roots().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+            vec!["en:beverages"]
+        );
+    }
+
+    #[test]
+    fn traversals() {
+        let taxonomy = taxonomy();
+        assert_eq!(
+            taxonomy.ancestors("en:colas"),
+            vec!["en:sodas", "en:sugared-beverages", "en:beverages"]
+        );
+        assert_eq!(
+            taxonomy.descendants("en:beverages"),
+            vec!["en:sodas", "en:sugared-beverages", "en:waters", "en:colas"]
+        );
+        assert!(taxonomy.is_a("en:colas", "en:beverages"));
+        assert!(taxonomy.is_a("en:colas", "en:colas"));
+        assert!(!taxonomy.is_a("en:beverages", "en:colas"));
+        assert!(taxonomy.ancestors("en:unknown").is_empty());
+    }
+
+    #[test]
+    fn cycles() {
+        let mut taxonomy = Taxonomy::new();
+        for (id, parent) in [("en:a", "en:c"), ("en:b", "en:a"), ("en:c", "en:b")] {
+            let mut entry = TaxonomyEntry::new(id);
+            entry.parents.push(parent.to_string());
+            taxonomy.insert(entry);
+        }
+        assert_eq!(taxonomy.ancestors("en:a"), vec!["en:c", "en:b"]);
+        assert_eq!(taxonomy.descendants("en:a"), vec!["en:b", "en:c"]);
+        assert!(taxonomy.is_a("en:a", "en:b"));
+        assert_eq!(taxonomy.roots().count(), 0);
+    }
+
+    #[test]
+    fn names_and_properties() {
+        let taxonomy = taxonomy();
+        assert_eq!(taxonomy.name("en:beverages", "fr"), Some("Boissons"));
+        assert_eq!(taxonomy.name("en:beverages", "de"), None);
+        assert_eq!(
+            taxonomy.synonyms("en:beverages", "en"),
+            ["Beverages", "Drinks"]
+        );
+        assert!(taxonomy.synonyms("en:sodas", "en").is_empty());
+        let waters = taxonomy.get("en:waters").unwrap();
+        assert_eq!(waters.properties["vegan"]["en"], "yes");
+    }
+
+    #[test]
+    fn insert_replaces() {
+        let mut taxonomy = taxonomy();
+        let mut colas = TaxonomyEntry::new("en:colas");
+        colas.parents.push(String::from("en:sodas"));
+        taxonomy.insert(colas);
+        assert_eq!(taxonomy.children("en:sugared-beverages"), [] as [String; 0]);
+        assert_eq!(taxonomy.children("en:sodas"), ["en:colas"]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let taxonomy = taxonomy();
+        let json = serde_json::to_value(&taxonomy).unwrap();
+        assert_eq!(json["en:sodas"]["children"], json!(["en:colas"]));
+        assert_eq!(serde_json::from_value::<Taxonomy>(json).unwrap(), taxonomy);
+    }
+
+    #[test]
+    fn malformed() {
+        let mut json = sample();
+        json["en:colas"]["parents"] = json!("en:sodas");
+        let error = serde_json::from_value::<Taxonomy>(json).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("invalid parents of en:colas: "));
+        let mut json = sample();
+        json["en:waters"]["name"] = json!(["Waters"]);
+        assert!(serde_json::from_value::<Taxonomy>(json).is_err());
+    }
+}
@@ -0,0 +1,409 @@
+// The Product Opener taxonomy source format, i.e. taxonomies/categories.txt:
+//
+//     # A comment.
+//     stopwords:fr: aux,au,de,le,du,la,a,et
+//     synonyms:en: fizzy, sparkling
+//
+//     < en:Beverages
+//     en: Sodas, soft drinks
+//     fr: Sodas
+//     wikidata:en: Q147538
+//
+// Entries are blocks of lines separated by blank lines. The first name of the
+// first language line gives the id of the entry. The parents are referenced by
+// any of their names or synonyms. The comments inside an entry, or right
+// before it, belong to the entry.
+use crate::canonical::{is_lang, normalize_tag};
+use crate::error::Error;
+use crate::taxonomy::{Taxonomy, TaxonomyEntry};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+const STOPWORDS: &str = "stopwords";
+const SYNONYMS: &str = "synonyms";
+
+// An entry being parsed, with the parent references and the line numbers used
+// in the errors.
+struct Pending {
+    line: usize,
+    entry: TaxonomyEntry,
+    parents: Vec<(usize, String)>,
+}
+
+impl Taxonomy {
+    /// Parses a taxonomy source file.
+    ///
+    /// Returns [Error::Syntax] with the line number of the first invalid line,
+    /// duplicate entry or unknown parent.
+    ///
+    /// The comment lines inside an entry or right before it, without blank
+    /// line, are kept in [TaxonomyEntry::comments]. The other comments, i.e.
+    /// the file header or the comments of the stopwords, are dropped.
+    ///
+    /// ```
+    /// use openfoodfacts::Taxonomy;
+    ///
+    /// let taxonomy = Taxonomy::from_txt("\
+    /// en: Beverages, drinks
+    /// fr: Boissons
+    ///
+    /// < en:drinks
+    /// en: Sodas
+    /// wikidata:en: Q147538
+    /// ").unwrap();
+    /// assert_eq!(taxonomy.parents("en:sodas"), ["en:beverages"]);
+    /// assert_eq!(taxonomy.synonyms("en:beverages", "en"), ["Beverages", "drinks"]);
+    /// ```
+    pub fn from_txt(text: &str) -> Result<Self, Error> {
+        let mut taxonomy = Taxonomy::new();
+        let mut pending: Vec<Pending> = Vec::new();
+        let mut current: Option<Pending> = None;
+        // The comments before the current block, until it is an entry.
+        let mut comments = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let number = i + 1;
+            let line = line.trim();
+            if line.is_empty() {
+                pending.extend(current.take());
+                comments.clear();
+                continue;
+            }
+            if line.starts_with('#') {
+                match current.as_mut() {
+                    Some(p) => p.entry.comments.push(line.to_string()),
+                    None => comments.push(line.to_string()),
+                }
+                continue;
+            }
+            if let Some(parent) = line.strip_prefix('<') {
+                let parent = parent.trim();
+                match parent.split_once(':') {
+                    Some((lang, name)) if is_lang(lang) && !name.trim().is_empty() => {}
+                    _ => return Err(syntax(number, "expected '< lc:parent'")),
+                }
+                Pending::start(&mut current, number, &mut comments)
+                    .parents
+                    .push((number, parent.to_string()));
+                continue;
+            }
+            let (head, rest) = line
+                .split_once(':')
+                .ok_or_else(|| syntax(number, "expected 'lc: names' or 'property:lc: value'"))?;
+            let head = head.trim();
+            if head == STOPWORDS || head == SYNONYMS {
+                let (lang, words) = split_property(number, head, rest)?;
+                let words = split_names(words);
+                comments.clear();
+                if head == STOPWORDS {
+                    taxonomy.add_stopwords(lang, words);
+                } else {
+                    taxonomy.add_synonym_group(lang, words);
+                }
+                continue;
+            }
+            let entry = &mut Pending::start(&mut current, number, &mut comments).entry;
+            if is_lang(head) {
+                let names = split_names(rest);
+                if names.is_empty() {
+                    return Err(syntax(number, "no names"));
+                }
+                if entry.synonyms.contains_key(head) {
+                    return Err(syntax(number, &format!("duplicate language '{}'", head)));
+                }
+                if entry.id.is_empty() {
+                    entry.id = format!("{}:{}", head, normalize_tag(head, &names[0]));
+                }
+                entry.names.insert(head.to_string(), names[0].clone());
+                entry.synonyms.insert(head.to_string(), names);
+            } else {
+                let (lang, value) = split_property(number, head, rest)?;
+                entry
+                    .properties
+                    .entry(head.to_string())
+                    .or_default()
+                    .insert(lang.to_string(), value.trim().to_string());
+            }
+        }
+        pending.extend(current);
+
+        // Index the entries by language and name, to resolve the parents.
+        let mut ids = BTreeSet::new();
+        let mut index = BTreeMap::new();
+        for p in &pending {
+            if p.entry.id.is_empty() {
+                return Err(syntax(p.line, "entry without names"));
+            }
+            if !ids.insert(p.entry.id.clone()) {
+                return Err(syntax(p.line, &format!("duplicate entry '{}'", p.entry.id)));
+            }
+            for (lang, names) in &p.entry.synonyms {
+                for name in names {
+                    index
+                        .entry(format!("{}:{}", lang, normalize_tag(lang, name)))
+                        .or_insert_with(|| p.entry.id.clone());
+                }
+            }
+        }
+        for mut p in pending {
+            for (number, parent) in p.parents {
+                let (lang, name) = parent.split_once(':').unwrap_or_default();
+                let lang = lang.trim();
+                let key = format!("{}:{}", lang, normalize_tag(lang, name));
+                let id = index
+                    .get(&key)
+                    .ok_or_else(|| syntax(number, &format!("unknown parent '{}'", parent)))?;
+                p.entry.parents.push(id.clone());
+            }
+            taxonomy.insert(p.entry);
+        }
+        Ok(taxonomy)
+    }
+
+    /// Writes the taxonomy in the source format, in canonical order: the
+    /// stopwords and synonym groups first, then the entries sorted by id. In
+    /// each entry, the comments come first, then the parents, then the names
+    /// with the language of the id first, then the properties sorted by name
+    /// and language.
+    ///
+    /// Only the comments of the entries are written: a file read with
+    /// [Taxonomy::from_txt] loses its header and the comments that are not
+    /// attached to an entry.
+    pub fn to_txt(&self) -> String {
+        let mut text = String::new();
+        for (lang, words) in self.stopwords() {
+            let _ = writeln!(text, "{}:{}: {}", STOPWORDS, lang, join_names(words));
+        }
+        for (lang, words) in self.synonym_groups() {
+            let _ = writeln!(text, "{}:{}: {}", SYNONYMS, lang, join_names(words));
+        }
+        for entry in self.iter() {
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            for comment in &entry.comments {
+                let _ = writeln!(text, "{}", comment);
+            }
+            for parent in &entry.parents {
+                let _ = writeln!(text, "< {}", self.reference(parent));
+            }
+            let id_lang = entry.id.split(':').next().unwrap_or_default();
+            let mut langs: Vec<&String> = entry.names.keys().chain(entry.synonyms.keys()).collect();
+            langs.sort_by_key(|lang| (lang.as_str() != id_lang, lang.as_str()));
+            langs.dedup();
+            for lang in langs {
+                let mut names: Vec<String> = entry.names.get(lang).cloned().into_iter().collect();
+                for synonym in entry.synonyms.get(lang).into_iter().flatten() {
+                    if !names.contains(synonym) {
+                        names.push(synonym.clone());
+                    }
+                }
+                let _ = writeln!(text, "{}: {}", lang, join_names(&names));
+            }
+            for (property, values) in &entry.properties {
+                for (lang, value) in values {
+                    let _ = writeln!(text, "{}:{}: {}", property, lang, value);
+                }
+            }
+        }
+        text
+    }
+
+    // Returns the `lc:name` reference of a parent: its name in the language
+    // of its id, or its id if unknown.
+    fn reference(&self, id: &str) -> String {
+        let lang = id.split(':').next().unwrap_or_default();
+        match self.name(id, lang) {
+            Some(name) => format!("{}:{}", lang, name),
+            None => id.to_string(),
+        }
+    }
+}
+
+impl Pending {
+    fn new(line: usize) -> Self {
+        Self {
+            line,
+            entry: TaxonomyEntry::default(),
+            parents: Vec::new(),
+        }
+    }
+
+    // Returns the current entry, starting it at the given line with the
+    // preceding comments if there is none.
+    fn start<'a>(
+        current: &'a mut Option<Pending>,
+        line: usize,
+        comments: &mut Vec<String>,
+    ) -> &'a mut Pending {
+        current.get_or_insert_with(|| {
+            let mut pending = Pending::new(line);
+            pending.entry.comments = std::mem::take(comments);
+            pending
+        })
+    }
+}
+
+// Splits the `lc: value` part of a property line.
+fn split_property<'a>(line: usize, name: &str, rest: &'a str) -> Result<(&'a str, &'a str), Error> {
+    match rest.split_once(':') {
+        Some((lang, value)) if is_lang(lang.trim()) => Ok((lang.trim(), value)),
+        _ => Err(syntax(line, &format!("expected '{}:lc: value'", name))),
+    }
+}
+
+// Splits a comma-separated list of names. Commas may be escaped as `\,`.
+fn split_names(names: &str) -> Vec<String> {
+    let mut split = Vec::new();
+    let mut name = String::new();
+    let mut chars = names.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&',') => {
+                name.push(',');
+                chars.next();
+            }
+            ',' => split.push(std::mem::take(&mut name)),
+            c => name.push(c),
+        }
+    }
+    split.push(name);
+    split
+        .into_iter()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+fn join_names(names: &[String]) -> String {
+    names
+        .iter()
+        .map(|name| name.replace(',', "\\,"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn syntax(line: usize, message: &str) -> Error {
+    Error::Syntax {
+        line,
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "\
+# Categories.
+stopwords:fr: aux, au, de
+synonyms:en: fizzy, sparkling
+
+en: Beverages, drinks
+fr: Boissons
+wikidata:en: Q40050
+
+# Carbonated drinks.
+< en:drinks
+en: Sodas, Soft drinks
+# Same in French.
+fr: Sodas
+xx: Soda
+
+< en:Sodas
+< fr:Boissons
+en: Colas
+fr: Colas, boissons au cola
+vegan:en: yes
+wikidata:en: Q134041
+
+en: Sauces\\, dips and spreads
+";
+
+    #[test]
+    fn parse() {
+        let taxonomy = Taxonomy::from_txt(SOURCE).unwrap();
+        assert_eq!(taxonomy.len(), 4);
+        assert_eq!(taxonomy.parents("en:colas"), ["en:sodas", "en:beverages"]);
+        assert!(taxonomy.is_a("en:colas", "en:beverages"));
+        assert_eq!(taxonomy.name("en:sodas", "xx"), Some("Soda"));
+        assert_eq!(
+            taxonomy.synonyms("en:colas", "fr"),
+            ["Colas", "boissons au cola"]
+        );
+        let colas = taxonomy.get("en:colas").unwrap();
+        assert_eq!(colas.properties["vegan"]["en"], "yes");
+        assert_eq!(
+            taxonomy.name("en:sauces-dips-and-spreads", "en"),
+            Some("Sauces, dips and spreads")
+        );
+        assert_eq!(taxonomy.stopwords()[0].1, vec!["aux", "au", "de"]);
+        assert_eq!(taxonomy.synonym_groups()[0].0, "en");
+        assert_eq!(
+            taxonomy.get("en:sodas").unwrap().comments,
+            ["# Carbonated drinks.", "# Same in French."]
+        );
+        assert!(taxonomy.get("en:beverages").unwrap().comments.is_empty());
+    }
+
+    #[test]
+    fn write() {
+        let taxonomy = Taxonomy::from_txt(SOURCE).unwrap();
+        assert_eq!(
+            taxonomy.to_txt(),
+            "\
+stopwords:fr: aux, au, de
+synonyms:en: fizzy, sparkling
+
+en: Beverages, drinks
+fr: Boissons
+wikidata:en: Q40050
+
+< en:Sodas
+< en:Beverages
+en: Colas
+fr: Colas, boissons au cola
+vegan:en: yes
+wikidata:en: Q134041
+
+en: Sauces\\, dips and spreads
+
+# Carbonated drinks.
+# Same in French.
+< en:Beverages
+en: Sodas, Soft drinks
+fr: Sodas
+xx: Soda
+"
+        );
+        let text = taxonomy.to_txt();
+        assert_eq!(Taxonomy::from_txt(&text).unwrap(), taxonomy);
+    }
+
+    fn error(text: &str) -> (usize, String) {
+        match Taxonomy::from_txt(text) {
+            Err(Error::Syntax { line, message }) => (line, message),
+            r => panic!("unexpected result {:?}", r),
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error("en: A\n\nnot a line").0, 3);
+        assert_eq!(error("en: A\n< A").0, 2);
+        assert_eq!(error("en: A\nwikidata: Q1").0, 2);
+        assert_eq!(error("en: A\nen: B").0, 2);
+        assert_eq!(error("en:  , ").0, 1);
+        assert_eq!(
+            error("en: A\n\n\nen: a"),
+            (4, String::from("duplicate entry 'en:a'"))
+        );
+        assert_eq!(
+            error("en: A\n\n< en:B\nen: C"),
+            (3, String::from("unknown parent 'en:B'"))
+        );
+        assert_eq!(
+            error("# Comment\nwikidata:en: Q1"),
+            (2, String::from("entry without names"))
+        );
+    }
+}
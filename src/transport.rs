@@ -0,0 +1,309 @@
+// The HTTP layer of the clients. The clients build HttpRequest objects and hand
+// them to a Transport, reqwest by default. The response statuses are checked by
+// the clients, not by the transports.
+use crate::error::Error;
+pub use http::{HeaderMap, Method, StatusCode};
+use serde::de::DeserializeOwned;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::io::Read;
+use url::Url;
+
+/// An HTTP request, as sent by a client to its transport.
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    /// The request method.
+    pub method: Method,
+    /// The full request URL, query included.
+    pub url: Url,
+    /// The request headers, including the client's default headers.
+    pub headers: HeaderMap,
+    /// The request body. Empty for GET requests.
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// Creates a request without headers and body.
+    pub fn new(method: Method, url: Url) -> Self {
+        Self {
+            method,
+            url,
+            headers: HeaderMap::new(),
+            body: Vec::new(),
+        }
+    }
+}
+
+/// The kinds of transport errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TransportErrorKind {
+    /// The connection to the server could not be established.
+    Connect,
+    /// The request or the response timed out.
+    Timeout,
+    /// Any other error.
+    Other,
+}
+
+/// A transport error: the request could not be sent or the response could not
+/// be received. Holds the error of the underlying HTTP library.
+#[derive(Debug)]
+pub struct TransportError {
+    kind: TransportErrorKind,
+    source: Box<dyn std::error::Error + Send + Sync>,
+}
+
+impl TransportError {
+    /// Creates an error of the given kind.
+    pub fn new<E>(kind: TransportErrorKind, source: E) -> Self
+    where
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        Self {
+            kind,
+            source: source.into(),
+        }
+    }
+
+    /// Returns the kind of the error.
+    pub fn kind(&self) -> TransportErrorKind {
+        self.kind
+    }
+
+    /// Returns true if the connection could not be established.
+    pub fn is_connect(&self) -> bool {
+        self.kind == TransportErrorKind::Connect
+    }
+
+    /// Returns true if the request or the response timed out.
+    pub fn is_timeout(&self) -> bool {
+        self.kind == TransportErrorKind::Timeout
+    }
+}
+
+impl Display for TransportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl std::error::Error for TransportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&*self.source)
+    }
+}
+
+impl From<reqwest::Error> for TransportError {
+    fn from(e: reqwest::Error) -> Self {
+        let kind = if e.is_timeout() {
+            TransportErrorKind::Timeout
+        } else if e.is_connect() {
+            TransportErrorKind::Connect
+        } else {
+            TransportErrorKind::Other
+        };
+        Self::new(kind, e)
+    }
+}
+
+impl From<std::io::Error> for TransportError {
+    fn from(e: std::io::Error) -> Self {
+        // The reqwest errors are wrapped in the I/O errors of the response bodies.
+        if matches!(e.get_ref(), Some(inner) if inner.is::<reqwest::Error>()) {
+            let inner = e.into_inner().unwrap();
+            return Self::from(*inner.downcast::<reqwest::Error>().unwrap());
+        }
+        let kind = match e.kind() {
+            std::io::ErrorKind::TimedOut => TransportErrorKind::Timeout,
+            std::io::ErrorKind::ConnectionRefused => TransportErrorKind::Connect,
+            _ => TransportErrorKind::Other,
+        };
+        Self::new(kind, e)
+    }
+}
+
+/// An HTTP response returned by a blocking transport. The body is read on
+/// demand.
+pub struct HttpResponse {
+    url: Url,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Box<dyn Read + Send>,
+}
+
+impl HttpResponse {
+    /// Creates a response with the given final URL (after redirects), status,
+    /// headers and body reader.
+    pub fn new<R>(url: Url, status: StatusCode, headers: HeaderMap, body: R) -> Self
+    where
+        R: Read + Send + 'static,
+    {
+        Self {
+            url,
+            status,
+            headers,
+            body: Box::new(body),
+        }
+    }
+
+    /// Returns the final URL of the response.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Returns the HTTP status.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns the response headers.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Returns the body reader.
+    pub fn into_reader(self) -> Box<dyn Read + Send> {
+        self.body
+    }
+
+    /// Reads the whole body.
+    pub fn bytes(mut self) -> Result<Vec<u8>, Error> {
+        let mut body = Vec::new();
+        self.body
+            .read_to_end(&mut body)
+            .map_err(TransportError::from)?;
+        Ok(body)
+    }
+
+    /// Reads the whole body as text. Invalid UTF-8 sequences are replaced.
+    pub fn text(self) -> Result<String, Error> {
+        Ok(String::from_utf8_lossy(&self.bytes()?).into_owned())
+    }
+
+    /// Reads and deserializes the JSON body.
+    pub fn json<T: DeserializeOwned>(self) -> Result<T, Error> {
+        Ok(serde_json::from_slice(&self.bytes()?)?)
+    }
+}
+
+impl Debug for HttpResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpResponse")
+            .field("url", &self.url)
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The HTTP transport of the blocking client.
+///
+/// A transport sends the requests built by the client and returns the responses
+/// whatever their status. The client checks the statuses, retries failed
+/// requests and applies the rate limits. The default transport is
+/// [ReqwestTransport]. Other HTTP libraries or in-memory fakes can be plugged in
+/// with `OffBuilder::transport()`:
+///
+/// ```
+/// use openfoodfacts::{self as off, HttpRequest, HttpResponse, StatusCode, Transport, TransportError};
+///
+/// #[derive(Debug)]
+/// struct Fake;
+///
+/// impl Transport for Fake {
+///     fn send(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+///         let body: &[u8] = br#"{"status": 1}"#;
+///         Ok(HttpResponse::new(request.url, StatusCode::OK, Default::default(), body))
+///     }
+/// }
+///
+/// # fn main() -> Result<(), off::Error> {
+/// let client = off::v2().transport(Fake).build()?;
+/// let response = client.product("3850102123681", None)?;
+/// assert!(response.status().is_success());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The session opened by `login()` is kept only by transports storing cookies.
+pub trait Transport: Debug + Send + Sync {
+    /// Sends the request and returns the response.
+    fn send(&self, request: HttpRequest) -> Result<HttpResponse, TransportError>;
+}
+
+impl<T> Transport for Box<T>
+where
+    T: Transport + ?Sized,
+{
+    fn send(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        (**self).send(request)
+    }
+}
+
+/// The default transport of the blocking client, a reqwest blocking client.
+#[derive(Debug, Clone)]
+pub struct ReqwestTransport {
+    client: reqwest::blocking::Client,
+}
+
+impl ReqwestTransport {
+    /// Creates a transport sending the requests with the given client.
+    pub fn new(client: reqwest::blocking::Client) -> Self {
+        Self { client }
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn send(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        let mut rb = self
+            .client
+            .request(request.method, request.url)
+            .headers(request.headers);
+        if !request.body.is_empty() {
+            rb = rb.body(request.body);
+        }
+        let response = rb.send()?;
+        Ok(HttpResponse::new(
+            response.url().clone(),
+            response.status(),
+            response.headers().clone(),
+            response,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn response(body: &'static [u8]) -> HttpResponse {
+        let url = Url::parse("https://world.openfoodfacts.org/").unwrap();
+        HttpResponse::new(url, StatusCode::OK, HeaderMap::new(), body)
+    }
+
+    #[test]
+    fn body() {
+        assert_eq!(response(b"abc").text().unwrap(), "abc");
+        let json: Value = response(br#"{"status": 1}"#).json().unwrap();
+        assert_eq!(json["status"], 1);
+        assert!(matches!(
+            response(b"not json").json::<Value>(),
+            Err(Error::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn io_error_kind() {
+        let error = std::io::Error::new(std::io::ErrorKind::TimedOut, "timeout");
+        assert!(TransportError::from(error).is_timeout());
+        let error = std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "refused");
+        assert!(TransportError::from(error).is_connect());
+        // The other kinds.
+        let error = std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid");
+        assert_eq!(
+            TransportError::from(error).kind(),
+            TransportErrorKind::Other
+        );
+    }
+}
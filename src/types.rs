@@ -7,13 +7,67 @@ use std::vec::Vec;
 pub type Params<'a> = Vec<(&'a str, String)>;
 
 /// Marker for objects implementing the openfoodfacts API V0.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub struct V0;
 
 /// Marker for objects implementing the openfoodfacts API V2.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub struct V2;
 
+/// Marker for objects implementing the openfoodfacts API V3.
+#[derive(Copy, Clone, Debug)]
+pub struct V3;
+
+/// Marker for builders producing a blocking [crate::OffClient].
+#[derive(Copy, Clone, Debug)]
+pub struct Blocking;
+
+/// Marker for builders producing an async [crate::AsyncOffClient].
+#[cfg(feature = "async")]
+#[derive(Copy, Clone, Debug)]
+pub struct Async;
+
+/// Client kind markers implement the Mode trait, giving the types of the
+/// underlying transport and reqwest client. This trait is sealed.
+pub trait Mode: private::Sealed {
+    /// The transport trait object type.
+    type Transport: ?Sized + std::fmt::Debug + Send + Sync;
+    /// The reqwest client type.
+    type HttpClient: std::fmt::Debug;
+
+    /// Returns the default (reqwest) transport sending requests with the given
+    /// client.
+    fn reqwest_transport(client: Self::HttpClient) -> Box<Self::Transport>;
+}
+
+impl Mode for Blocking {
+    type Transport = dyn crate::transport::Transport;
+    type HttpClient = reqwest::blocking::Client;
+
+    fn reqwest_transport(client: Self::HttpClient) -> Box<Self::Transport> {
+        Box::new(crate::transport::ReqwestTransport::new(client))
+    }
+}
+
+#[cfg(feature = "async")]
+impl Mode for Async {
+    type Transport = dyn crate::async_transport::AsyncTransport;
+    type HttpClient = reqwest::Client;
+
+    fn reqwest_transport(client: Self::HttpClient) -> Box<Self::Transport> {
+        Box::new(crate::async_transport::AsyncReqwestTransport::new(client))
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for super::Blocking {}
+
+    #[cfg(feature = "async")]
+    impl Sealed for super::Async {}
+}
+
 /// Version marker objects implement the Version trait. This serves
 /// two purposes:
 ///
@@ -37,3 +91,9 @@ impl Version for V2 {
         "v2"
     }
 }
+
+impl Version for V3 {
+    fn version(&self) -> &str {
+        "v3"
+    }
+}
@@ -0,0 +1,92 @@
+// Authentication tests against a local stand-in server.
+mod common;
+
+use common::{Reply, StandIn};
+use openfoodfacts as off;
+
+const PRODUCT: &str = r#"{"status": 1, "code": "3850102123681"}"#;
+
+// A server opening a session for the "user" account.
+fn server() -> StandIn {
+    StandIn::start(|req| {
+        if req.url.starts_with("/cgi/session.pl") {
+            if req.body_str().contains("user_id=user") {
+                Reply::json(200, "{}").header("Set-Cookie", "session=user_session; Path=/")
+            } else {
+                Reply::json(200, "{}")
+            }
+        } else {
+            Reply::json(200, PRODUCT)
+        }
+    })
+}
+
+#[test]
+fn basic_auth() {
+    let server = server();
+    let client = off::v2()
+        .url_template(&server.url())
+        .auth("off", "off")
+        .build()
+        .unwrap();
+    assert!(client.product("3850102123681", None).is_ok());
+    assert_eq!(
+        server.received()[0].header("Authorization"),
+        Some("Basic b2ZmOm9mZg==")
+    );
+}
+
+#[test]
+fn login() {
+    let server = server();
+    let client = off::v2()
+        .url_template(&server.url())
+        .auth("user", "p@ss&word")
+        .build()
+        .unwrap();
+    client.login().unwrap();
+    assert!(client.product("3850102123681", None).is_ok());
+
+    let received = server.received();
+    assert_eq!(received[0].method, "POST");
+    assert_eq!(
+        received[0].body_str(),
+        "user_id=user&password=p%40ss%26word&.submit=Sign-in"
+    );
+    // The session cookie is sent with the later requests.
+    assert_eq!(received[1].header("Cookie"), Some("session=user_session"));
+}
+
+#[test]
+fn login_failed() {
+    let server = server();
+    let client = off::v2()
+        .url_template(&server.url())
+        .auth("unknown", "pwd")
+        .build()
+        .unwrap();
+    assert!(matches!(client.login(), Err(off::Error::Authentication(_))));
+}
+
+#[test]
+fn login_without_credentials() {
+    let client = off::v2().build().unwrap();
+    assert!(matches!(client.login(), Err(off::Error::Validation(_))));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn async_login() {
+    let server = server();
+    let client = off::async_v2()
+        .url_template(&server.url())
+        .auth("user", "pwd")
+        .build()
+        .unwrap();
+    client.login().await.unwrap();
+    assert!(client.product("3850102123681", None).await.is_ok());
+    assert_eq!(
+        server.received()[1].header("Cookie"),
+        Some("session=user_session")
+    );
+}
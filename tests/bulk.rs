@@ -0,0 +1,174 @@
+// Bulk edit tests against a local stand-in server.
+mod common;
+
+use common::{Reply, StandIn};
+use openfoodfacts::{self as off, BulkEdit, RowOutcome};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+const ROWS: &str = "code,brands,quantity\n\
+                    3850102123681,Kraš,100 g\n\
+                    3850102123681,Kraš,200 g\n\
+                    abc,Foo,\n\
+                    12345678,New brand,\n";
+
+// A server knowing one product and saving the edits, or failing with a 503
+// status while `down` is set.
+fn server(down: Arc<AtomicBool>) -> StandIn {
+    StandIn::start(move |req| {
+        if req.url.starts_with("/api/v2/product/3850102123681") {
+            Reply::json(
+                200,
+                r#"{"status": 1, "product": {"code": "3850102123681",
+                    "brands": "Kraš", "quantity": "100 g"}}"#,
+            )
+        } else if req.url.starts_with("/api/v2/product/") {
+            Reply::json(
+                404,
+                r#"{"status": 0, "status_verbose": "product not found"}"#,
+            )
+        } else if down.load(Ordering::SeqCst) {
+            Reply::json(503, "{}")
+        } else {
+            Reply::json(200, r#"{"status": 1, "status_verbose": "fields saved"}"#)
+        }
+    })
+}
+
+// Returns the paths of the checkpoint and report files of a test, removed.
+fn files(test: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir();
+    let prefix = format!("off-bulk-{}-{}", test, std::process::id());
+    let checkpoint = dir.join(format!("{}.checkpoint", prefix));
+    let report = dir.join(format!("{}.csv", prefix));
+    let _ = std::fs::remove_file(&checkpoint);
+    let _ = std::fs::remove_file(&report);
+    (checkpoint, report)
+}
+
+#[test]
+fn bulk_edit() {
+    let server = server(Arc::new(AtomicBool::new(false)));
+    let client = off::v2()
+        .url_template(&server.url())
+        .auth("user", "pwd")
+        .build()
+        .unwrap();
+    let (checkpoint, report_file) = files("run");
+    let bulk = BulkEdit::from_csv(ROWS)
+        .unwrap()
+        .checkpoint(&checkpoint)
+        .report(&report_file)
+        .comment("Bulk fixes");
+    let report = client.bulk_edit(&bulk).unwrap();
+    assert_eq!(report.skipped, 0);
+    let outcomes: Vec<&str> = report.rows.iter().map(|r| r.outcome.name()).collect();
+    assert_eq!(outcomes, ["unchanged", "saved", "failed", "saved"]);
+    assert_eq!(report.rows[1].fields, ["quantity"]);
+    assert_eq!(report.rows[3].fields, ["brands"]);
+    assert_eq!(report.count("saved"), 2);
+
+    let edits: Vec<String> = server
+        .received()
+        .iter()
+        .filter(|r| r.method == "POST")
+        .map(|r| r.body_str())
+        .collect();
+    assert_eq!(
+        edits,
+        [
+            "code=3850102123681&quantity=200+g&comment=Bulk+fixes&user_id=user&password=pwd",
+            "code=12345678&brands=New+brand&comment=Bulk+fixes&user_id=user&password=pwd",
+        ]
+    );
+    assert_eq!(
+        std::fs::read_to_string(&report_file).unwrap(),
+        "line,code,outcome,fields,message\n\
+         2,3850102123681,unchanged,,\n\
+         3,3850102123681,saved,quantity,\n\
+         4,abc,failed,,invalid argument: invalid barcode 'abc'\n\
+         5,12345678,saved,brands,\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(&checkpoint).unwrap(),
+        "4 12345678\n"
+    );
+
+    // All rows are done: nothing is sent again.
+    let requests = server.received().len();
+    let report = client.bulk_edit(&bulk).unwrap();
+    assert_eq!(report.skipped, 4);
+    assert!(report.rows.is_empty());
+    assert_eq!(server.received().len(), requests);
+    let _ = std::fs::remove_file(&checkpoint);
+    let _ = std::fs::remove_file(&report_file);
+}
+
+#[test]
+fn bulk_edit_resume() {
+    let down = Arc::new(AtomicBool::new(true));
+    let server = server(down.clone());
+    let client = off::v2().url_template(&server.url()).build().unwrap();
+    let (checkpoint, report_file) = files("resume");
+    let bulk = BulkEdit::from_csv(ROWS)
+        .unwrap()
+        .checkpoint(&checkpoint)
+        .report(&report_file);
+    let error = client.bulk_edit(&bulk).unwrap_err();
+    assert_eq!(error.status(), Some(503));
+    assert_eq!(
+        std::fs::read_to_string(&checkpoint).unwrap(),
+        "1 3850102123681\n"
+    );
+
+    down.store(false, Ordering::SeqCst);
+    let report = client.bulk_edit(&bulk).unwrap();
+    assert_eq!(report.skipped, 1);
+    assert_eq!(report.rows[0].line, 3);
+    assert_eq!(report.rows[0].outcome, RowOutcome::Saved);
+    let lines = std::fs::read_to_string(&report_file).unwrap();
+    assert_eq!(lines.lines().count(), 5);
+    assert_eq!(lines.matches("line,code").count(), 1);
+
+    // A checkpoint of other rows is rejected.
+    let other = BulkEdit::from_csv("code\n1\n2\n3\n4\n")
+        .unwrap()
+        .checkpoint(&checkpoint);
+    assert!(matches!(
+        client.bulk_edit(&other),
+        Err(off::Error::Validation(_))
+    ));
+    let _ = std::fs::remove_file(&checkpoint);
+    let _ = std::fs::remove_file(&report_file);
+}
+
+#[test]
+fn bulk_edit_dry_run() {
+    let server = server(Arc::new(AtomicBool::new(false)));
+    let client = off::v2()
+        .url_template(&server.url())
+        .dry_run(true)
+        .build()
+        .unwrap();
+    let (checkpoint, input) = files("dry-run");
+    let input = input.with_extension("jsonl");
+    std::fs::write(
+        &input,
+        "{\"code\": \"3850102123681\", \"quantity\": \"250 g\", \"nutriment_fat\": 31.5}\n",
+    )
+    .unwrap();
+    let bulk = BulkEdit::load(&input).unwrap().checkpoint(&checkpoint);
+    let report = client.bulk_edit(&bulk).unwrap();
+    match &report.rows[0].outcome {
+        RowOutcome::DryRun(request) => {
+            assert!(request
+                .to_string()
+                .ends_with("+ code: 3850102123681\n+ nutriment_fat: 31.5\n+ quantity: 250 g\n"))
+        }
+        outcome => panic!("unexpected outcome {:?}", outcome),
+    }
+    assert!(server.received().iter().all(|r| r.method == "GET"));
+    assert!(!checkpoint.exists());
+    std::fs::remove_file(&input).unwrap();
+}
@@ -1,43 +1,67 @@
-// Integration tests using API v1.
+// Integration tests of the read API, v0 and v2, replaying the synthetic
+// fixtures of tests/fixtures: hand-written cassettes, not recordings of the
+// real server. See common::fixture.
+mod common;
+
 use openfoodfacts::{self as off, Locale, Output};
 use reqwest::StatusCode;
 
 #[test]
 fn taxonomy() {
-    let client = off::v0().build().unwrap();
+    let client = off::v0()
+        .transport(common::fixture("taxonomy"))
+        .build()
+        .unwrap();
     let response = client.taxonomy("nova_groups").unwrap();
     assert_eq!(
         response.url().as_str(),
         "https://world.openfoodfacts.org/data/taxonomies/nova_groups.json"
     );
-    assert_eq!(response.status().is_success(), true);
+    assert!(response.status().is_success());
+}
+
+#[test]
+fn taxonomy_response() {
+    let client = off::v0()
+        .transport(common::fixture("taxonomy_response"))
+        .build()
+        .unwrap();
+    let taxonomy = client.taxonomy_response("categories").unwrap();
+    assert!(taxonomy.is_a("en:colas", "en:beverages"));
+    assert_eq!(taxonomy.descendants("en:beverages").len(), 3);
+    assert_eq!(taxonomy.name("en:sodas", "fr"), Some("Sodas"));
 }
 
 #[test]
 fn taxonomy_not_found() {
-    let client = off::v0().build().unwrap();
-    let response = client.taxonomy("not_found").unwrap();
-    assert_eq!(
-        response.url().as_str(),
-        "https://world.openfoodfacts.org/data/taxonomies/not_found.json"
-    );
-    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let client = off::v0()
+        .transport(common::fixture("taxonomy_not_found"))
+        .build()
+        .unwrap();
+    let error = client.taxonomy("not_found").unwrap_err();
+    assert_eq!(error.status(), Some(StatusCode::NOT_FOUND.as_u16()));
 }
 
 #[test]
 fn facet() {
-    let client = off::v0().build().unwrap();
+    let client = off::v0()
+        .transport(common::fixture("facet"))
+        .build()
+        .unwrap();
     let response = client.facet("brands", None).unwrap();
     assert_eq!(
         response.url().as_str(),
         "https://world.openfoodfacts.org/brands.json"
     );
-    assert_eq!(response.status().is_success(), true);
+    assert!(response.status().is_success());
 }
 
 #[test]
 fn facet_params() {
-    let client = off::v0().build().unwrap();
+    let client = off::v0()
+        .transport(common::fixture("facet_params"))
+        .build()
+        .unwrap();
     let output = Output::new()
         .locale(Locale::new("fr", None))
         .page(22)
@@ -48,23 +72,42 @@ fn facet_params() {
         response.url().as_str(),
         "https://fr.openfoodfacts.org/brands.json?page=22&fields=url&nocache=true"
     );
-    assert_eq!(response.status().is_success(), true);
+    assert!(response.status().is_success());
+}
+
+#[test]
+fn facet_response() {
+    let client = off::v0()
+        .transport(common::fixture("facet_response"))
+        .build()
+        .unwrap();
+    let response = client.facet_response(off::Facet::Allergens, None).unwrap();
+    assert_eq!(response.count, 2);
+    assert_eq!(response.tags[0].id, "en:milk");
+    assert!(response.tags[0].known);
+    assert_eq!(response.tags[0].same_as.len(), 1);
 }
 
 #[test]
 fn categories() {
-    let client = off::v0().build().unwrap();
+    let client = off::v0()
+        .transport(common::fixture("categories"))
+        .build()
+        .unwrap();
     let response = client.categories(None).unwrap();
     assert_eq!(
         response.url().as_str(),
         "https://world.openfoodfacts.org/categories.json"
     );
-    assert_eq!(response.status().is_success(), true);
+    assert!(response.status().is_success());
 }
 
 #[test]
 fn categories_params() {
-    let client = off::v0().build().unwrap();
+    let client = off::v0()
+        .transport(common::fixture("categories_params"))
+        .build()
+        .unwrap();
     // Accepts only the locale parameter.
     let output = Output::new().locale(Locale::new("fr", None)).page(22);
     let response = client.categories(Some(output)).unwrap();
@@ -72,23 +115,29 @@ fn categories_params() {
         response.url().as_str(),
         "https://fr.openfoodfacts.org/categories.json"
     );
-    assert_eq!(response.status().is_success(), true);
+    assert!(response.status().is_success());
 }
 
 #[test]
 fn nutrients() {
-    let client = off::v0().build().unwrap();
+    let client = off::v0()
+        .transport(common::fixture("nutrients"))
+        .build()
+        .unwrap();
     let response = client.nutrients(None).unwrap();
     assert_eq!(
         response.url().as_str(),
         "https://world.openfoodfacts.org/cgi/nutrients.pl"
     );
-    assert_eq!(response.status().is_success(), true);
+    assert!(response.status().is_success());
 }
 
 #[test]
 fn nutrients_params() {
-    let client = off::v0().build().unwrap();
+    let client = off::v0()
+        .transport(common::fixture("nutrients_params"))
+        .build()
+        .unwrap();
     // Accepts only the locale parameter.
     let output = Output::new().locale(Locale::new("fr", None)).page(22);
     let response = client.nutrients(Some(output)).unwrap();
@@ -96,12 +145,15 @@ fn nutrients_params() {
         response.url().as_str(),
         "https://fr.openfoodfacts.org/cgi/nutrients.pl"
     );
-    assert_eq!(response.status().is_success(), true);
+    assert!(response.status().is_success());
 }
 
 #[test]
 fn products_by_facet() {
-    let client = off::v0().build().unwrap();
+    let client = off::v0()
+        .transport(common::fixture("products_by_facet"))
+        .build()
+        .unwrap();
     let response = client
         .products_by("additive", "e322-lecithins", None)
         .unwrap();
@@ -109,12 +161,15 @@ fn products_by_facet() {
         response.url().as_str(),
         "https://world.openfoodfacts.org/additive/e322-lecithins.json"
     );
-    assert_eq!(response.status().is_success(), true);
+    assert!(response.status().is_success());
 }
 
 #[test]
 fn products_by_facet_params() {
-    let client = off::v0().build().unwrap();
+    let client = off::v0()
+        .transport(common::fixture("products_by_facet_params"))
+        .build()
+        .unwrap();
     let output = Output::new()
         .locale(Locale::new("fr", None))
         .pagination(22, 20)
@@ -126,23 +181,29 @@ fn products_by_facet_params() {
         response.url().as_str(),
         "https://fr.openfoodfacts.org/additif/e322-lecithines.json?page=22&page_size=20&fields=url"
     );
-    assert_eq!(response.status().is_success(), true);
+    assert!(response.status().is_success());
 }
 
 #[test]
 fn products_by_category() {
-    let client = off::v0().build().unwrap();
+    let client = off::v0()
+        .transport(common::fixture("products_by_category"))
+        .build()
+        .unwrap();
     let response = client.products_by("category", "cheeses", None).unwrap();
     assert_eq!(
         response.url().as_str(),
         "https://world.openfoodfacts.org/category/cheeses.json"
     );
-    assert_eq!(response.status().is_success(), true);
+    assert!(response.status().is_success());
 }
 
 #[test]
 fn products_by_category_params() {
-    let client = off::v0().build().unwrap();
+    let client = off::v0()
+        .transport(common::fixture("products_by_category_params"))
+        .build()
+        .unwrap();
     let output = Output::new()
         .locale(Locale::new("fr", None))
         .pagination(22, 20)
@@ -155,23 +216,53 @@ fn products_by_category_params() {
         response.url().as_str(),
         "https://fr.openfoodfacts.org/categorie/fromages.json?page=22&page_size=20&fields=url"
     );
-    assert_eq!(response.status().is_success(), true);
+    assert!(response.status().is_success());
+}
+
+#[test]
+fn products_by_response() {
+    #[derive(Debug, serde::Deserialize)]
+    struct Name {
+        code: String,
+        product_name: String,
+    }
+
+    let client = off::v0()
+        .transport(common::fixture("products_by_response"))
+        .build()
+        .unwrap();
+    let output = Output::new().pagination(1, 2).fields("code,product_name");
+    let response = client
+        .products_by_response::<Name>("category", "cheeses", Some(output))
+        .unwrap();
+    assert_eq!(response.page, 1);
+    assert_eq!(response.page_size, 2);
+    assert_eq!(response.products.len(), 2);
+    assert_eq!(response.products[0].code, "3073781115345");
+    assert_eq!(response.products[1].product_name, "Emmental râpé");
+    assert!(response.count > 2);
 }
 
 #[test]
 fn product() {
-    let client = off::v0().build().unwrap();
+    let client = off::v0()
+        .transport(common::fixture("product"))
+        .build()
+        .unwrap();
     let response = client.product("069000019832", None).unwrap(); // Diet Pepsi
     assert_eq!(
         response.url().as_str(),
         "https://world.openfoodfacts.org/api/v0/product/069000019832"
     );
-    assert_eq!(response.status().is_success(), true);
+    assert!(response.status().is_success());
 }
 
 #[test]
 fn product_params() {
-    let client = off::v0().build().unwrap();
+    let client = off::v0()
+        .transport(common::fixture("product_params"))
+        .build()
+        .unwrap();
     // Accepts only the locale and fields parameters.
     let output = Output::new()
         .locale(Locale::new("fr", None))
@@ -182,12 +273,79 @@ fn product_params() {
         response.url().as_str(),
         "https://fr.openfoodfacts.org/api/v0/product/069000019832?fields=url"
     );
-    assert_eq!(response.status().is_success(), true);
+    assert!(response.status().is_success());
 }
 
 #[test]
-fn search_v0() {
+fn product_response() {
+    let client = off::v2()
+        .transport(common::fixture("product_response"))
+        .build()
+        .unwrap();
+    let response = client.product_response("3017620422003", None).unwrap(); // Nutella
+    assert_eq!(response.status, 1);
+    let product = response.product.unwrap();
+    assert_eq!(product.code, "3017620422003");
+    assert_eq!(product.brands_tags, vec!["nutella", "ferrero"]);
+    assert_eq!(product.nova_group, Some(4));
+    let sugars = product.nutriments.get("sugars").unwrap();
+    assert_eq!(sugars.as_sold.per_100g, Some(56.3));
+    assert!(product.name("fr").unwrap().starts_with("Pâte à tartiner"));
+}
+
+#[test]
+fn product_images() {
+    let client = off::v2().flavor(off::Flavor::Beauty).build().unwrap();
+    let product: off::Product = serde_json::from_value(serde_json::json!({
+        "code": "3600523614516",
+        "lang": "fr",
+        "images": {
+            "1": {"uploaded_t": 1457680652},
+            "front_fr": {"imgid": "1", "rev": "3"}
+        }
+    }))
+    .unwrap();
+    let images = client.product_images(&product).unwrap();
+    assert_eq!(
+        images
+            .display_url(off::ImageField::Front, "en")
+            .unwrap()
+            .as_str(),
+        "https://images.openbeautyfacts.org/images/products/360/052/361/4516/front_fr.3.400.jpg"
+    );
+    assert!(images
+        .display_url(off::ImageField::Ingredients, "en")
+        .is_none());
+
+    let local = off::v2()
+        .url_template("http://127.0.0.1:8080/")
+        .build()
+        .unwrap();
+    assert_eq!(
+        local
+            .product_images(&product)
+            .unwrap()
+            .raw_url("1", off::ImageSize::Thumb)
+            .as_str(),
+        "http://127.0.0.1:8080/images/products/360/052/361/4516/1.100.jpg"
+    );
+    let error = client.product_images(&off::Product::default()).unwrap_err();
+    assert!(matches!(error, off::Error::Validation(_)));
+}
+
+#[test]
+fn product_invalid_barcode() {
     let client = off::v0().build().unwrap();
+    let error = client.product("not-a-barcode", None).unwrap_err();
+    assert!(matches!(error, off::Error::Validation(_)));
+}
+
+#[test]
+fn search_v0() {
+    let client = off::v0()
+        .transport(common::fixture("search_v0"))
+        .build()
+        .unwrap();
     let query = client
         .query()
         .criteria("brands", "contains", "Nestlé")
@@ -199,12 +357,15 @@ fn search_v0() {
 
     let response = client.search(query, None).unwrap();
     assert_eq!(response.url().path(), "/cgi/search.pl");
-    assert_eq!(response.status().is_success(), true);
+    assert!(response.status().is_success());
 }
 
 #[test]
 fn search_v2() {
-    let client = off::v2().build().unwrap();
+    let client = off::v2()
+        .transport(common::fixture("search_v2"))
+        .build()
+        .unwrap();
     let query = client
         .query()
         .criteria("brands", "Nestlé", Some("fr"))
@@ -214,5 +375,16 @@ fn search_v2() {
 
     let response = client.search(query, None).unwrap();
     assert_eq!(response.url().path(), "/api/v2/search");
-    assert_eq!(response.status().is_success(), true);
+    assert!(response.status().is_success());
+}
+
+#[test]
+fn find_flavor() {
+    let client = off::v0()
+        .flavor(off::Flavor::Beauty)
+        .transport(common::fixture("find_flavor"))
+        .build()
+        .unwrap();
+    let flavor = client.find_flavor("069000019832").unwrap(); // Diet Pepsi
+    assert_eq!(flavor, Some(off::Flavor::Food));
 }
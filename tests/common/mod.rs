@@ -0,0 +1,156 @@
+// A local stand-in for the OFF server, answering requests with a handler
+// function, and the fixtures replayed by the tests of the read API. Shared by
+// the integration tests.
+#![allow(dead_code)]
+use openfoodfacts::{ReplayTransport, Transport};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// A request received by the stand-in server.
+#[derive(Debug, Clone)]
+pub struct Received {
+    pub method: String,
+    // Path and query.
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Received {
+    /// Returns the value of the given header, if any.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the body as a string.
+    pub fn body_str(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// A reply of the stand-in server.
+#[derive(Debug, Clone)]
+pub struct Reply {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Reply {
+    /// A reply with the given status and JSON body.
+    pub fn json(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            headers: vec![(
+                String::from("Content-Type"),
+                String::from("application/json"),
+            )],
+            body: body.to_string(),
+        }
+    }
+
+    /// Adds a header to the reply.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// The stand-in server. Stops when dropped.
+pub struct StandIn {
+    server: Arc<tiny_http::Server>,
+    received: Arc<Mutex<Vec<Received>>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl StandIn {
+    /// Starts a server on a free local port, answering each request with the
+    /// reply returned by `handler`.
+    pub fn start<F>(mut handler: F) -> Self
+    where
+        F: FnMut(&Received) -> Reply + Send + 'static,
+    {
+        let server = Arc::new(tiny_http::Server::http("127.0.0.1:0").unwrap());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let handle = {
+            let server = Arc::clone(&server);
+            let received = Arc::clone(&received);
+            std::thread::spawn(move || {
+                for mut request in server.incoming_requests() {
+                    let mut body = Vec::new();
+                    request.as_reader().read_to_end(&mut body).unwrap();
+                    let req = Received {
+                        method: request.method().to_string(),
+                        url: request.url().to_string(),
+                        headers: request
+                            .headers()
+                            .iter()
+                            .map(|h| (h.field.to_string(), h.value.to_string()))
+                            .collect(),
+                        body,
+                    };
+                    let reply = handler(&req);
+                    received.lock().unwrap().push(req);
+                    let mut response =
+                        tiny_http::Response::from_string(reply.body).with_status_code(reply.status);
+                    for (name, value) in reply.headers {
+                        response.add_header(
+                            tiny_http::Header::from_bytes(name.as_bytes(), value.as_bytes())
+                                .unwrap(),
+                        );
+                    }
+                    let _ = request.respond(response);
+                }
+            })
+        };
+        Self {
+            server,
+            received,
+            handle: Some(handle),
+        }
+    }
+
+    /// Returns the port of the server.
+    pub fn port(&self) -> u16 {
+        self.server.server_addr().to_ip().unwrap().port()
+    }
+
+    /// Returns the base URL of the server, usable as a client URL template.
+    pub fn url(&self) -> String {
+        format!("http://127.0.0.1:{}/", self.port())
+    }
+
+    /// Returns the requests received so far.
+    pub fn received(&self) -> Vec<Received> {
+        self.received.lock().unwrap().clone()
+    }
+}
+
+impl Drop for StandIn {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Returns a transport replaying the fixture `tests/fixtures/{name}.json`.
+///
+/// The fixtures are synthetic: hand-written cassettes in the format of
+/// [openfoodfacts::Cassette], with trimmed payloads and only the
+/// `content-type` header. They check the requests sent by the client and the
+/// decoding of the responses, not the payloads of the real server.
+pub fn fixture(name: &str) -> Box<dyn Transport> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(format!("{}.json", name));
+    match ReplayTransport::load(&path) {
+        Ok(transport) => Box::new(transport),
+        Err(e) => panic!("cannot load fixture {}: {}", path.display(), e),
+    }
+}
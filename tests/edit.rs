@@ -0,0 +1,273 @@
+// Write API tests against a local stand-in server.
+mod common;
+
+use common::{Reply, StandIn};
+use openfoodfacts::{
+    self as off, ImageCrop, ImageField, ImageUpload, NutritionDataPer, ProductEdit,
+};
+
+// A server saving the edits of products with a valid barcode.
+fn server() -> StandIn {
+    StandIn::start(|req| {
+        if req.body_str().starts_with("code=3850102123681&") {
+            Reply::json(200, r#"{"status": 1, "status_verbose": "fields saved"}"#)
+        } else {
+            Reply::json(
+                200,
+                r#"{"status": 0, "status_verbose": "no code or invalid code"}"#,
+            )
+        }
+    })
+}
+
+fn edit() -> ProductEdit {
+    ProductEdit::new("3850102123681")
+        .name("hr", "Čokolada")
+        .brands("Kraš")
+        .nutrition_data_per(NutritionDataPer::Per100g)
+        .nutriment("fat", 31.5, "g")
+        .comment("From the label")
+        .app("off-test", "1.0")
+}
+
+#[test]
+fn edit_product() {
+    let server = server();
+    let client = off::v2()
+        .url_template(&server.url())
+        .auth("user", "p&ss")
+        .build()
+        .unwrap();
+    let response = client.edit_product_response(&edit()).unwrap();
+    assert!(response.is_saved());
+    assert_eq!(response.status_verbose, "fields saved");
+
+    let received = &server.received()[0];
+    assert_eq!(received.method, "POST");
+    assert_eq!(received.url, "/cgi/product_jqm2.pl?cc=world");
+    assert_eq!(
+        received.header("Content-Type"),
+        Some("application/x-www-form-urlencoded")
+    );
+    assert_eq!(
+        received.body_str(),
+        "code=3850102123681&product_name_hr=%C4%8Cokolada&brands=Kra%C5%A1\
+         &nutrition_data_per=100g&nutriment_fat=31.5&nutriment_fat_unit=g\
+         &comment=From+the+label&app_name=off-test&app_version=1.0\
+         &user_id=user&password=p%26ss"
+    );
+}
+
+#[test]
+fn edit_not_saved() {
+    let server = server();
+    let client = off::v2().url_template(&server.url()).build().unwrap();
+    let edit = ProductEdit::new("1").quantity("1 kg");
+    let response = client.edit_product_response(&edit).unwrap();
+    assert!(!response.is_saved());
+    assert_eq!(server.received()[0].body_str(), "code=1&quantity=1+kg");
+}
+
+#[test]
+fn edit_invalid_barcode() {
+    let client = off::v2().build().unwrap();
+    let error = client
+        .edit_product(&ProductEdit::new("38501021236a1"))
+        .unwrap_err();
+    assert!(matches!(error, off::Error::Validation(_)));
+}
+
+// A server answering the image requests. The uploads after the first one are
+// duplicates.
+fn image_server() -> StandIn {
+    let mut uploads = 0;
+    StandIn::start(move |req| {
+        if req.url.starts_with("/cgi/product_image_upload.pl") {
+            uploads += 1;
+            if uploads > 1 {
+                return Reply::json(
+                    200,
+                    r#"{"status": "status not ok", "imagefield": "front_fr",
+                        "error": "This picture has already been sent.",
+                        "debug": "imgid_already_exists", "image": {"imgid": 1}}"#,
+                );
+            }
+            Reply::json(
+                200,
+                r#"{"status": "status ok", "imagefield": "front_fr",
+                    "image": {"imgid": 1, "thumb_url": "1.100.jpg"}}"#,
+            )
+        } else if req.url.starts_with("/cgi/product_image_crop.pl") {
+            Reply::json(
+                200,
+                r#"{"status": "status ok", "imagefield": "front_fr",
+                    "image": {"display_url": "front_fr.5.400.jpg"}}"#,
+            )
+        } else {
+            Reply::json(
+                200,
+                r#"{"status_code": 0, "status": "status ok", "imagefield": "front_fr"}"#,
+            )
+        }
+    })
+}
+
+#[test]
+fn upload_image() {
+    let server = image_server();
+    let client = off::v2()
+        .url_template(&server.url())
+        .auth("user", "pwd")
+        .build()
+        .unwrap();
+    let upload = ImageUpload::new(
+        "3850102123681",
+        &ImageField::Front.key("fr"),
+        b"\xff\xd8 image".to_vec(),
+    )
+    .file_name("front.jpg");
+    let response = client.upload_image_response(&upload).unwrap();
+    assert!(response.is_ok());
+    assert_eq!(response.imgid(), Some(1));
+
+    let received = &server.received()[0];
+    assert_eq!(received.url, "/cgi/product_image_upload.pl?cc=world");
+    let content_type = received.header("Content-Type").unwrap();
+    let boundary = content_type
+        .strip_prefix("multipart/form-data; boundary=")
+        .unwrap();
+    let body = received.body_str();
+    assert!(body.starts_with(&format!("--{}\r\n", boundary)));
+    assert!(body.ends_with(&format!("\r\n--{}--\r\n", boundary)));
+    assert!(body.contains("name=\"code\"\r\n\r\n3850102123681\r\n"));
+    assert!(body.contains("name=\"imagefield\"\r\n\r\nfront_fr\r\n"));
+    assert!(body.contains("name=\"user_id\"\r\n\r\nuser\r\n"));
+    assert!(body.contains(
+        "name=\"imgupload_front_fr\"; filename=\"front.jpg\"\r\nContent-Type: image/jpeg\r\n\r\n"
+    ));
+
+    // The same image again.
+    let response = client.upload_image_response(&upload).unwrap();
+    assert!(!response.is_ok());
+    assert!(response.is_duplicate());
+    assert_eq!(response.imgid(), Some(1));
+}
+
+#[test]
+fn upload_image_file() {
+    let server = image_server();
+    let client = off::v2().url_template(&server.url()).build().unwrap();
+    let path = std::env::temp_dir().join(format!("off-upload-{}.png", std::process::id()));
+    std::fs::write(&path, b"\x89PNG").unwrap();
+    let upload = ImageUpload::from_file("3850102123681", "other", &path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(client.upload_image_response(&upload).unwrap().is_ok());
+    let body = server.received()[0].body.clone();
+    assert!(String::from_utf8_lossy(&body).contains("Content-Type: image/png"));
+    assert!(body.windows(4).any(|w| w == b"\x89PNG"));
+}
+
+#[test]
+fn crop_and_unselect_image() {
+    let server = image_server();
+    let client = off::v2().url_template(&server.url()).build().unwrap();
+    let crop = ImageCrop::new("3850102123681", "1", "front_fr")
+        .crop(10, 20, 300, 400)
+        .angle(90)
+        .normalize(true);
+    let response = client.crop_image_response(&crop).unwrap();
+    assert!(response.is_ok());
+    assert_eq!(
+        response.image.unwrap().display_url.as_deref(),
+        Some("front_fr.5.400.jpg")
+    );
+    let response = client
+        .unselect_image_response("3850102123681", "front_fr")
+        .unwrap();
+    assert!(response.is_ok());
+
+    let received = server.received();
+    assert_eq!(received[0].url, "/cgi/product_image_crop.pl?cc=world");
+    assert_eq!(
+        received[0].body_str(),
+        "code=3850102123681&imgid=1&id=front_fr&x1=10&y1=20&x2=300&y2=400\
+         &coordinates_image_size=full&angle=90&normalize=true&white_magic=false"
+    );
+    assert_eq!(received[1].url, "/cgi/product_image_unselect.pl?cc=world");
+    assert_eq!(received[1].body_str(), "code=3850102123681&id=front_fr");
+    assert!(matches!(
+        client.unselect_image("front", "front_fr"),
+        Err(off::Error::Validation(_))
+    ));
+}
+
+#[test]
+fn dry_run() {
+    let server = image_server();
+    let client = off::v2()
+        .url_template(&server.url())
+        .auth("user", "secret")
+        .dry_run(true)
+        .build()
+        .unwrap();
+    let request = match client.edit_product(&edit()) {
+        Err(off::Error::DryRun(request)) => request,
+        r => panic!("unexpected result {:?}", r),
+    };
+    let description = request.to_string();
+    assert!(description.starts_with(&format!(
+        "POST {}cgi/product_jqm2.pl?cc=world\n",
+        server.url()
+    )));
+    assert!(description.contains("authorization: <redacted>\n"));
+    assert!(description.contains("+ product_name_hr: Čokolada\n+ brands: Kraš\n"));
+    assert!(description.ends_with("+ user_id: user\n+ password: <redacted>\n"));
+    assert!(!description.contains("secret"));
+
+    let upload = ImageUpload::new("3850102123681", "front_fr", vec![1, 2, 3]);
+    match client.upload_image(&upload) {
+        Err(off::Error::DryRun(request)) => assert_eq!(
+            request.field("imgupload_front_fr").unwrap().to_string(),
+            "<file front_fr.jpg, image/jpeg, 3 bytes>"
+        ),
+        r => panic!("unexpected result {:?}", r),
+    }
+    assert!(matches!(client.login(), Err(off::Error::DryRun(_))));
+    assert!(server.received().is_empty());
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn async_edit_product() {
+    let server = server();
+    let client = off::async_v2().url_template(&server.url()).build().unwrap();
+    let response = client.edit_product_response(&edit()).await.unwrap();
+    assert!(response.is_saved());
+    assert_eq!(server.received()[0].url, "/cgi/product_jqm2.pl?cc=world");
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn async_upload_image() {
+    let server = image_server();
+    let client = off::async_v2().url_template(&server.url()).build().unwrap();
+    let upload = ImageUpload::new("3850102123681", "front_fr", vec![1, 2, 3]);
+    let response = client.upload_image_response(&upload).await.unwrap();
+    assert_eq!(response.imgid(), Some(1));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn async_dry_run() {
+    let server = server();
+    let client = off::async_v2()
+        .url_template(&server.url())
+        .dry_run(true)
+        .build()
+        .unwrap();
+    let error = client.edit_product(&edit()).await.unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("dry run, request not sent:\nPOST "));
+    assert!(server.received().is_empty());
+}
@@ -0,0 +1,102 @@
+// HTTP options tests against a local stand-in server.
+mod common;
+
+use common::{Reply, StandIn};
+use openfoodfacts as off;
+use std::time::Duration;
+
+const PRODUCT: &str = r#"{"status": 1, "code": "3850102123681"}"#;
+
+#[test]
+fn timeout() {
+    let server = StandIn::start(|_| {
+        std::thread::sleep(Duration::from_millis(500));
+        Reply::json(200, PRODUCT)
+    });
+    let client = off::v2()
+        .url_template(&server.url())
+        .timeout(Duration::from_millis(100))
+        .build()
+        .unwrap();
+    match client.product("3850102123681", None) {
+        Err(off::Error::Transport(e)) => assert!(e.is_timeout()),
+        r => panic!("unexpected result {:?}", r),
+    }
+}
+
+#[test]
+fn proxy() {
+    let proxy = StandIn::start(|_| Reply::json(200, PRODUCT));
+    let client = off::v2()
+        .scheme("http")
+        .proxy(&proxy.url())
+        .build()
+        .unwrap();
+    assert!(client.product("3850102123681", None).is_ok());
+    let received = proxy.received();
+    assert_eq!(received.len(), 1);
+    assert_eq!(
+        received[0].url,
+        "http://world.openfoodfacts.org/api/v2/product/3850102123681"
+    );
+}
+
+#[test]
+fn no_proxy() {
+    let server = StandIn::start(|_| Reply::json(200, PRODUCT));
+    let client = off::v2()
+        .url_template(&server.url())
+        .proxy("http://192.0.2.1:3128")
+        .no_proxy("127.0.0.1")
+        .connect_timeout(Duration::from_millis(500))
+        .build()
+        .unwrap();
+    assert!(client.product("3850102123681", None).is_ok());
+    assert_eq!(server.received().len(), 1);
+}
+
+#[test]
+fn invalid_proxy() {
+    let result = off::v2().proxy("http://[invalid").build();
+    assert!(matches!(result, Err(off::Error::Transport(_))));
+}
+
+#[test]
+fn http_client() {
+    let server = StandIn::start(|_| Reply::json(200, PRODUCT));
+    let mut headers = off::HeaderMap::new();
+    headers.insert("X-Custom", "custom".parse().unwrap());
+    let http_client = off::HttpClient::builder()
+        .default_headers(headers)
+        .build()
+        .unwrap();
+    let client = off::v2()
+        .url_template(&server.url())
+        .user_agent("agent")
+        .http_client(http_client)
+        .build()
+        .unwrap();
+    assert!(client.product("3850102123681", None).is_ok());
+    let received = server.received();
+    assert_eq!(received[0].header("X-Custom"), Some("custom"));
+    // The client's default headers are sent whatever the transport.
+    assert_eq!(received[0].header("User-Agent"), Some("agent"));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn async_timeout() {
+    let server = StandIn::start(|_| {
+        std::thread::sleep(Duration::from_millis(500));
+        Reply::json(200, PRODUCT)
+    });
+    let client = off::async_v2()
+        .url_template(&server.url())
+        .timeout(Duration::from_millis(100))
+        .build()
+        .unwrap();
+    match client.product("3850102123681", None).await {
+        Err(off::Error::Transport(e)) => assert!(e.is_timeout()),
+        r => panic!("unexpected result {:?}", r),
+    }
+}
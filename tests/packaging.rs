@@ -0,0 +1,114 @@
+// Packaging components tests against a local stand-in server.
+mod common;
+
+use common::{Reply, StandIn};
+use openfoodfacts::{
+    self as off, PackagingComponent, PackagingValidator, ProductResponseV3, ProductUpdate,
+    PACKAGING_TAXONOMIES,
+};
+use serde_json::{json, Value};
+
+const PRODUCT: &str = r#"{
+    "code": "3850102123681",
+    "status": "success",
+    "product": {
+        "code": "3850102123681",
+        "packagings": [{
+            "number_of_units": 1,
+            "shape": {"id": "en:box", "lc_name": "Box"},
+            "material": {"id": "en:cardboard", "lc_name": "Cardboard"},
+            "recycling": {"id": "en:recycle", "lc_name": "Recycle"},
+            "weight_measured": 12.5
+        }]
+    }
+}"#;
+
+// A server serving a product, the packaging taxonomies and updates.
+fn server() -> StandIn {
+    StandIn::start(|req| match (req.method.as_str(), req.url.as_str()) {
+        ("GET", url) if url.starts_with("/api/v3/product/") => Reply::json(200, PRODUCT),
+        ("GET", url) if url.starts_with("/data/taxonomies/packaging_shapes.json") => Reply::json(
+            200,
+            r#"{"en:box": {"name": {"en": "Box", "fr": "Boîte"}},
+                "en:bottle": {"name": {"en": "Bottle", "fr": "Bouteille"}}}"#,
+        ),
+        ("GET", url) if url.starts_with("/data/taxonomies/packaging_materials.json") => {
+            Reply::json(
+                200,
+                r#"{"en:cardboard": {"name": {"en": "Cardboard", "fr": "Carton"}},
+                    "en:glass": {"name": {"en": "Glass", "fr": "Verre"}}}"#,
+            )
+        }
+        ("GET", url) if url.starts_with("/data/taxonomies/packaging_recycling.json") => {
+            Reply::json(
+                200,
+                r#"{"en:recycle": {"name": {"en": "Recycle", "fr": "À recycler"}}}"#,
+            )
+        }
+        ("PATCH", _) => Reply::json(200, PRODUCT),
+        _ => Reply::json(404, r#"{"status": "failure"}"#),
+    })
+}
+
+#[test]
+fn packagings() {
+    let server = server();
+    let client = off::v3()
+        .url_template(&server.url())
+        .auth("user", "pwd")
+        .build()
+        .unwrap();
+    let response: ProductResponseV3 = client.product_response("3850102123681", None).unwrap();
+    let packagings = response.product.unwrap().packagings().unwrap();
+    assert_eq!(
+        packagings,
+        vec![PackagingComponent::new()
+            .number_of_units(1)
+            .shape("en:box")
+            .material("en:cardboard")
+            .recycling("en:recycle")
+            .weight_measured(12.5)]
+    );
+
+    let taxonomies: Vec<off::Taxonomy> = PACKAGING_TAXONOMIES
+        .iter()
+        .map(|name| client.taxonomy_response(name).unwrap())
+        .collect();
+    let validator = PackagingValidator::new(&taxonomies[0], &taxonomies[1], &taxonomies[2]);
+    let mut components = packagings;
+    components.push(
+        PackagingComponent::new()
+            .number_of_units(2)
+            .shape("Bouteilles")
+            .material("verre")
+            .quantity_per_unit("75 cl"),
+    );
+    assert!(matches!(
+        validator.validate_all(&components, "fr"),
+        Err(off::Error::Validation(message)) if message == "component 1: unknown shape 'Bouteilles'"
+    ));
+    components[1].shape = Some(String::from("Bouteille"));
+    let components = validator.validate_all(&components, "fr").unwrap();
+
+    let update = ProductUpdate::new("3850102123681").packaging_components(&components);
+    client.update_product(&update).unwrap();
+    let body: Value = serde_json::from_slice(&server.received().last().unwrap().body).unwrap();
+    assert_eq!(
+        body["product"]["packagings"],
+        json!([
+            {
+                "number_of_units": 1,
+                "shape": {"id": "en:box"},
+                "material": {"id": "en:cardboard"},
+                "recycling": {"id": "en:recycle"},
+                "weight_measured": 12.5
+            },
+            {
+                "number_of_units": 2,
+                "shape": {"id": "en:bottle"},
+                "material": {"id": "en:glass"},
+                "quantity_per_unit": "75 cl"
+            }
+        ])
+    );
+}
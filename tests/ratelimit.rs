@@ -0,0 +1,74 @@
+// Client-side rate limit tests against a local stand-in server.
+mod common;
+
+use common::{Reply, StandIn};
+use openfoodfacts::{self as off, EndpointClass, Quota, RateLimits};
+use std::time::{Duration, Instant};
+
+const PRODUCT: &str = r#"{"status": 1, "code": "3850102123681"}"#;
+
+fn server() -> StandIn {
+    StandIn::start(|_| Reply::json(200, PRODUCT))
+}
+
+// One product request per 200ms.
+fn limits() -> RateLimits {
+    RateLimits::new().quota(
+        EndpointClass::Product,
+        Quota::new(1, Duration::from_millis(200)),
+    )
+}
+
+#[test]
+fn waits_for_token() {
+    let server = server();
+    let client = off::v2()
+        .url_template(&server.url())
+        .rate_limits(limits())
+        .build()
+        .unwrap();
+    let start = Instant::now();
+    for _ in 0..3 {
+        assert!(client.product("3850102123681", None).is_ok());
+    }
+    assert!(start.elapsed() >= Duration::from_millis(400));
+    assert_eq!(server.received().len(), 3);
+}
+
+#[test]
+fn fails_fast() {
+    let server = server();
+    let client = off::v2()
+        .url_template(&server.url())
+        .rate_limits(limits().fail_fast(true))
+        .build()
+        .unwrap();
+    assert!(client.product("3850102123681", None).is_ok());
+    match client.product("3850102123681", None) {
+        Err(off::Error::QuotaExceeded { class, retry_after }) => {
+            assert_eq!(class, EndpointClass::Product);
+            assert!(retry_after <= Duration::from_millis(200));
+        }
+        r => panic!("unexpected result {:?}", r),
+    }
+    // Other endpoint classes have their own bucket.
+    assert!(client.nutrients(None).is_ok());
+    assert_eq!(server.received().len(), 2);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn async_waits_for_token() {
+    let server = server();
+    let client = off::async_v2()
+        .url_template(&server.url())
+        .rate_limits(limits())
+        .build()
+        .unwrap();
+    let start = Instant::now();
+    for _ in 0..2 {
+        assert!(client.product("3850102123681", None).await.is_ok());
+    }
+    assert!(start.elapsed() >= Duration::from_millis(200));
+    assert_eq!(server.received().len(), 2);
+}
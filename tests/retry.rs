@@ -0,0 +1,150 @@
+// Retry policy tests against a local stand-in server injecting failures.
+mod common;
+
+use common::{Reply, StandIn};
+use openfoodfacts::{self as off, RetryPolicy};
+use std::time::{Duration, Instant};
+
+const PRODUCT: &str = r#"{"status": 1, "code": "3850102123681"}"#;
+
+fn policy() -> RetryPolicy {
+    RetryPolicy::new()
+        .base_delay(Duration::from_millis(10))
+        .jitter(0.0)
+}
+
+// A server failing with the given status the first `failures` times.
+fn failing(failures: usize, status: u16) -> StandIn {
+    let mut count = 0;
+    StandIn::start(move |_| {
+        count += 1;
+        if count <= failures {
+            Reply::json(status, "{}")
+        } else {
+            Reply::json(200, PRODUCT)
+        }
+    })
+}
+
+#[test]
+fn retries_server_errors() {
+    let server = failing(2, 503);
+    let client = off::v2()
+        .url_template(&server.url())
+        .retry(policy())
+        .build()
+        .unwrap();
+    let response = client.product("3850102123681", None).unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(server.received().len(), 3);
+}
+
+#[test]
+fn gives_up_after_max_attempts() {
+    let server = failing(5, 502);
+    let client = off::v2()
+        .url_template(&server.url())
+        .retry(policy().max_attempts(2))
+        .build()
+        .unwrap();
+    let error = client.product("3850102123681", None).unwrap_err();
+    assert_eq!(error.status(), Some(502));
+    assert_eq!(server.received().len(), 2);
+}
+
+#[test]
+fn no_retry_by_default() {
+    let server = failing(1, 503);
+    let client = off::v2().url_template(&server.url()).build().unwrap();
+    assert!(client.product("3850102123681", None).is_err());
+    assert_eq!(server.received().len(), 1);
+}
+
+#[test]
+fn no_retry_on_other_statuses() {
+    let server = failing(1, 500);
+    let client = off::v2()
+        .url_template(&server.url())
+        .retry(policy())
+        .build()
+        .unwrap();
+    let error = client.product("3850102123681", None).unwrap_err();
+    assert_eq!(error.status(), Some(500));
+    assert_eq!(server.received().len(), 1);
+}
+
+#[test]
+fn honors_retry_after() {
+    let mut count = 0;
+    let server = StandIn::start(move |_| {
+        count += 1;
+        if count == 1 {
+            Reply::json(429, "{}").header("Retry-After", "1")
+        } else {
+            Reply::json(200, PRODUCT)
+        }
+    });
+    let client = off::v2()
+        .url_template(&server.url())
+        .retry(policy())
+        .build()
+        .unwrap();
+    let start = Instant::now();
+    assert!(client.product("3850102123681", None).is_ok());
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    assert_eq!(server.received().len(), 2);
+}
+
+#[test]
+fn honors_retry_after_of_server_errors() {
+    let mut count = 0;
+    let server = StandIn::start(move |_| {
+        count += 1;
+        if count == 1 {
+            Reply::json(503, "{}").header("Retry-After", "1")
+        } else {
+            Reply::json(200, PRODUCT)
+        }
+    });
+    let client = off::v2()
+        .url_template(&server.url())
+        .retry(policy())
+        .build()
+        .unwrap();
+    let start = Instant::now();
+    assert!(client.product("3850102123681", None).is_ok());
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    assert_eq!(server.received().len(), 2);
+}
+
+#[test]
+fn rate_limited_error() {
+    let server = StandIn::start(|_| Reply::json(429, "{}").header("Retry-After", "120"));
+    let client = off::v2()
+        .url_template(&server.url())
+        .retry(policy().max_delay(Duration::from_secs(10)))
+        .build()
+        .unwrap();
+    // The server asks to wait longer than the maximum delay: no retry.
+    match client.product("3850102123681", None) {
+        Err(off::Error::RateLimited { retry_after }) => {
+            assert_eq!(retry_after, Some(Duration::from_secs(120)))
+        }
+        r => panic!("unexpected result {:?}", r),
+    }
+    assert_eq!(server.received().len(), 1);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn async_retries_server_errors() {
+    let server = failing(2, 503);
+    let client = off::async_v2()
+        .url_template(&server.url())
+        .retry(policy())
+        .build()
+        .unwrap();
+    let response = client.product("3850102123681", None).await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(server.received().len(), 3);
+}
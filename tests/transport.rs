@@ -0,0 +1,136 @@
+// Custom transport tests with an in-memory fake.
+use openfoodfacts::{
+    self as off, HttpRequest, HttpResponse, StatusCode, Transport, TransportError,
+    TransportErrorKind,
+};
+use std::sync::{Arc, Mutex};
+
+// A transport answering each request with the given status and body, and
+// recording the requests.
+#[derive(Debug, Clone)]
+struct Fake {
+    status: StatusCode,
+    body: &'static str,
+    sent: Arc<Mutex<Vec<HttpRequest>>>,
+}
+
+impl Fake {
+    fn new(status: u16, body: &'static str) -> Self {
+        Self {
+            status: StatusCode::from_u16(status).unwrap(),
+            body,
+            sent: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    fn sent(&self) -> Vec<HttpRequest> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+impl Transport for Fake {
+    fn send(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        let url = request.url.clone();
+        self.sent.lock().unwrap().push(request);
+        Ok(HttpResponse::new(
+            url,
+            self.status,
+            Default::default(),
+            self.body.as_bytes(),
+        ))
+    }
+}
+
+#[test]
+fn fake_transport() {
+    let fake = Fake::new(200, r#"{"status": 1, "code": "3850102123681"}"#);
+    let client = off::v2()
+        .auth("off", "off")
+        .user_agent("agent")
+        .transport(fake.clone())
+        .build()
+        .unwrap();
+    let response = client.product("3850102123681", None).unwrap();
+    let json: serde_json::Value = response.json().unwrap();
+    assert_eq!(json["code"], "3850102123681");
+
+    let sent = fake.sent();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].method, off::Method::GET);
+    assert_eq!(
+        sent[0].url.as_str(),
+        "https://world.openfoodfacts.org/api/v2/product/3850102123681"
+    );
+    assert_eq!(sent[0].headers["User-Agent"], "agent");
+    assert_eq!(sent[0].headers["Authorization"], "Basic b2ZmOm9mZg==");
+}
+
+#[test]
+fn fake_transport_status() {
+    let fake = Fake::new(404, "not found");
+    let client = off::v2().transport(fake).build().unwrap();
+    match client.product("3850102123681", None) {
+        Err(off::Error::ProductNotFound(barcode)) => assert_eq!(barcode, "3850102123681"),
+        r => panic!("unexpected result {:?}", r),
+    }
+}
+
+#[derive(Debug)]
+struct Unreachable;
+
+impl Transport for Unreachable {
+    fn send(&self, _request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        Err(TransportError::new(
+            TransportErrorKind::Connect,
+            "connection refused",
+        ))
+    }
+}
+
+#[test]
+fn transport_error() {
+    let client = off::v2().transport(Unreachable).build().unwrap();
+    match client.nutrients(None) {
+        Err(off::Error::Transport(e)) => assert!(e.is_connect()),
+        r => panic!("unexpected result {:?}", r),
+    }
+}
+
+#[cfg(feature = "async")]
+mod async_transport {
+    use super::*;
+    use off::{AsyncHttpResponse, AsyncTransport, BoxFuture};
+
+    impl AsyncTransport for Fake {
+        fn send(
+            &self,
+            request: HttpRequest,
+        ) -> BoxFuture<'_, Result<AsyncHttpResponse, TransportError>> {
+            let url = request.url.clone();
+            self.sent.lock().unwrap().push(request);
+            let chunks = self
+                .body
+                .as_bytes()
+                .chunks(4)
+                .map(|c| Ok(c.to_vec()))
+                .collect::<Vec<_>>();
+            let response = AsyncHttpResponse::new(
+                url,
+                self.status,
+                Default::default(),
+                futures_util::stream::iter(chunks),
+            );
+            Box::pin(async move { Ok(response) })
+        }
+    }
+
+    #[tokio::test]
+    async fn async_fake_transport() {
+        let fake = Fake::new(200, r#"{"status": 1, "code": "3850102123681"}"#);
+        let client = off::async_v2().transport(fake.clone()).build().unwrap();
+        let response = client.product("3850102123681", None).await.unwrap();
+        let json: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(json["code"], "3850102123681");
+        assert_eq!(fake.sent().len(), 1);
+    }
+}
@@ -0,0 +1,139 @@
+// API V3 tests against a local stand-in server.
+mod common;
+
+use common::{Reply, StandIn};
+use openfoodfacts::{self as off, Impact, ProductResponseV3, ProductUpdate, ResponseStatus};
+use serde_json::{json, Value};
+
+const FOUND: &str = r#"{
+    "code": "3850102123681",
+    "status": "success",
+    "result": {"id": "product_found", "name": "Product found"},
+    "product": {"code": "3850102123681", "product_name": "Čokolada"}
+}"#;
+
+const INVALID_CODE: &str = r#"{
+    "code": "0",
+    "status": "failure",
+    "result": {"id": "product_not_found"},
+    "errors": [{
+        "message": {"id": "invalid_code"},
+        "field": {"id": "code", "value": "0"},
+        "impact": {"id": "failure"}
+    }]
+}"#;
+
+const UPDATED: &str = r#"{
+    "code": "3850102123681",
+    "status": "success_with_warnings",
+    "result": {"id": "product_updated"},
+    "warnings": [{
+        "message": {"id": "unknown_field"},
+        "field": {"id": "colour"},
+        "impact": {"id": "field_ignored"}
+    }],
+    "product": {"product_name_hr": "Čokolada"}
+}"#;
+
+fn server() -> StandIn {
+    StandIn::start(|req| match (req.method.as_str(), req.url.as_str()) {
+        ("GET", url) if url.starts_with("/api/v3/product/3850102123681") => Reply::json(200, FOUND),
+        ("GET", url) if url.starts_with("/api/v3/product/0") => Reply::json(400, INVALID_CODE),
+        ("PATCH", _) => Reply::json(200, UPDATED),
+        ("GET", url) if url.starts_with("/data/taxonomies/") => Reply::json(200, "{}"),
+        _ => Reply::json(404, r#"{"status": "failure"}"#),
+    })
+}
+
+#[test]
+fn product() {
+    let server = server();
+    let client = off::v3().url_template(&server.url()).build().unwrap();
+    let response: ProductResponseV3 = client.product_response("3850102123681", None).unwrap();
+    assert!(response.is_success());
+    assert_eq!(response.result.unwrap().id, "product_found");
+    assert_eq!(
+        response.product.unwrap().product_name.as_deref(),
+        Some("Čokolada")
+    );
+    assert!(server.received()[0]
+        .url
+        .starts_with("/api/v3/product/3850102123681"));
+}
+
+#[test]
+fn product_errors() {
+    let server = server();
+    let client = off::v3().url_template(&server.url()).build().unwrap();
+    // The errors are returned in the envelope.
+    let response: ProductResponseV3<Value> = client.product_response("0", None).unwrap();
+    assert_eq!(response.status, ResponseStatus::Failure);
+    assert_eq!(response.errors[0].message.id, "invalid_code");
+    assert_eq!(response.errors[0].impact(), Impact::Failure);
+    // Except for unknown products.
+    let error = client
+        .product_response::<Value>("3017620422003", None)
+        .unwrap_err();
+    assert!(matches!(error, off::Error::ProductNotFound(code) if code == "3017620422003"));
+}
+
+#[test]
+fn update_product() {
+    let server = server();
+    let client = off::v3()
+        .url_template(&server.url())
+        .auth("user", "pwd")
+        .build()
+        .unwrap();
+    let update = ProductUpdate::new("3850102123681")
+        .name("hr", "Čokolada")
+        .set("colour", "brown")
+        .tags_lc("hr")
+        .fields("updated");
+    let response: ProductResponseV3 = client.update_product_response(&update).unwrap();
+    assert_eq!(response.status, ResponseStatus::SuccessWithWarnings);
+    assert!(response.is_success());
+    assert_eq!(response.warnings[0].impact(), Impact::FieldIgnored);
+
+    let received = &server.received()[0];
+    assert_eq!(received.method, "PATCH");
+    assert_eq!(received.url, "/api/v3/product/3850102123681?cc=world");
+    assert_eq!(received.header("Content-Type"), Some("application/json"));
+    let body: Value = serde_json::from_slice(&received.body).unwrap();
+    assert_eq!(
+        body,
+        json!({
+            "product": {"product_name_hr": "Čokolada", "colour": "brown"},
+            "tags_lc": "hr",
+            "fields": "updated",
+            "user_id": "user",
+            "password": "pwd"
+        })
+    );
+}
+
+#[test]
+fn shared_endpoints() {
+    let server = server();
+    let client = off::v3().url_template(&server.url()).build().unwrap();
+    assert!(client.taxonomy("categories").is_ok());
+    assert!(matches!(
+        client.update_product(&ProductUpdate::new("abc")),
+        Err(off::Error::Validation(_))
+    ));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn async_update_product() {
+    let server = server();
+    let client = off::async_v3().url_template(&server.url()).build().unwrap();
+    let response: ProductResponseV3 = client
+        .product_response("3850102123681", None)
+        .await
+        .unwrap();
+    assert!(response.is_success());
+    let update = ProductUpdate::new("3850102123681").name("hr", "Čokolada");
+    let response: ProductResponseV3 = client.update_product_response(&update).await.unwrap();
+    assert_eq!(response.warnings.len(), 1);
+}